@@ -1,23 +1,32 @@
 pub mod arc_analyzer{
     //! necessary functions for analyzing StructureBlock
+    use std::borrow::Borrow;
     use std::cmp::Ordering;
+    use std::collections::HashMap;
     use nalgebra::{Const, Dyn, VecStorage};
     use itertools::Itertools;
 
 
-    use crate::modules::periodic_table::PeriodicTable;
+    use crate::modules::periodic_table::ElementError;
     use crate::modules::structures::{ StructureBlock, Atom};
+    use crate::neighbor::neighbor_list::{BondCriterion, NeighborList};
     extern crate nalgebra as na;
     
     /**
-    find the minimum energy of a given vector of StrucutreBlock
+    find the minimum energy of the given StrucutreBlocks
     return some(f64) if minimum found
     or None if not found(empty vector or others)
+
+    the blocks can be a `&Vec<StructureBlock>` or a `StructureIterator`, so large files are handled in constant memory
      */
-    pub fn find_minimum_energy(blocks: &Vec<StructureBlock>) -> Option<f64> {
-        blocks.iter().fold(None, |min, b| match min {
-            None => Some(b.energy),
-            Some(min_energy) => Some(min_energy.min(b.energy)),
+    pub fn find_minimum_energy<I>(blocks: I) -> Option<f64>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<StructureBlock>,
+    {
+        blocks.into_iter().fold(None, |min, b| match min {
+            None => Some(b.borrow().energy),
+            Some(min_energy) => Some(min_energy.min(b.borrow().energy)),
         })
     }
 
@@ -25,34 +34,40 @@ pub mod arc_analyzer{
      count the number of blocks in the file
 
      returns:
-        + count:u64, the number of blocks in a `Vec<StructureBlock>`
+        + count:u64, the number of blocks
      */
-    pub fn count_strucutre_block(blocks: &Vec<StructureBlock>) -> u64{
-        blocks.len() as u64
+    pub fn count_strucutre_block<I>(blocks: I) -> u64
+    where
+        I: IntoIterator,
+        I::Item: Borrow<StructureBlock>,
+    {
+        blocks.into_iter().count() as u64
     }
 
     /**
     check if all of the blocks have the same atoms
 
     returns:
-        + `true` if all blocks have the same atoms; 
-        + `false` elsewise
+        + `Some(map)` of element counts if all blocks have the same atoms; 
+        + `None` elsewise
      */
-    pub fn check_atom_consistency(blocks: &Vec<StructureBlock>) -> Option<std::collections::HashMap<&String, i32>>{
-        let mut atom_map = std::collections::HashMap::new();
+    pub fn check_atom_consistency<I>(blocks: I) -> Option<HashMap<String, i32>>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<StructureBlock>,
+    {
+        let mut atom_map = HashMap::new();
 
         for block in blocks {
-            let mut local_map = std::collections::HashMap::new();
-            for atom in &block.atoms {
-                *local_map.entry(&atom.element).or_insert(0) += 1;
+            let mut local_map = HashMap::new();
+            for atom in &block.borrow().atoms {
+                *local_map.entry(atom.element.clone()).or_insert(0) += 1;
             }
 
             if atom_map.is_empty() {
                 atom_map = local_map;
-            } else {
-                if atom_map != local_map {
-                    return None;
-                }
+            } else if atom_map != local_map {
+                return None;
             }
         }
         Some(atom_map)
@@ -65,16 +80,20 @@ pub mod arc_analyzer{
         pub count: u64,
     }
     /**
-    list different energy in a `Vec<StructureBlock>`.
+    list different energy of the given blocks.
     threshold setted to be 0.001
      */
-    pub fn list_energy(blocks: &Vec<StructureBlock>) -> Vec<EnergyInfo>{
+    pub fn list_energy<I>(blocks: I) -> Vec<EnergyInfo>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<StructureBlock>,
+    {
         let mut energy_list: Vec<EnergyInfo> = Vec::new();
         let threshold = 0.001;
         for block in blocks{
             let mut min_diff = f64::MAX;
             let mut min_index:Option<usize> = None;
-            let current_energy = block.energy;
+            let current_energy = block.borrow().energy;
             for (i, info) in energy_list.iter_mut().enumerate(){
                 let diff = (info.energy - current_energy).abs();
                 if diff < threshold && diff < min_diff{
@@ -91,80 +110,87 @@ pub mod arc_analyzer{
     }
 
     /**
-    return the minimum structure block of the given `Vec<StructureBlock>`
+    return the minimum structure block of the given blocks
 
     args:
-        + `blocks:&Vec<StructureBlock>`
+        + `blocks`, a `&Vec<StructureBlock>` or a `StructureIterator`
 
     returns:
         + `Option<StructureBlock>`, `Some(StructureBlock)` if the minimum found; `None` if no minumum found.
+          Blocks with a NaN energy are skipped.
      */
-    pub fn extract_minimum(blocks: &Vec<StructureBlock>) -> Option<StructureBlock> {
-        blocks.iter().min_by(|a, b| a.energy.partial_cmp(&b.energy).unwrap()).cloned()
+    pub fn extract_minimum<I>(blocks: I) -> Option<StructureBlock>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<StructureBlock>,
+    {
+        // only the best block so far is kept, NaN would compare neither lower nor higher than it
+        blocks.into_iter().filter(|b| !b.borrow().energy.is_nan()).fold(None, |min: Option<StructureBlock>, b| match min {
+            Some(min) if min.energy <= b.borrow().energy => Some(min),
+            _ => Some(b.borrow().clone()),
+        })
     }
 
     /**
-    rearrange the atoms in a `Vec<StructureBlock>`
+    the largest force on an atom of the block, in eV/angstrom
+
+    returns `None` if the block has no forces
      */
-    pub fn rearrange_atoms<F>(block: &mut StructureBlock, compare: F)
-    where
-        F: Fn(&Atom, &Atom) -> Ordering,
-    {
-        block.atoms.sort_by(compare);
+    pub fn max_force(block: &StructureBlock) -> Option<f64> {
+        let forces = block.forces.as_ref()?;
+        forces.iter()
+            .map(|f| (f.0.powi(2) + f.1.powi(2) + f.2.powi(2)).sqrt())
+            .fold(None, |max: Option<f64>, f| Some(max.map_or(f, |m| m.max(f))))
     }
 
     /**
-     calculate distance between two atoms
+    the root mean square of the atomic forces of the block, in eV/angstrom
+
+    $F_{rms} = \sqrt{\frac{1}{N}\sum_i |F_i|^2}$, `None` if the block has no forces
+     */
+    pub fn rms_force(block: &StructureBlock) -> Option<f64> {
+        let forces = block.forces.as_ref().filter(|f| !f.is_empty())?;
+        let sum: f64 = forces.iter().map(|f| f.0.powi(2) + f.1.powi(2) + f.2.powi(2)).sum();
+        Some((sum / forces.len() as f64).sqrt())
+    }
 
-     $distance = \sqrt{(x_1-x_2)^2+(y_1-y_2)^2+(z_1-z_2)^2}$
+    /**
+    rearrange the atoms in a `Vec<StructureBlock>`
      */
-    fn distance(atom1: &Atom, atom2: &Atom) -> f64
+    pub fn rearrange_atoms<F>(block: &mut StructureBlock, compare: F)
+    where
+        F: Fn(&Atom, &Atom) -> Ordering,
     {
-        ((atom1.coordinate.0-atom2.coordinate.0).powi(2) + (atom1.coordinate.1-atom2.coordinate.1).powi(2) + (atom1.coordinate.2-atom2.coordinate.2).powi(2)).sqrt()
+        block.atoms.sort_by(compare);
     }
 
     /**
      calculate coordination number of atoms in the 
 
-     # TODO
-     + implement support on multiple elements
-     + implement changable threshold
-     + implement atom radious table for threshold calculation
+     atoms are bonded by `criterion`, `BondCriterion::default()` being the sum of the atom radii plus 0.3 angstrom.
+     periodic blocks count neighbours across the cell faces, clusters (`PBC=OFF`) do not.
+     an element missing from the periodic table, or from the radius set of `criterion`, is an error
      */
-    pub fn calc_coordination(block:&StructureBlock) -> Vec<u64>
+    pub fn calc_coordination(block:&StructureBlock, criterion: &BondCriterion) -> Result<Vec<u64>, ElementError>
     {
-        let periodic_table = PeriodicTable::new();
-        let mut coordination = vec![0; block.atoms.len()];
-        for i in 0..block.atoms.len(){
-            for j in i+1..block.atoms.len(){
-                let threshold = periodic_table.get(&block.atoms[i].element).unwrap().atom_radius + periodic_table.get(&block.atoms[j].element).unwrap().atom_radius + 0.3;
-                let distance = distance(&block.atoms[i], &block.atoms[j]);
-                if distance <= threshold{
-                    coordination[i] += 1;
-                    coordination[j] += 1;
-                }
-            }
-        }
-        coordination
+        Ok(NeighborList::new(block, criterion)?.coordination())
     }
 
     /**
      calculate the coordination matrix of a `StructureBlock`
+
+     in periodic blocks an entry counts the bonds to every image of the other atom, and the diagonal the bonds
+     of an atom to its own images. Atoms are bonded by `criterion`, as in `calc_coordination`
      */
-    pub fn calc_coordination_matrix(block:&StructureBlock) -> na::Matrix<u64, Dyn, Dyn, VecStorage<u64, Dyn, Dyn>> {
+    pub fn calc_coordination_matrix(block:&StructureBlock, criterion: &BondCriterion) -> Result<na::DMatrix<u64>, ElementError> {
         let mut matrix = na::Matrix::<u64, Dyn, Dyn, VecStorage<u64, Dyn, Dyn>>::zeros(block.atoms.len(), block.atoms.len());
-        let periodic_table = PeriodicTable::new();
-        for i in 0..block.atoms.len(){
-            for j in i+1..block.atoms.len(){
-                let threshold = periodic_table.get(&block.atoms[i].element).unwrap().atom_radius + periodic_table.get(&block.atoms[j].element).unwrap().atom_radius + 0.5;
-                let distance = distance(&block.atoms[i], &block.atoms[j]);
-                if distance <= threshold {
-                    matrix[(i, j)] += 1;
-                    matrix[(j, i)] += 1;
-                }
+        let neighbors = NeighborList::new(block, criterion)?;
+        for (i, neighbors) in neighbors.iter().enumerate() {
+            for neighbor in neighbors {
+                matrix[(i, neighbor.index)] += 1;
             }
         }
-        matrix 
+        Ok(matrix)
     }
 
     #[derive(Clone)]
@@ -194,13 +220,12 @@ pub mod arc_analyzer{
 
     fn calculate_b(plane:&Plane, atom:&Atom) -> Plane{
         let coordinate = &atom.coordinate;
-        let new_plane = Plane{
+        Plane{
             a: plane.a,
             b: plane.b,
             c: plane.c,
             d: -(plane.a * coordinate.0 + plane.b * coordinate.1  + plane.c * coordinate.2)
-        };
-        new_plane
+        }
     }
 
     fn calculate_distance_from_plane(plane:&Plane, point:&Atom) -> f64{
@@ -237,19 +262,19 @@ pub mod arc_analyzer{
         for atom in structure{
             let mut distances:Vec<f64> = Vec::new();
             for plane in &planes{
-                let distance = calculate_distance_from_plane(plane, &atom);
+                let distance = calculate_distance_from_plane(plane, atom);
                 distances.push(distance);
             }
             let min_result = distances.iter().min_by(|a, b| a.partial_cmp(b).unwrap());
             if let Some(min) = min_result{
                 if *min > in_plane_threshold{
-                    let new_plane = calculate_b(&plane, &atom);
+                    let new_plane = calculate_b(&plane, atom);
                     planes.push(new_plane);
                     continue;
                 }
             }
             else{
-            let new_plane = calculate_b(&plane, &atom);
+            let new_plane = calculate_b(&plane, atom);
             planes.push(new_plane);
             }
         }
@@ -337,8 +362,10 @@ pub mod arc_analyzer{
 #[cfg(test)]
 mod tests{
     use crate::analyzer::arc_analyzer;
-    use crate::modules::structures::{Atom, Coordinate, StructureBlock};
+    use crate::modules::structures::{Atom, Coordinate, CrystalInfo, StructureBlock};
+    use crate::neighbor::neighbor_list::BondCriterion;
     use nalgebra::{self as na, Const, Dyn, VecStorage};
+    use std::f64::consts::FRAC_1_SQRT_2;
 
     #[test]
     fn test_find_minimum_energy() {
@@ -359,7 +386,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 2,
@@ -376,7 +409,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 3,
@@ -393,7 +432,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
         ];
         assert!(arc_analyzer::find_minimum_energy(&blocks).unwrap() - 1.0 < 1e-6);
@@ -420,7 +465,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 2,
@@ -437,7 +488,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 3,
@@ -454,11 +511,17 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
         ];
         assert_eq!(arc_analyzer::count_strucutre_block(&blocks), 3);
-        assert_eq!(arc_analyzer::count_strucutre_block(&vec![]), 0);
+        assert_eq!(arc_analyzer::count_strucutre_block(Vec::<StructureBlock>::new()), 0);
     }
 
     #[test]
@@ -480,7 +543,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 2,
@@ -497,7 +566,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 3,
@@ -514,7 +589,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
         ];
         assert!(arc_analyzer::check_atom_consistency(&blocks).is_some());
@@ -535,7 +616,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 2,
@@ -552,7 +639,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 3,
@@ -569,7 +662,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "O".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
         ];
         assert!(arc_analyzer::check_atom_consistency(&blocks).is_none());
@@ -591,7 +690,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 2,
@@ -608,7 +713,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 3,
@@ -626,12 +737,20 @@ mod tests{
                     Atom {
                         element: "Fe".to_string(),
                         coordinate: Coordinate(5.0, 5.0, 5.0),
+                        record: None,
+                        selective: None
                     },
                     Atom {
                         element: "Fe".to_string(),
                         coordinate: Coordinate(5.0, 5.0, 5.0),
+                        record: None,
+                        selective: None
                     },
                 ],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
         ];
         assert!(arc_analyzer::check_atom_consistency(&blocks).is_none());
@@ -660,7 +779,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 2,
@@ -677,7 +802,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 3,
@@ -694,7 +825,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "O".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
         ];
         let energy_list = arc_analyzer::list_energy(&blocks);
@@ -726,7 +863,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 2,
@@ -743,7 +886,13 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
             StructureBlock {
                 number: 3,
@@ -760,11 +909,24 @@ mod tests{
                 atoms: vec![Atom {
                     element: "Fe".to_string(),
                     coordinate: Coordinate(5.0, 5.0, 5.0),
+                    record: None,
+                    selective: None
                 }],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             },
         ];
         let min_block = arc_analyzer::extract_minimum(&blocks).unwrap();
         assert_eq!(min_block.energy, 1.0);
+        // NaN energies are skipped wherever they are
+        let mut with_nan = blocks.clone();
+        with_nan[0].energy = f64::NAN;
+        with_nan.push(with_nan[0].clone());
+        with_nan.insert(0, with_nan[0].clone());
+        assert_eq!(arc_analyzer::extract_minimum(&with_nan).unwrap().energy, 2.0);
+        assert!(arc_analyzer::extract_minimum(&with_nan[..1]).is_none());
         // Test with an empty vector
         let empty_blocks: Vec<StructureBlock> = vec![];
         assert!(arc_analyzer::extract_minimum(&empty_blocks).is_none());
@@ -812,11 +974,78 @@ mod tests{
         // Test with identical but rotated structures
         let rotation = na::Matrix::<f64, Const<3>, Dyn, VecStorage<f64, Const<3>, Dyn>>::from_row_slice(&[
             1.0, 0.0, 0.0,
-            0.0, 0.7071, -0.7071,
-            0.0, 0.7071, 0.7071
+            0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2,
+            0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2
         ]);
         let structure2 = rotation * structure1.clone();
         let rmsd = arc_analyzer::calculate_rmsd_by_matrix(&structure1, &structure2);
         assert!(rmsd < 1e-3);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_force_statistics() {
+        let mut block = StructureBlock{
+            number: 0,
+            energy: 0.0,
+            symmetry: "C1".to_string(),
+            crystal: CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 },
+            atoms: Vec::new(),
+            periodic: false,
+            forces: None,
+            record: None,
+            stress: None
+        };
+        assert!(arc_analyzer::max_force(&block).is_none());
+        assert!(arc_analyzer::rms_force(&block).is_none());
+        block.forces = Some(vec![Coordinate(0.3, -0.4, 0.0), Coordinate(0.0, 0.0, 0.0)]);
+        assert_eq!(arc_analyzer::max_force(&block), Some(0.5));
+        assert!((arc_analyzer::rms_force(&block).unwrap() - 0.125f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_periodic_coordination() {
+        let criterion = BondCriterion::default();
+        let atom = |x, y, z| Atom{ element: "Fe".to_string(), coordinate: Coordinate(x, y, z), record: None, selective: None };
+        // two atoms 1 angstrom apart across the x face of the cell
+        let mut block = StructureBlock{
+            number: 0,
+            energy: 0.0,
+            symmetry: "C1".to_string(),
+            crystal: CrystalInfo{ x: 10.0, y: 10.0, z: 10.0, alpha: 90.0, beta: 90.0, gamma: 90.0 },
+            atoms: vec![atom(0.5, 5.0, 5.0), atom(9.5, 5.0, 5.0)],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
+        };
+        assert_eq!(arc_analyzer::calc_coordination(&block, &criterion).unwrap(), vec![1, 1]);
+        assert_eq!(arc_analyzer::calc_coordination_matrix(&block, &criterion).unwrap()[(0, 1)], 1);
+        // a cluster in the same box is left alone
+        block.periodic = false;
+        assert_eq!(arc_analyzer::calc_coordination(&block, &criterion).unwrap(), vec![0, 0]);
+
+        // a triclinic cell, the atoms are neighbours through the skewed b face
+        block.periodic = true;
+        block.crystal = CrystalInfo{ x: 8.0, y: 8.0, z: 8.0, alpha: 90.0, beta: 90.0, gamma: 60.0 };
+        let lattice = block.lattice().unwrap();
+        block.atoms = vec![atom(0.0, 0.0, 4.0), atom(0.0, 0.0, 4.0)];
+        block.atoms[0].set_fractional(&Coordinate(0.1, 0.05, 0.5), &lattice);
+        block.atoms[1].set_fractional(&Coordinate(0.1, 0.95, 0.5), &lattice);
+        assert_eq!(arc_analyzer::calc_coordination(&block, &criterion).unwrap(), vec![1, 1]);
+
+        // a cutoff beyond half the cell: a simple cubic cell of one atom has six neighbours, all its own images
+        block.crystal = CrystalInfo{ x: 2.5, y: 2.5, z: 2.5, alpha: 90.0, beta: 90.0, gamma: 90.0 };
+        block.atoms = vec![atom(0.0, 0.0, 0.0)];
+        assert_eq!(arc_analyzer::calc_coordination(&block, &criterion).unwrap(), vec![6]);
+        assert_eq!(arc_analyzer::calc_coordination_matrix(&block, &criterion).unwrap()[(0, 0)], 6);
+        // and a pair in a thin cell is bonded to two images of each other
+        block.crystal = CrystalInfo{ x: 3.0, y: 20.0, z: 20.0, alpha: 90.0, beta: 90.0, gamma: 90.0 };
+        block.atoms = vec![atom(0.0, 0.0, 0.0), atom(1.5, 0.0, 0.0)];
+        assert_eq!(arc_analyzer::calc_coordination(&block, &criterion).unwrap(), vec![2, 2]);
+        // labels in any case are bonded as their element, an unknown one is an error
+        block.atoms[0].element = "fe".to_string();
+        assert_eq!(arc_analyzer::calc_coordination(&block, &criterion).unwrap(), vec![2, 2]);
+        block.atoms[1].element = "X".to_string();
+        assert_eq!(arc_analyzer::calc_coordination(&block, &criterion).unwrap_err().to_string(), "unknown element `X`");
+    }
+}
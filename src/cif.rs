@@ -0,0 +1,527 @@
+pub mod cif_parser {
+    //! reader and writer of Crystallographic Information Files (CIF)
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::io::{self, Read, Write};
+    use nalgebra::{Matrix3, RowVector3, Vector3};
+
+    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::compression::compression::{open_reader, CompressedWriter};
+    use crate::parser::parser::{StructureIO, StructureIterator, ParseError, Location};
+
+    /// data item used to keep the energy of a block in a CIF
+    const ENERGY_TAG: &str = "_lasp_energy";
+    /// data item used to keep the symmetry label of a block in a CIF
+    const SYMMETRY_TAG: &str = "_lasp_symmetry";
+    /// tags holding the symmetry operators, old and new dictionary names
+    const SYMMETRY_OPERATION_TAGS: [&str; 2] = ["_symmetry_equiv_pos_as_xyz", "_space_group_symop_operation_xyz"];
+    /// atoms closer than this (in angstroms) after applying symmetry operators are the same atom
+    const DUPLICATE_TOLERANCE: f64 = 0.01;
+
+    /// a token of a CIF file, quoted tokens are never tags or keywords
+    #[derive(Clone, Default)]
+    struct Token {
+        text: String,
+        quoted: bool,
+        /// position of the token in the file, starting from 1
+        line: usize,
+        column: usize,
+    }
+    impl Token {
+        fn location(&self, path: Option<&Path>) -> Location {
+            Location::new(path, self.line, self.column)
+        }
+        /// parse the token as a CIF number
+        fn number(&self, path: Option<&Path>) -> Result<f64, ParseError> {
+            parse_number(&self.text).ok_or_else(|| ParseError::BadFloat{ location: self.location(path), text: self.text.clone() })
+        }
+        fn unexpected(&self, path: Option<&Path>) -> ParseError {
+            ParseError::UnexpectedLine{ location: self.location(path), line: self.text.clone() }
+        }
+    }
+
+    /// a `data_` block of a CIF file
+    #[derive(Default)]
+    struct DataBlock {
+        /// line of the `data_` keyword
+        line: usize,
+        items: HashMap<String, Token>,
+        loops: Vec<(Vec<String>, Vec<Token>)>,
+    }
+    impl DataBlock {
+        /// the values of a looped tag, or the single value of an unlooped one
+        fn column(&self, tag: &str) -> Option<Vec<&Token>> {
+            if let Some(value) = self.items.get(tag) {
+                return Some(vec![value]);
+            }
+            self.loops.iter().find_map(|(tags, values)| {
+                let index = tags.iter().position(|t| t == tag)?;
+                Some(values.iter().skip(index).step_by(tags.len()).collect())
+            })
+        }
+        /// a cell parameter, a missing one means the block has no cell
+        fn cell(&self, tag: &str, path: Option<&Path>) -> Result<f64, ParseError> {
+            match self.items.get(tag) {
+                Some(value) => value.number(path),
+                None => Err(ParseError::MissingCell{ location: Location::new(path, self.line, 0) }),
+            }
+        }
+    }
+
+    /// split a CIF file into tokens, dropping comments
+    fn tokenize(content: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut lines = content.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            // a semicolon at the start of a line opens a text field closed by the next one
+            if let Some(first) = line.strip_prefix(';') {
+                let mut text = vec![first.to_string()];
+                for (_, line) in lines.by_ref() {
+                    if line.starts_with(';') {
+                        break;
+                    }
+                    text.push(line.to_string());
+                }
+                tokens.push(Token{ text: text.join("\n").trim().to_string(), quoted: true, line: index + 1, column: 1 });
+                continue;
+            }
+            let mut chars = line.char_indices().peekable();
+            while let Some(&(start, c)) = chars.peek() {
+                let column = start + 1;
+                if c.is_whitespace() {
+                    chars.next();
+                } else if c == '#' {
+                    break;
+                } else if c == '\'' || c == '"' {
+                    // a quote only closes the value when followed by whitespace
+                    chars.next();
+                    let mut text = String::new();
+                    while let Some((_, c2)) = chars.next() {
+                        if c2 == c && chars.peek().is_none_or(|(_, n)| n.is_whitespace()) {
+                            break;
+                        }
+                        text.push(c2);
+                    }
+                    tokens.push(Token{ text, quoted: true, line: index + 1, column });
+                } else {
+                    let mut text = String::new();
+                    while let Some(&(_, c2)) = chars.peek() {
+                        if c2.is_whitespace() {
+                            break;
+                        }
+                        text.push(c2);
+                        chars.next();
+                    }
+                    tokens.push(Token{ text, quoted: false, line: index + 1, column });
+                }
+            }
+        }
+        tokens
+    }
+
+    fn is_tag(token: &Token) -> bool {
+        !token.quoted && token.text.starts_with('_')
+    }
+    fn is_keyword(token: &Token, keyword: &str) -> bool {
+        !token.quoted && token.text.to_lowercase().starts_with(keyword)
+    }
+
+    /// group the tokens of a CIF file into data blocks
+    fn read_data_blocks(tokens: &[Token], path: Option<&Path>) -> Result<Vec<DataBlock>, ParseError> {
+        let mut blocks: Vec<DataBlock> = Vec::new();
+        let mut index = 0;
+        while index < tokens.len() {
+            let token = &tokens[index];
+            if is_keyword(token, "data_") {
+                blocks.push(DataBlock{ line: token.line, ..Default::default() });
+                index += 1;
+                continue;
+            }
+            let block = blocks.last_mut().ok_or_else(|| token.unexpected(path))?;
+            if is_keyword(token, "loop_") {
+                let keyword = token;
+                index += 1;
+                let mut tags = Vec::new();
+                while index < tokens.len() && is_tag(&tokens[index]) {
+                    tags.push(tokens[index].text.to_lowercase());
+                    index += 1;
+                }
+                let mut values = Vec::new();
+                while index < tokens.len() && !is_tag(&tokens[index]) && !is_keyword(&tokens[index], "loop_") && !is_keyword(&tokens[index], "data_") {
+                    values.push(tokens[index].clone());
+                    index += 1;
+                }
+                if tags.is_empty() || values.len() % tags.len() != 0 {
+                    return Err(keyword.unexpected(path));
+                }
+                block.loops.push((tags, values));
+            } else if is_tag(token) {
+                let value = tokens.get(index + 1).filter(|t| !is_tag(t)).ok_or_else(|| token.unexpected(path))?;
+                block.items.insert(token.text.to_lowercase(), value.clone());
+                index += 2;
+            } else {
+                // global_ and save_ frames are not used by structure files
+                index += 1;
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// parse a CIF number, dropping the standard uncertainty, e.g. `5.431(2)`
+    fn parse_number(input: &str) -> Option<f64> {
+        input.split('(').next()?.parse::<f64>().ok()
+    }
+
+    /// parse a fraction like `1/2` or a decimal number
+    fn parse_fraction(input: &str) -> Option<f64> {
+        match input.split_once('/') {
+            Some((numerator, denominator)) => Some(numerator.parse::<f64>().ok()? / denominator.parse::<f64>().ok()?),
+            None => input.parse::<f64>().ok(),
+        }
+    }
+
+    /**
+    parse a symmetry operator such as `-x+1/2, y, -z+1/2`
+
+    returns the rotation matrix and translation vector acting on fractional coordinates
+     */
+    fn parse_symmetry_operation(input: &str) -> Option<(Matrix3<f64>, Vector3<f64>)> {
+        let parts: Vec<&str> = input.split(',').collect();
+        if parts.len() != 3 {
+            return None;
+        }
+        let mut rotation = Matrix3::zeros();
+        let mut translation = Vector3::zeros();
+        for (i, part) in parts.iter().enumerate() {
+            let mut sign = 1.0;
+            let mut number = String::new();
+            // a trailing '+' flushes the last number
+            for c in part.to_lowercase().chars().filter(|c| !c.is_whitespace()).chain(std::iter::once('+')) {
+                match c {
+                    '+' | '-' => {
+                        if !number.is_empty() {
+                            translation[i] += sign * parse_fraction(&number)?;
+                            number.clear();
+                        }
+                        sign = if c == '-' { -1.0 } else { 1.0 };
+                    },
+                    'x' | 'y' | 'z' => {
+                        let coefficient = if number.is_empty() { 1.0 } else { parse_fraction(&number)? };
+                        number.clear();
+                        rotation[(i, (c as u8 - b'x') as usize)] += sign * coefficient;
+                    },
+                    '*' => (),
+                    _ => number.push(c),
+                }
+            }
+        }
+        Some((rotation, translation))
+    }
+
+    /// element symbol of a type symbol or label, e.g. `Fe2+` or `O12`
+    fn element_of(label: &str) -> String {
+        let letters: String = label.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+        let mut chars = letters.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+            None => label.to_string(),
+        }
+    }
+
+    /// parser for CIF files, every data block becomes a `StructureBlock`
+    pub struct CifParser{}
+    impl CifParser {
+        pub fn new() -> Self {
+            Self {}
+        }
+
+        /**
+        build a `StructureBlock` from a data block, expanding the asymmetric unit with its symmetry operators
+
+        a data block without atom sites is reported as a truncated block
+         */
+        fn to_structure(&self, data: &DataBlock, number: u64, path: Option<&Path>) -> Result<StructureBlock, ParseError> {
+            let crystal = CrystalInfo{
+                x: data.cell("_cell_length_a", path)?,
+                y: data.cell("_cell_length_b", path)?,
+                z: data.cell("_cell_length_c", path)?,
+                alpha: data.cell("_cell_angle_alpha", path)?,
+                beta: data.cell("_cell_angle_beta", path)?,
+                gamma: data.cell("_cell_angle_gamma", path)?,
+            };
+            let lattice = crystal.lattice_vectors();
+            let missing = || ParseError::TruncatedBlock{ location: Location::new(path, data.line, 0), expected: None, found: 0 };
+            let elements = data.column("_atom_site_type_symbol")
+                .or_else(|| data.column("_atom_site_label"))
+                .ok_or_else(missing)?;
+            let mut positions = Vec::new();
+            for tag in ["_atom_site_fract_x", "_atom_site_fract_y", "_atom_site_fract_z"] {
+                let column = data.column(tag).ok_or_else(missing)?;
+                let column = column.iter()
+                    .map(|value| value.number(path))
+                    .collect::<Result<Vec<f64>, ParseError>>()?;
+                positions.push(column);
+            }
+            if positions.iter().any(|column| column.len() != elements.len()) {
+                return Err(missing());
+            }
+            let operations = match SYMMETRY_OPERATION_TAGS.iter().find_map(|tag| data.column(tag)) {
+                Some(column) => column.iter()
+                    .map(|op| parse_symmetry_operation(&op.text).ok_or_else(|| op.unexpected(path)))
+                    .collect::<Result<Vec<_>, ParseError>>()?,
+                None => vec![(Matrix3::identity(), Vector3::zeros())],
+            };
+            let mut sites: Vec<(String, Vector3<f64>)> = Vec::new();
+            for (i, element) in elements.iter().enumerate() {
+                let element = element_of(&element.text);
+                let site = Vector3::new(positions[0][i], positions[1][i], positions[2][i]);
+                for (rotation, translation) in operations.iter() {
+                    let image = (rotation * site + translation).map(|v| v - v.floor());
+                    let duplicated = sites.iter().any(|(_, other)| {
+                        let difference = (image - other).map(|v| v - v.round());
+                        (difference.transpose() * lattice).norm() < DUPLICATE_TOLERANCE
+                    });
+                    if !duplicated {
+                        sites.push((element.clone(), image));
+                    }
+                }
+            }
+            let atoms = sites.into_iter().map(|(element, site)| {
+                let position = site.transpose() * lattice;
+                Atom{
+                    element,
+                    coordinate: Coordinate(position[0], position[1], position[2]),
+                    record: None,
+                    selective: None
+                }
+            }).collect();
+            Ok(StructureBlock{
+                number,
+                energy: data.items.get(ENERGY_TAG).and_then(|e| parse_number(&e.text)).unwrap_or(0.0),
+                symmetry: data.items.get(SYMMETRY_TAG).map(|s| s.text.clone()).unwrap_or(String::from("C1")),
+                crystal,
+                atoms,
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
+            })
+        }
+    }
+    impl Default for CifParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl StructureIO for CifParser{
+        // a CIF is not line oriented, everything is handled in `parse_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            // CIF files are small, the data blocks are read at once
+            let blocks = self.parse_structure(input, ignore_parse_error)?.unwrap_or_default();
+            Ok(StructureIterator::new(blocks.into_iter().map(Ok)))
+        }
+        fn parse_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+            let mut content = String::new();
+            open_reader(input).and_then(|mut f| f.read_to_string(&mut content)).map_err(ParseError::IoError)?;
+            let data_blocks = match read_data_blocks(&tokenize(&content), Some(input)) {
+                Ok(data_blocks) => data_blocks,
+                Err(_) if ignore_parse_error => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            let mut blocks = Vec::new();
+            for (i, data) in data_blocks.iter().enumerate() {
+                match self.to_structure(data, i as u64, Some(input)) {
+                    Ok(block) => blocks.push(block),
+                    Err(_) if ignore_parse_error => (),
+                    Err(e) => return Err(e),
+                }
+            }
+            if !blocks.is_empty() {
+                Ok(Some(blocks))
+            } else {
+                Ok(None)
+            }
+        }
+        /// write the blocks as P1 structures, one data block each
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let mut file = CompressedWriter::create(path)?;
+            for (i, block) in structures.iter().enumerate() {
+                let lattice = block.crystal.lattice_vectors();
+                let inverse = lattice.try_inverse().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("block {} with energy {} has no valid cell", block.number, block.energy)))?;
+                writeln!(file, "data_structure_{}", i)?;
+                writeln!(file, "{: <35} {}", ENERGY_TAG, block.energy)?;
+                writeln!(file, "{: <35} '{}'", SYMMETRY_TAG, block.symmetry)?;
+                writeln!(file, "{: <35} 'P 1'", "_symmetry_space_group_name_H-M")?;
+                writeln!(file, "{: <35} 1", "_symmetry_Int_Tables_number")?;
+                writeln!(file, "{: <35} {:.8}", "_cell_length_a", block.crystal.x)?;
+                writeln!(file, "{: <35} {:.8}", "_cell_length_b", block.crystal.y)?;
+                writeln!(file, "{: <35} {:.8}", "_cell_length_c", block.crystal.z)?;
+                writeln!(file, "{: <35} {:.8}", "_cell_angle_alpha", block.crystal.alpha)?;
+                writeln!(file, "{: <35} {:.8}", "_cell_angle_beta", block.crystal.beta)?;
+                writeln!(file, "{: <35} {:.8}", "_cell_angle_gamma", block.crystal.gamma)?;
+                writeln!(file, "loop_")?;
+                writeln!(file, "_symmetry_equiv_pos_as_xyz")?;
+                writeln!(file, "  'x, y, z'")?;
+                writeln!(file, "loop_")?;
+                writeln!(file, "_atom_site_label")?;
+                writeln!(file, "_atom_site_type_symbol")?;
+                writeln!(file, "_atom_site_fract_x")?;
+                writeln!(file, "_atom_site_fract_y")?;
+                writeln!(file, "_atom_site_fract_z")?;
+                writeln!(file, "_atom_site_occupancy")?;
+                for (j, atom) in block.atoms.iter().enumerate() {
+                    let position = RowVector3::new(atom.coordinate.0, atom.coordinate.1, atom.coordinate.2) * inverse;
+                    writeln!(file, "  {: <8} {: <3} {: >14.10} {: >14.10} {: >14.10} 1.0", format!("{}{}", atom.element, j + 1), atom.element, position[0], position[1], position[2])?;
+                }
+                writeln!(file)?;
+            }
+            file.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use tempfile::{NamedTempFile, TempDir};
+    use std::io::Write;
+
+    use crate::parser::parser::*;
+    use crate::cif::cif_parser::*;
+    use crate::modules::structures::*;
+
+    /// rock salt NaCl in Fm-3m, given by its asymmetric unit and the face centering operators
+    fn write_nacl(file: &mut NamedTempFile) {
+        writeln!(file, "# NaCl").unwrap();
+        writeln!(file, "data_NaCl").unwrap();
+        writeln!(file, "_cell_length_a    5.640(1)").unwrap();
+        writeln!(file, "_cell_length_b    5.640(1)").unwrap();
+        writeln!(file, "_cell_length_c    5.640(1)").unwrap();
+        writeln!(file, "_cell_angle_alpha 90").unwrap();
+        writeln!(file, "_cell_angle_beta  90").unwrap();
+        writeln!(file, "_cell_angle_gamma 90").unwrap();
+        writeln!(file, "_symmetry_space_group_name_H-M 'F m -3 m'").unwrap();
+        writeln!(file, "loop_").unwrap();
+        writeln!(file, "_symmetry_equiv_pos_as_xyz").unwrap();
+        writeln!(file, "  'x, y, z'").unwrap();
+        writeln!(file, "  'x, y+1/2, z+1/2'").unwrap();
+        writeln!(file, "  '1/2+x, y, 1/2+z'").unwrap();
+        writeln!(file, "  'x+1/2, y+1/2, z'").unwrap();
+        writeln!(file, "  '-x, -y, -z'").unwrap();
+        writeln!(file, "loop_").unwrap();
+        writeln!(file, "_atom_site_label").unwrap();
+        writeln!(file, "_atom_site_fract_x").unwrap();
+        writeln!(file, "_atom_site_fract_y").unwrap();
+        writeln!(file, "_atom_site_fract_z").unwrap();
+        writeln!(file, "Na1 0.0 0.0 0.0").unwrap();
+        writeln!(file, "CL1 0.5 0.5 0.5").unwrap();
+    }
+
+    #[test]
+    fn test_parse_cif_structure_success() {
+        let mut file = NamedTempFile::new().unwrap();
+        write_nacl(&mut file);
+        let parser = CifParser::new();
+        let blocks = parser.parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 1);
+        let block = &blocks[0];
+        assert_eq!(block.crystal.x, 5.64);
+        assert_eq!(block.crystal.gamma, 90.0);
+        assert_eq!(block.energy, 0.0);
+        assert_eq!(block.symmetry, "C1");
+        // the inversion adds no new atoms
+        assert_eq!(block.atoms.len(), 8);
+        assert_eq!(block.atoms.iter().filter(|a| a.element == "Na").count(), 4);
+        assert_eq!(block.atoms.iter().filter(|a| a.element == "Cl").count(), 4);
+        let chlorine = block.atoms.iter().find(|a| a.element == "Cl").unwrap();
+        assert!((chlorine.coordinate.0 - 2.82).abs() < 1e-9);
+        assert!((chlorine.coordinate.2 - 2.82).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_cif_structure_fail() {
+        let parser = CifParser::new();
+        // a data block without cell
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "data_empty").unwrap();
+        writeln!(file, "_chemical_name_common 'nothing'").unwrap();
+        let path = file.path();
+        let result = parser.parse_structure(path, true);
+        assert!(result.unwrap().is_none());
+        let result = parser.parse_structure(path, false);
+        assert!(matches!(result.unwrap_err(), ParseError::MissingCell{ location } if location.line == 1));
+        let result = parser.parse_structure(Path::new("non_existent_file.cif"), false);
+        assert!(matches!(result.unwrap_err(), ParseError::IoError(_)));
+    }
+
+    #[test]
+    fn test_write_cif_structure() {
+        let blocks = vec![
+            StructureBlock{
+                number: 0,
+                energy: -3620.67936,
+                symmetry: "C2v".to_string(),
+                crystal: CrystalInfo{
+                    x: 5.0,
+                    y: 6.0,
+                    z: 7.0,
+                    alpha: 80.0,
+                    beta: 95.0,
+                    gamma: 120.0
+                },
+                atoms: vec![
+                    Atom{
+                        element: "Fe".to_string(),
+                        coordinate: Coordinate(1.0, 1.0, 1.0),
+                        record: None,
+                        selective: None
+                    },
+                    Atom{
+                        element: "O".to_string(),
+                        coordinate: Coordinate(-1.0, 2.5, 3.0),
+                        record: None,
+                        selective: None
+                    }
+                ],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
+            }
+        ];
+        let parser = CifParser::new();
+        let dir = TempDir::new().unwrap();
+        for name in ["out.cif", "out.cif.gz", "out.cif.xz", "out.cif.zst"] {
+            let path = dir.path().join(name);
+            parser.write_structure(&blocks, &path).unwrap();
+            let read_blocks = parser.parse_structure(&path, false).unwrap().unwrap();
+            assert_eq!(read_blocks.len(), 1, "{}", name);
+            let read_block = &read_blocks[0];
+            assert_eq!(read_block.energy, -3620.67936);
+            assert_eq!(read_block.symmetry, "C2v");
+            assert!((read_block.crystal.alpha - 80.0).abs() < 1e-6);
+            assert_eq!(read_block.atoms.len(), 2);
+            assert_eq!(read_block.atoms[1].element, "O");
+            // coordinates are wrapped into the cell, compare the iron atom which is inside
+            assert!((read_block.atoms[0].coordinate.0 - 1.0).abs() < 1e-6);
+            assert!((read_block.atoms[0].coordinate.1 - 1.0).abs() < 1e-6);
+            assert!((read_block.atoms[0].coordinate.2 - 1.0).abs() < 1e-6);
+        }
+        // a block without a cell is named by its number and energy
+        let mut cluster = blocks[0].clone();
+        cluster.crystal = CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 90.0, beta: 90.0, gamma: 90.0 };
+        let err = parser.write_structure(&[cluster], &dir.path().join("cluster.cif")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "block 0 with energy -3620.67936 has no valid cell");
+    }
+}
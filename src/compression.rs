@@ -0,0 +1,161 @@
+pub mod compression {
+    //! transparent reading and writing of compressed structure files
+    //!
+    //! the compression is given by the last extension, so `all.arc.gz` is a gzipped .arc file.
+    //! The path `-` stands for stdin when reading and stdout when writing, without compression
+    use std::fs::File;
+    use std::io::{self, BufRead, BufReader, BufWriter, Write};
+    use std::path::Path;
+
+    use flate2::{read::MultiGzDecoder, write::GzEncoder};
+    use xz2::{read::XzDecoder, write::XzEncoder};
+
+    /// compression formats, recognised by the extension of a file
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Compression {
+        None,
+        Gzip,
+        Xz,
+        Zstd,
+    }
+    impl Compression {
+        /// the compression of an extension, `None` for an extension of a structure format
+        pub fn from_extension(extension: &str) -> Compression {
+            match extension.to_lowercase().as_str() {
+                "gz" | "gzip" => Compression::Gzip,
+                "xz" => Compression::Xz,
+                "zst" | "zstd" => Compression::Zstd,
+                _ => Compression::None,
+            }
+        }
+        /// the compression of a file, given by its last extension
+        pub fn from_path(path: &Path) -> Compression {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .map_or(Compression::None, Compression::from_extension)
+        }
+    }
+
+    /// split a compression suffix from a file type, e.g. `arc.gz` gives `arc` and `Gzip`
+    pub fn split_compression(file_type: &str) -> (&str, Compression) {
+        match file_type.rsplit_once('.') {
+            Some((inner, extension)) if Compression::from_extension(extension) != Compression::None => {
+                (inner, Compression::from_extension(extension))
+            },
+            _ => (file_type, Compression::None),
+        }
+    }
+
+    /// whether a path is `-`, for stdin or stdout
+    pub fn is_stdio(path: &Path) -> bool {
+        path == Path::new("-")
+    }
+
+    /// open a file for reading, decompressing it according to its extension
+    pub fn open_reader(path: &Path) -> io::Result<Box<dyn BufRead>> {
+        if is_stdio(path) {
+            return Ok(Box::new(BufReader::new(io::stdin())));
+        }
+        let file = File::open(path)?;
+        Ok(match Compression::from_path(path) {
+            Compression::None => Box::new(BufReader::new(file)),
+            // concatenated gzip members are common when archives are appended to
+            Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+            Compression::Xz => Box::new(BufReader::new(XzDecoder::new_multi_decoder(file))),
+            Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::new(file)?)),
+        })
+    }
+
+    /**
+    a file opened for writing, compressed according to its extension
+
+    `finish` must be called once everything is written, so that the compressed stream is
+    completed and errors are reported
+     */
+    pub enum CompressedWriter {
+        Plain(BufWriter<File>),
+        Stdout(BufWriter<io::Stdout>),
+        Gzip(GzEncoder<BufWriter<File>>),
+        Xz(XzEncoder<BufWriter<File>>),
+        Zstd(zstd::Encoder<'static, BufWriter<File>>),
+    }
+    impl CompressedWriter {
+        pub fn create(path: &Path) -> io::Result<Self> {
+            if is_stdio(path) {
+                return Ok(CompressedWriter::Stdout(BufWriter::new(io::stdout())));
+            }
+            let file = BufWriter::new(File::create(path)?);
+            Ok(match Compression::from_path(path) {
+                Compression::None => CompressedWriter::Plain(file),
+                Compression::Gzip => CompressedWriter::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+                Compression::Xz => CompressedWriter::Xz(XzEncoder::new(file, 6)),
+                Compression::Zstd => CompressedWriter::Zstd(zstd::Encoder::new(file, 0)?),
+            })
+        }
+        /// complete the compressed stream and flush the file
+        pub fn finish(self) -> io::Result<()> {
+            let mut file = match self {
+                CompressedWriter::Stdout(mut stdout) => return stdout.flush(),
+                CompressedWriter::Plain(file) => file,
+                CompressedWriter::Gzip(encoder) => encoder.finish()?,
+                CompressedWriter::Xz(encoder) => encoder.finish()?,
+                CompressedWriter::Zstd(encoder) => encoder.finish()?,
+            };
+            file.flush()
+        }
+    }
+    impl Write for CompressedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            match self {
+                CompressedWriter::Plain(file) => file.write(buf),
+                CompressedWriter::Stdout(stdout) => stdout.write(buf),
+                CompressedWriter::Gzip(encoder) => encoder.write(buf),
+                CompressedWriter::Xz(encoder) => encoder.write(buf),
+                CompressedWriter::Zstd(encoder) => encoder.write(buf),
+            }
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            match self {
+                CompressedWriter::Plain(file) => file.flush(),
+                CompressedWriter::Stdout(stdout) => stdout.flush(),
+                CompressedWriter::Gzip(encoder) => encoder.flush(),
+                CompressedWriter::Xz(encoder) => encoder.flush(),
+                CompressedWriter::Zstd(encoder) => encoder.flush(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use tempfile::TempDir;
+
+    use crate::compression::compression::*;
+
+    #[test]
+    fn test_compression_of_path() {
+        assert_eq!(Compression::from_path(std::path::Path::new("all.arc.gz")), Compression::Gzip);
+        assert_eq!(Compression::from_path(std::path::Path::new("all.xyz.XZ")), Compression::Xz);
+        assert_eq!(Compression::from_path(std::path::Path::new("all.arc.zst")), Compression::Zstd);
+        assert_eq!(Compression::from_path(std::path::Path::new("all.arc")), Compression::None);
+        assert_eq!(split_compression("arc.gz"), ("arc", Compression::Gzip));
+        assert_eq!(split_compression("xyz"), ("xyz", Compression::None));
+        assert!(is_stdio(std::path::Path::new("-")));
+        assert!(!is_stdio(std::path::Path::new("./-")));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = TempDir::new().unwrap();
+        for name in ["plain.arc", "all.arc.gz", "all.arc.xz", "all.arc.zst"] {
+            let path = dir.path().join(name);
+            let mut writer = CompressedWriter::create(&path).unwrap();
+            writeln!(writer, "!BIOSYM archive 2").unwrap();
+            writer.finish().unwrap();
+            let mut content = String::new();
+            open_reader(&path).unwrap().read_to_string(&mut content).unwrap();
+            assert_eq!(content, "!BIOSYM archive 2\n", "{}", name);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+pub mod cp2k_input {
+    //! the `&CELL` and `&COORD` sections of CP2K inputs and `.restart` files
+    //!
+    //! structures are written as the `&CELL` and `&COORD` sections of `&FORCE_EVAL/&SUBSYS`, to be pasted into an
+    //! input. The same sections are read back from inputs and from the `.restart` files of a geometry optimisation
+    use std::io::{self, BufRead, Write};
+    use std::path::Path;
+    use nalgebra::{Matrix3, RowVector3};
+
+    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::compression::compression::{open_reader, CompressedWriter};
+    use crate::parser::parser::{StructureIO, StructureIterator, ParseError, Location, split_columns, parse_float};
+
+    /// the Bohr radius in angstrom
+    const BOHR: f64 = 0.529177210903;
+
+    /// scale of a length unit such as `[bohr]` to angstrom, `None` for units that are not lengths
+    fn unit_scale(unit: &str) -> Option<f64> {
+        match unit.trim_matches(['[', ']']).to_lowercase().as_str() {
+            "angstrom" | "ang" => Some(1.0),
+            "bohr" | "au_l" => Some(BOHR),
+            "nm" => Some(10.0),
+            "pm" => Some(0.01),
+            _ => None,
+        }
+    }
+
+    /**
+    parser for CP2K inputs and `.restart` files
+
+    a file gives a single structure from its `&CELL` (`A`/`B`/`C` vectors, or `ABC` with `ALPHA_BETA_GAMMA`)
+    and `&COORD` sections, which may be `SCALED` or in another `UNIT`. `&CELL_REF` is skipped.
+    Kind labels such as `Fe1` are read as their element. The energy is only known from the
+    `# Energy:` comment written by this parser. The cell is rotated into the standard orientation of `CrystalInfo`.
+     */
+    pub struct Cp2kParser;
+    impl Cp2kParser {
+        pub fn new() -> Self {
+            Self
+        }
+
+        /// parse the lines of an input, `path` is only used to locate parse errors
+        fn parse_lines(&self, lines: &[String], path: Option<&Path>) -> Result<StructureBlock, ParseError> {
+            let mut sections: Vec<String> = Vec::new();
+            let mut energy = 0.0;
+            let mut vectors: [Option<RowVector3<f64>>; 3] = [None; 3];
+            let mut lengths: Option<RowVector3<f64>> = None;
+            let mut angles = RowVector3::new(90.0, 90.0, 90.0);
+            let mut atoms: Vec<(String, RowVector3<f64>)> = Vec::new();
+            let mut scaled = false;
+            let mut scale = 1.0;
+            let mut coord_line = 0;
+            let mut periodic = true;
+            // the three numbers after the keyword and an optional unit
+            let vector = |index: usize, columns: &[(usize, &str)]| -> Result<(RowVector3<f64>, f64), ParseError> {
+                let (factor, values) = match columns.get(1) {
+                    Some((_, unit)) if unit.starts_with('[') => (unit_scale(unit), &columns[2..]),
+                    _ => (Some(1.0), &columns[1..]),
+                };
+                if values.len() < 3 {
+                    return Err(ParseError::UnexpectedLine{ location: Location::new(path, index + 1, 1), line: lines[index].clone() });
+                }
+                let factor = factor.ok_or_else(|| ParseError::UnexpectedLine{ location: Location::new(path, index + 1, columns[1].0), line: lines[index].clone() })?;
+                let values = values.iter().take(3)
+                    .map(|(column, text)| parse_float(text, *column).map_err(|e| e.at(path, index + 1)))
+                    .collect::<Result<Vec<f64>, ParseError>>()?;
+                Ok((RowVector3::new(values[0], values[1], values[2]), factor))
+            };
+            for (index, line) in lines.iter().enumerate() {
+                let trimmed = line.trim();
+                if let Some(value) = trimmed.strip_prefix('#').and_then(|l| l.trim().strip_prefix("Energy:")) {
+                    energy = value.trim().parse::<f64>().unwrap_or(energy);
+                    continue;
+                }
+                if trimmed.is_empty() || trimmed.starts_with(['#', '!']) {
+                    continue;
+                }
+                let columns = split_columns(line);
+                let keyword = columns[0].1.to_uppercase();
+                if keyword == "&END" {
+                    sections.pop();
+                    continue;
+                }
+                if let Some(section) = keyword.strip_prefix('&') {
+                    if section == "COORD" {
+                        // a later `&COORD` replaces the previous one
+                        atoms.clear();
+                        scaled = false;
+                        scale = 1.0;
+                        coord_line = index + 1;
+                    }
+                    sections.push(section.to_string());
+                    continue;
+                }
+                match sections.last().map(|s| s.as_str()) {
+                    Some("CELL") => match keyword.as_str() {
+                        "A" | "B" | "C" => {
+                            let (v, factor) = vector(index, &columns)?;
+                            let i = (keyword.as_bytes()[0] - b'A') as usize;
+                            vectors[i] = Some(v * factor);
+                        },
+                        "ABC" => {
+                            let (v, factor) = vector(index, &columns)?;
+                            lengths = Some(v * factor);
+                        },
+                        "ALPHA_BETA_GAMMA" => angles = vector(index, &columns)?.0,
+                        "PERIODIC" => periodic = columns.get(1).is_none_or(|(_, v)| !v.eq_ignore_ascii_case("NONE")),
+                        _ => (),
+                    },
+                    Some("COORD") => match keyword.as_str() {
+                        "UNIT" => {
+                            scale = columns.get(1).and_then(|(_, u)| unit_scale(u))
+                                .ok_or_else(|| ParseError::UnexpectedLine{ location: Location::new(path, index + 1, 1), line: line.clone() })?;
+                        },
+                        "SCALED" => scaled = columns.get(1).is_none_or(|(_, v)| matches!(v.to_uppercase().as_str(), "T" | "TRUE" | ".TRUE." | "Y" | "YES" | "ON")),
+                        _ => {
+                            let (position, _) = vector(index, &columns[..columns.len().min(4)])?;
+                            // kinds are named after their element, optionally numbered
+                            let element = columns[0].1.trim_end_matches(|c: char| !c.is_ascii_alphabetic()).to_string();
+                            atoms.push((element, position));
+                        },
+                    },
+                    _ => (),
+                }
+            }
+            if coord_line == 0 {
+                return Err(ParseError::TruncatedBlock{ location: Location::new(path, lines.len(), 0), expected: None, found: 0 });
+            }
+            let location = Location::new(path, coord_line, 0);
+            let (lattice, crystal) = match (vectors, lengths) {
+                ([Some(a), Some(b), Some(c)], _) => {
+                    let lattice = Matrix3::from_rows(&[a, b, c]);
+                    (lattice, CrystalInfo::from_lattice_vectors(&lattice))
+                },
+                (_, Some(abc)) => {
+                    let crystal = CrystalInfo{ x: abc[0], y: abc[1], z: abc[2], alpha: angles[0], beta: angles[1], gamma: angles[2] };
+                    (crystal.lattice_vectors(), crystal)
+                },
+                _ => return Err(ParseError::MissingCell{ location }),
+            };
+            let inverse = lattice.try_inverse().ok_or(ParseError::MissingCell{ location })?;
+            let standard = crystal.lattice_vectors();
+            let atoms = atoms.into_iter().map(|(element, position)| {
+                let fractional = if scaled { position } else { position * scale * inverse };
+                let position = fractional * standard;
+                Atom{ element, coordinate: Coordinate(position[0], position[1], position[2]), record: None, selective: None }
+            }).collect();
+            Ok(StructureBlock{
+                number: 0,
+                energy,
+                symmetry: String::from("C1"),
+                crystal,
+                atoms,
+                periodic,
+                forces: None,
+                record: None,
+                stress: None
+            })
+        }
+    }
+    impl Default for Cp2kParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl StructureIO for Cp2kParser {
+        // sections span several lines and may come in any order, everything is handled in `parse_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            let blocks = self.parse_structure(input, ignore_parse_error)?.unwrap_or_default();
+            Ok(StructureIterator::new(blocks.into_iter().map(Ok)))
+        }
+        fn parse_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+            let lines = open_reader(input).map_err(ParseError::IoError)?
+                .lines()
+                .collect::<Result<Vec<String>, io::Error>>()
+                .map_err(ParseError::IoError)?;
+            match self.parse_lines(&lines, Some(input)) {
+                Ok(block) => Ok(Some(vec![block])),
+                Err(_) if ignore_parse_error => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let block = match structures {
+                [block] => block,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("a CP2K input holds exactly one structure, got {}", structures.len()))),
+            };
+            // CP2K needs a box even for molecules, which is then not periodic
+            let lattice = block.lattice().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("block {} with energy {} has no valid cell", block.number, block.energy)))?;
+            let lattice = lattice.matrix();
+            let mut file = CompressedWriter::create(path)?;
+            writeln!(file, "# Energy: {}", block.energy)?;
+            writeln!(file, "&CELL")?;
+            for (name, row) in ["A", "B", "C"].iter().zip(lattice.row_iter()) {
+                writeln!(file, "  {} [angstrom] {: >18.10} {: >18.10} {: >18.10}", name, row[0], row[1], row[2])?;
+            }
+            let periodic = if block.periodic { "XYZ" } else { "NONE" };
+            writeln!(file, "  PERIODIC {}", periodic)?;
+            writeln!(file, "&END CELL")?;
+            writeln!(file, "&COORD")?;
+            for atom in block.atoms.iter() {
+                writeln!(file, "  {: <3} {: >18.10} {: >18.10} {: >18.10}", atom.element, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2)?;
+            }
+            writeln!(file, "&END COORD")?;
+            file.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::{NamedTempFile, TempDir};
+
+    use crate::cp2k::cp2k_input::*;
+    use crate::parser::parser::*;
+    use crate::modules::structures::CrystalInfo;
+
+    #[test]
+    fn test_parse_restart() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, " # Version information for this restart file").unwrap();
+        writeln!(file, " &FORCE_EVAL").unwrap();
+        writeln!(file, "   &SUBSYS").unwrap();
+        writeln!(file, "     &CELL").unwrap();
+        writeln!(file, "       A     0.0000000000000000E+00    1.0000000000000000E+01    0.0000000000000000E+00").unwrap();
+        writeln!(file, "       B    -1.0000000000000000E+01    0.0000000000000000E+00    0.0000000000000000E+00").unwrap();
+        writeln!(file, "       C     0.0000000000000000E+00    0.0000000000000000E+00    1.2000000000000000E+01").unwrap();
+        writeln!(file, "       &CELL_REF").unwrap();
+        writeln!(file, "         ABC 1.0 1.0 1.0").unwrap();
+        writeln!(file, "       &END CELL_REF").unwrap();
+        writeln!(file, "     &END CELL").unwrap();
+        writeln!(file, "     &COORD").unwrap();
+        writeln!(file, "Fe1    0.0000000000000000E+00    1.0000000000000000E+00    0.0000000000000000E+00").unwrap();
+        writeln!(file, "O     -2.0000000000000000E+00    0.0000000000000000E+00    3.0000000000000000E+00  H2O").unwrap();
+        writeln!(file, "     &END COORD").unwrap();
+        writeln!(file, "   &END SUBSYS").unwrap();
+        writeln!(file, " &END FORCE_EVAL").unwrap();
+        let block = &Cp2kParser::new().parse_structure(file.path(), false).unwrap().unwrap()[0];
+        assert!((block.crystal.x - 10.0).abs() < 1e-9);
+        assert!((block.crystal.z - 12.0).abs() < 1e-9);
+        assert_eq!(block.atoms[0].element, "Fe");
+        // rotated by -90 degrees around z
+        assert!((block.atoms[0].coordinate.0 - 1.0).abs() < 1e-9);
+        assert!((block.atoms[1].coordinate.1 - 2.0).abs() < 1e-9);
+        assert!((block.atoms[1].coordinate.2 - 3.0).abs() < 1e-9);
+        assert_eq!(detect_format(file.path()).unwrap(), "cp2k");
+    }
+
+    #[test]
+    fn test_parse_restart_fail() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "&COORD").unwrap();
+        writeln!(file, "  Fe 0.0 0.0 0.0").unwrap();
+        writeln!(file, "&END COORD").unwrap();
+        let err = Cp2kParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::MissingCell{ ref location } if location.line == 1), "Got {:?}", err);
+        writeln!(file, "&CELL").unwrap();
+        writeln!(file, "  ABC 5.0 5.x 5.0").unwrap();
+        writeln!(file, "&END CELL").unwrap();
+        let err = Cp2kParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::BadFloat{ ref location, .. } if location.line == 5 && location.column == 11), "Got {:?}", err);
+        assert!(Cp2kParser::new().parse_structure(file.path(), true).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cp2k_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "&CELL").unwrap();
+        writeln!(file, "  ABC [bohr] 10.0 10.0 10.0").unwrap();
+        writeln!(file, "  ALPHA_BETA_GAMMA 90 90 120").unwrap();
+        writeln!(file, "&END CELL").unwrap();
+        writeln!(file, "&COORD").unwrap();
+        writeln!(file, "  SCALED").unwrap();
+        writeln!(file, "  Si 0.0 0.0 0.0").unwrap();
+        writeln!(file, "  Si 0.5 0.5 0.5").unwrap();
+        writeln!(file, "&END COORD").unwrap();
+        let blocks = Cp2kParser::new().parse_structure(file.path(), false).unwrap().unwrap();
+        assert!((blocks[0].crystal.x - 10.0 * 0.529177210903).abs() < 1e-9);
+        assert!((blocks[0].crystal.gamma - 120.0).abs() < 1e-9);
+        let path = dir.path().join("geo.cp2k");
+        Cp2kParser::new().write_structure(&blocks, &path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("  PERIODIC XYZ\n&END CELL\n&COORD\n"), "{}", content);
+        let written = &Cp2kParser::new().parse_structure(&path, false).unwrap().unwrap()[0];
+        for (a, b) in written.atoms.iter().zip(blocks[0].atoms.iter()) {
+            assert!((a.coordinate.0 - b.coordinate.0).abs() < 1e-8);
+            assert!((a.coordinate.1 - b.coordinate.1).abs() < 1e-8);
+            assert!((a.coordinate.2 - b.coordinate.2).abs() < 1e-8);
+        }
+        assert!(written.periodic);
+        let mut cluster = blocks[0].clone();
+        cluster.periodic = false;
+        Cp2kParser::new().write_structure(&[cluster], &path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("  PERIODIC NONE\n"));
+        assert!(!Cp2kParser::new().parse_structure(&path, false).unwrap().unwrap()[0].periodic);
+        assert!(Cp2kParser::new().write_structure(&[], &path).is_err());
+        // a cluster without a box, e.g. from a Gaussian output, can not be written
+        let mut molecule = blocks[0].clone();
+        molecule.periodic = false;
+        molecule.crystal = CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 };
+        let err = Cp2kParser::new().write_structure(&[molecule], &path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,316 @@
+pub mod gaussian {
+    //! Gaussian `.gjf` inputs and the final geometry of `.log` outputs
+    //!
+    //! molecules have no cell, so structures are read and written with an empty `CrystalInfo`
+    //! and written to an .arc file with `PBC=OFF`
+    use std::io::{self, BufRead, Write};
+    use std::path::Path;
+
+    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::modules::periodic_table::PeriodicTable;
+    use crate::compression::compression::{open_reader, CompressedWriter};
+    use crate::parser::parser::{StructureIO, StructureIterator, ParseError, Location, split_columns, parse_float};
+
+    /// the Hartree energy in eV
+    pub const HARTREE: f64 = 27.211386245988;
+
+    /// an empty block for a molecule, which has no cell
+    pub(crate) fn molecule(energy: f64, atoms: Vec<Atom>) -> StructureBlock {
+        StructureBlock{
+            number: 0,
+            energy,
+            symmetry: String::from("C1"),
+            crystal: CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 },
+            atoms,
+            periodic: false,
+            forces: None,
+            record: None,
+            stress: None
+        }
+    }
+
+    /// spin multiplicity of a neutral molecule with the fewest unpaired electrons
+    pub(crate) fn multiplicity(table: &PeriodicTable, atoms: &[Atom]) -> io::Result<u64> {
+        let mut electrons = 0;
+        for atom in atoms.iter() {
+            electrons += table.get(&atom.element).map(|e| e.atomic_number)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("unknown element {}", atom.element)))?;
+        }
+        Ok(electrons % 2 + 1)
+    }
+
+    /**
+    parser for Gaussian inputs and outputs
+
+    a `.log` output gives a single structure: the last `Standard orientation` (or `Input orientation` with `NoSymm`)
+    and the last `SCF Done` energy, converted to eV. A `.gjf` input gives a structure per `--Link1--` job with
+    cartesian coordinates, the energy is read from a title such as `Energy: -1.0`.
+
+    inputs are written with `route` and a neutral charge, in the lowest multiplicity, one job per structure
+     */
+    pub struct GaussianParser {
+        table: PeriodicTable,
+        /// the route section of written inputs
+        pub route: String,
+    }
+    impl GaussianParser {
+        pub fn new() -> Self {
+            Self { table: PeriodicTable::new(), route: String::from("# opt b3lyp/6-31g(d)") }
+        }
+
+        /// the element of an atom label, which may be an atomic number or carry a suffix as in `Fe1` or `C(Fragment=1)`
+        fn element(&self, label: &str) -> Option<String> {
+            match label.parse::<u64>() {
+                Ok(number) => self.table.symbol(number).map(|s| s.to_string()),
+                Err(_) => {
+                    let element: String = label.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+                    (!element.is_empty()).then_some(element)
+                },
+            }
+        }
+
+        /// the coordinates in the last three columns of a line
+        fn coordinate(columns: &[(usize, &str)], path: Option<&Path>, line: usize) -> Result<Coordinate, ParseError> {
+            let values = columns[columns.len() - 3..].iter()
+                .map(|(column, text)| parse_float(text, *column).map_err(|e| e.at(path, line)))
+                .collect::<Result<Vec<f64>, ParseError>>()?;
+            Ok(Coordinate(values[0], values[1], values[2]))
+        }
+
+        /// parse the lines of a `.log` output, `None` if they hold no orientation table
+        fn parse_log(&self, lines: &[String], path: Option<&Path>) -> Result<Option<StructureBlock>, ParseError> {
+            let mut energy = 0.0;
+            let mut standard = None;
+            let mut input = None;
+            for (index, line) in lines.iter().enumerate() {
+                let trimmed = line.trim();
+                if trimmed.starts_with("SCF Done:") {
+                    let value = line.split('=').nth(1).and_then(|v| v.split_whitespace().next());
+                    if let Some(value) = value {
+                        let column = line.find(value).unwrap_or(0) + 1;
+                        energy = parse_float(value, column).map_err(|e| e.at(path, index + 1))? * HARTREE;
+                    }
+                }
+                else if trimmed == "Standard orientation:" {
+                    standard = Some(index);
+                }
+                else if trimmed == "Input orientation:" {
+                    input = Some(index);
+                }
+            }
+            let start = match standard.or(input) {
+                Some(start) => start,
+                None => return Ok(None),
+            };
+            // a line of dashes, two lines of titles and dashes again before the rows
+            let mut atoms = Vec::new();
+            for (index, line) in lines.iter().enumerate().skip(start + 5) {
+                if line.trim_start().starts_with("---") {
+                    return Ok(Some(molecule(energy, atoms)));
+                }
+                // center, atomic number, optionally the atomic type, and the coordinates
+                let columns = split_columns(line);
+                if columns.len() < 5 {
+                    return Err(ParseError::UnexpectedLine{ location: Location::new(path, index + 1, 1), line: line.clone() });
+                }
+                let element = self.element(columns[1].1)
+                    .ok_or_else(|| ParseError::UnexpectedLine{ location: Location::new(path, index + 1, columns[1].0), line: line.clone() })?;
+                atoms.push(Atom{ element, coordinate: Self::coordinate(&columns, path, index + 1)?, record: None, selective: None });
+            }
+            Err(ParseError::TruncatedBlock{ location: Location::new(path, start + 1, 0), expected: None, found: atoms.len() })
+        }
+
+        /// parse the jobs of a `.gjf` input
+        fn parse_input(&self, lines: &[String], path: Option<&Path>) -> Result<Vec<StructureBlock>, ParseError> {
+            let mut blocks = Vec::new();
+            let mut index = 0;
+            while index < lines.len() {
+                // link 0 commands and the route section
+                while index < lines.len() && (lines[index].trim().starts_with('%') || lines[index].trim().is_empty()) {
+                    index += 1;
+                }
+                if index == lines.len() {
+                    break;
+                }
+                if !lines[index].trim().starts_with('#') {
+                    return Err(ParseError::UnexpectedLine{ location: Location::new(path, index + 1, 1), line: lines[index].clone() });
+                }
+                let route = index + 1;
+                let mut sections = lines[index..].split(|l| l.trim().is_empty());
+                let (route_lines, title, molecule_lines) = (sections.next().unwrap_or_default(), sections.next().unwrap_or_default(), sections.next().unwrap_or_default());
+                let start = index + route_lines.len() + title.len() + 2;
+                index = start + molecule_lines.len();
+                let energy = title.iter().find_map(|l| l.split("Energy:").nth(1)).and_then(|v| v.split_whitespace().next()).and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+                // the charge and multiplicity, then the atoms
+                let mut atoms = Vec::new();
+                for (i, line) in molecule_lines.iter().enumerate().skip(1) {
+                    let line_number = start + i + 1;
+                    let columns = split_columns(line);
+                    // z-matrices are not supported
+                    if columns.len() < 4 {
+                        return Err(ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line: line.clone() });
+                    }
+                    let element = self.element(columns[0].1)
+                        .ok_or_else(|| ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line: line.clone() })?;
+                    atoms.push(Atom{ element, coordinate: Self::coordinate(&columns, path, line_number)?, record: None, selective: None });
+                }
+                if atoms.is_empty() {
+                    return Err(ParseError::TruncatedBlock{ location: Location::new(path, route, 0), expected: None, found: 0 });
+                }
+                blocks.push(molecule(energy, atoms));
+                // the next job
+                while index < lines.len() && !lines[index].trim().eq_ignore_ascii_case("--Link1--") {
+                    index += 1;
+                }
+                index += 1;
+            }
+            Ok(blocks)
+        }
+    }
+    impl Default for GaussianParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl StructureIO for GaussianParser {
+        // outputs are only read at their final geometry, everything is handled in `parse_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            let blocks = self.parse_structure(input, ignore_parse_error)?.unwrap_or_default();
+            Ok(StructureIterator::new(blocks.into_iter().map(Ok)))
+        }
+        fn parse_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+            let lines = open_reader(input).map_err(ParseError::IoError)?
+                .lines()
+                .collect::<Result<Vec<String>, io::Error>>()
+                .map_err(ParseError::IoError)?;
+            let blocks = match self.parse_log(&lines, Some(input)) {
+                Ok(Some(block)) => Ok(vec![block]),
+                Ok(None) => self.parse_input(&lines, Some(input)),
+                Err(e) => Err(e),
+            };
+            match blocks {
+                Ok(blocks) => Ok(Some(blocks)),
+                Err(_) if ignore_parse_error => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let mut file = CompressedWriter::create(path)?;
+            for (i, block) in structures.iter().enumerate() {
+                if i > 0 {
+                    writeln!(file, "--Link1--")?;
+                }
+                writeln!(file, "{}", self.route)?;
+                writeln!(file)?;
+                writeln!(file, "Structure {} Energy: {}", i + 1, block.energy)?;
+                writeln!(file)?;
+                writeln!(file, "0 {}", multiplicity(&self.table, &block.atoms)?)?;
+                for atom in block.atoms.iter() {
+                    writeln!(file, " {: <3} {: >16.10} {: >16.10} {: >16.10}", atom.element, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2)?;
+                }
+                writeln!(file)?;
+            }
+            file.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::{NamedTempFile, TempDir};
+
+    use crate::gaussian::gaussian::*;
+    use crate::parser::parser::*;
+
+    const DASHES: &str = " ---------------------------------------------------------------------";
+
+    fn orientation(file: &mut NamedTempFile, title: &str, z: f64) {
+        writeln!(file, "                         {}", title).unwrap();
+        writeln!(file, "{}", DASHES).unwrap();
+        writeln!(file, " Center     Atomic      Atomic             Coordinates (Angstroms)").unwrap();
+        writeln!(file, " Number     Number       Type             X           Y           Z").unwrap();
+        writeln!(file, "{}", DASHES).unwrap();
+        writeln!(file, "      1          8           0        0.000000    0.000000    {:.6}", z).unwrap();
+        writeln!(file, "      2          1           0        0.000000    0.763239   -0.477047").unwrap();
+        writeln!(file, "      3          1           0        0.000000   -0.763239   -0.477047").unwrap();
+        writeln!(file, "{}", DASHES).unwrap();
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, " Entering Gaussian System, Link 0=g16").unwrap();
+        orientation(&mut file, "Input orientation:", 0.5);
+        orientation(&mut file, "Standard orientation:", 0.2);
+        writeln!(file, " SCF Done:  E(RB3LYP) =  -76.4000000000     A.U. after   10 cycles").unwrap();
+        orientation(&mut file, "Input orientation:", 0.5);
+        orientation(&mut file, "Standard orientation:", 0.119262);
+        writeln!(file, " SCF Done:  E(RB3LYP) =  -76.4089000000     A.U. after    5 cycles").unwrap();
+        writeln!(file, " Normal termination of Gaussian 16").unwrap();
+        let blocks = GaussianParser::new().parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert!((blocks[0].energy + 76.4089 * HARTREE).abs() < 1e-6);
+        assert_eq!(blocks[0].crystal.x, 0.0);
+        assert_eq!(blocks[0].atoms.iter().map(|a| a.element.as_str()).collect::<Vec<&str>>(), ["O", "H", "H"]);
+        assert!((blocks[0].atoms[0].coordinate.2 - 0.119262).abs() < 1e-9);
+        assert_eq!(detect_format(file.path()).unwrap(), "gaussian");
+    }
+
+    #[test]
+    fn test_parse_log_fail() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, " Entering Gaussian System, Link 0=g16").unwrap();
+        writeln!(file, "                         Standard orientation:").unwrap();
+        writeln!(file, "{}", DASHES).unwrap();
+        writeln!(file, " Center     Atomic      Atomic             Coordinates (Angstroms)").unwrap();
+        writeln!(file, " Number     Number       Type             X           Y           Z").unwrap();
+        writeln!(file, "{}", DASHES).unwrap();
+        writeln!(file, "      1          8           0        0.000000    0.0x0000    0.119262").unwrap();
+        let err = GaussianParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::BadFloat{ ref location, .. } if location.line == 7 && location.column == 51), "Got {:?}", err);
+        assert!(GaussianParser::new().parse_structure(file.path(), true).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_gjf_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "%chk=water.chk").unwrap();
+        writeln!(file, "#p opt b3lyp/6-31g(d)").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "water Energy: -2.5").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "0 1").unwrap();
+        writeln!(file, "O(Fragment=1)  0.0  0.0  0.1").unwrap();
+        writeln!(file, "1  0  0.0  0.76 -0.48").unwrap();
+        writeln!(file, "H1    0.0 -0.76 -0.48").unwrap();
+        writeln!(file).unwrap();
+        let blocks = GaussianParser::new().parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks[0].energy, -2.5);
+        assert_eq!(blocks[0].atoms.iter().map(|a| a.element.as_str()).collect::<Vec<&str>>(), ["O", "H", "H"]);
+        assert!((blocks[0].atoms[1].coordinate.1 - 0.76).abs() < 1e-9);
+
+        let mut radical = blocks[0].clone();
+        radical.atoms.pop();
+        let path = dir.path().join("water.gjf");
+        GaussianParser::new().write_structure(&[blocks[0].clone(), radical], &path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("\n\n0 1\n") && content.contains("--Link1--\n") && content.contains("\n\n0 2\n"), "{}", content);
+        let written = GaussianParser::new().parse_structure(&path, false).unwrap().unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[1].atoms.len(), 2);
+        assert_eq!(written[0].energy, -2.5);
+        assert!((written[0].atoms[2].coordinate.1 + 0.76).abs() < 1e-9);
+    }
+}
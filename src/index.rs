@@ -0,0 +1,292 @@
+pub mod arc_index {
+    //! random access to the blocks of an .arc file
+    use std::fs::{self, File};
+    use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+    use std::ops::Range;
+    use std::path::{Path, PathBuf};
+    use std::time::UNIX_EPOCH;
+
+    use crate::compression::compression::Compression;
+    use crate::modules::structures::StructureBlock;
+    use crate::parser::parser::{ArcParser, ParseError, StructureIO, StructureIterator};
+
+    /// first line of a sidecar index file, bumped whenever the layout changes
+    const INDEX_VERSION: &str = "arc_parser index 2";
+
+    /// where a block starts in an .arc file
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct IndexEntry {
+        /// byte offset of the block header
+        pub offset: u64,
+        /// line number of the block header, starting from 1
+        pub line: usize,
+        /// the structure number written in the block header
+        pub number: u64,
+        /// the periodicity declared before the block
+        pub periodic: bool,
+        /// whether an end line came before the block
+        pub uses_block_end: bool,
+    }
+
+    /**
+    byte offsets of the block headers of an .arc file
+
+    the file is scanned once, then `get(n)` and `range(a..b)` seek to the blocks and parse only them,
+    with the periodicity and end lines seen before them, as `parse_parallel` does.
+    Blocks are counted by their position in the file, starting from 0.
+    `open` keeps the offsets in a sidecar `<file>.idx`, which is rebuilt when the size or
+    modification time of the .arc file changes.
+     */
+    pub struct ArcIndex {
+        path: PathBuf,
+        entries: Vec<IndexEntry>,
+        /// size of the indexed file, the end of the last block
+        size: u64,
+        parser: ArcParser,
+    }
+    impl ArcIndex {
+        /// scan the block headers of a file, without touching the sidecar
+        pub fn build(path: &Path) -> Result<Self, ParseError> {
+            // offsets into a compressed stream can not be seeked to
+            if Compression::from_path(path) != Compression::None {
+                return Err(ParseError::IoError(io::Error::new(io::ErrorKind::Unsupported, format!("{} is compressed and can not be indexed", path.display()))));
+            }
+            let parser = ArcParser::new();
+            let mut reader = BufReader::new(File::open(path).map_err(ParseError::IoError)?);
+            let mut entries = Vec::new();
+            let mut buffer = Vec::new();
+            let mut offset = 0;
+            let mut line = 0;
+            let (mut periodic, mut uses_block_end) = (false, false);
+            loop {
+                buffer.clear();
+                let length = reader.read_until(b'\n', &mut buffer).map_err(ParseError::IoError)?;
+                if length == 0 {
+                    break;
+                }
+                line += 1;
+                let text = String::from_utf8_lossy(&buffer);
+                let text = text.trim_end_matches(['\n', '\r']);
+                // a cheap check first, most lines are atoms
+                if text.contains("Energy") {
+                    if let Ok(Some(header)) = parser.parse_header(text) {
+                        entries.push(IndexEntry{ offset, line, number: header.number, periodic, uses_block_end });
+                    }
+                } else if let Some(declared) = parser.parse_periodicity(text) {
+                    periodic = declared;
+                } else if parser.is_block_end(text) {
+                    uses_block_end = true;
+                }
+                offset += length as u64;
+            }
+            Ok(Self { path: path.to_path_buf(), entries, size: offset, parser })
+        }
+
+        /// load the sidecar index of a file if it is up to date, otherwise scan the file and save the sidecar
+        ///
+        /// failing to save the sidecar, e.g. in a read-only directory, is not an error
+        pub fn open(path: &Path) -> Result<Self, ParseError> {
+            let stamp = file_stamp(path).map_err(ParseError::IoError)?;
+            let sidecar = Self::sidecar_path(path);
+            if let Some(index) = Self::load(path, &sidecar, &stamp) {
+                return Ok(index);
+            }
+            let index = Self::build(path)?;
+            // the file may change while it is scanned, then the sidecar is left stale on purpose
+            if file_stamp(path).ok().as_ref() == Some(&stamp) && stamp.1.is_some() {
+                let _ = index.save(&sidecar, &stamp);
+            }
+            Ok(index)
+        }
+
+        /// path of the sidecar index of a file, `all.arc` is indexed in `all.arc.idx`
+        pub fn sidecar_path(path: &Path) -> PathBuf {
+            let mut sidecar = path.as_os_str().to_owned();
+            sidecar.push(".idx");
+            PathBuf::from(sidecar)
+        }
+
+        /// read a sidecar, `None` if it is missing, malformed or out of date
+        fn load(path: &Path, sidecar: &Path, stamp: &(u64, Option<u128>)) -> Option<Self> {
+            let content = fs::read_to_string(sidecar).ok()?;
+            let mut lines = content.lines();
+            if lines.next()? != INDEX_VERSION {
+                return None;
+            }
+            let size = lines.next()?.strip_prefix("size ")?.parse::<u64>().ok()?;
+            let mtime = lines.next()?.strip_prefix("mtime ")?.parse::<u128>().ok()?;
+            if (size, Some(mtime)) != *stamp {
+                return None;
+            }
+            let entries = lines.map(|line| {
+                let mut columns = line.split_whitespace().map(|s| s.parse::<u64>().ok());
+                Some(IndexEntry{
+                    offset: columns.next()??,
+                    line: columns.next()?? as usize,
+                    number: columns.next()??,
+                    periodic: columns.next()?? != 0,
+                    uses_block_end: columns.next()?? != 0,
+                })
+            }).collect::<Option<Vec<IndexEntry>>>()?;
+            Some(Self { path: path.to_path_buf(), entries, size, parser: ArcParser::new() })
+        }
+
+        fn save(&self, sidecar: &Path, stamp: &(u64, Option<u128>)) -> io::Result<()> {
+            let mut file = io::BufWriter::new(File::create(sidecar)?);
+            writeln!(file, "{}", INDEX_VERSION)?;
+            writeln!(file, "size {}", stamp.0)?;
+            writeln!(file, "mtime {}", stamp.1.unwrap_or_default())?;
+            for entry in self.entries.iter() {
+                writeln!(file, "{} {} {} {} {}", entry.offset, entry.line, entry.number, entry.periodic as u8, entry.uses_block_end as u8)?;
+            }
+            file.flush()
+        }
+
+        /// the number of blocks in the file
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+        pub fn entries(&self) -> &[IndexEntry] {
+            &self.entries
+        }
+        /// positions of the blocks whose header carries the given structure number
+        pub fn positions_of(&self, number: u64) -> impl Iterator<Item = usize> + '_ {
+            self.entries.iter().enumerate().filter(move |(_, e)| e.number == number).map(|(i, _)| i)
+        }
+
+        /// parse the block at position `n`, `None` if it is out of range
+        pub fn get(&self, n: usize) -> Result<Option<StructureBlock>, ParseError> {
+            if n >= self.len() {
+                return Ok(None);
+            }
+            self.range(n..n + 1)?.next().transpose()
+        }
+
+        /**
+        lazily parse the blocks at positions `a..b`
+
+        the range is clamped to the blocks in the file. Errors are located in the whole file, as for `ArcParser`
+         */
+        pub fn range(&self, range: Range<usize>) -> Result<StructureIterator<'_>, ParseError> {
+            let end = range.end.min(self.len());
+            if range.start >= end {
+                return Ok(StructureIterator::new(std::iter::empty()));
+            }
+            let first = &self.entries[range.start];
+            let stop = self.entries.get(end).map_or(self.size, |e| e.offset);
+            let mut file = File::open(&self.path).map_err(ParseError::IoError)?;
+            file.seek(SeekFrom::Start(first.offset)).map_err(ParseError::IoError)?;
+            let reader = BufReader::new(file.take(stop - first.offset));
+            Ok(StructureIterator::from_lines_at(&self.parser, reader, Some(&self.path), false, first.line, first.periodic, first.uses_block_end))
+        }
+    }
+
+    /// size and modification time (in nanoseconds) of a file, the modification time may be unavailable
+    fn file_stamp(path: &Path) -> io::Result<(u64, Option<u128>)> {
+        let metadata = fs::metadata(path)?;
+        let mtime = metadata.modified().ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos());
+        Ok((metadata.len(), mtime))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    use crate::index::arc_index::*;
+    use crate::parser::parser::ParseError;
+
+    fn write_arc(path: &std::path::Path, blocks: usize) {
+        let mut file = std::fs::File::create(path).unwrap();
+        writeln!(file, "!BIOSYM archive 2").unwrap();
+        writeln!(file, "PBC=ON").unwrap();
+        for i in 0..blocks {
+            writeln!(file, "   Energy         {}          0.0099      -362{}.679360        C1", i + 1, i).unwrap();
+            writeln!(file, "!DATE").unwrap();
+            writeln!(file, "PBC   20.19500000   20.19500000   29.51410000   90.00000000   90.00000000  120.00000000").unwrap();
+            for j in 0..=i {
+                writeln!(file, "C        7.210469000   10.148070000    {}.813536200 CORE    1 C  C    0.0000    1", j).unwrap();
+            }
+            writeln!(file, "end").unwrap();
+            writeln!(file, "end").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_get_and_range() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("all.arc");
+        write_arc(&path, 4);
+        let index = ArcIndex::build(&path).unwrap();
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.entries()[0], IndexEntry{ offset: 25, line: 3, number: 1, periodic: true, uses_block_end: false });
+        assert_eq!(index.entries()[1], IndexEntry{ offset: 272, line: 9, number: 2, periodic: true, uses_block_end: true });
+        let block = index.get(2).unwrap().unwrap();
+        assert_eq!(block.energy, -3622.67936);
+        assert_eq!(block.atoms.len(), 3);
+        assert!((block.atoms[2].coordinate.2 - 2.8135362).abs() < 1e-9);
+        assert!(index.get(4).unwrap().is_none());
+        let energies = index.range(1..10).unwrap().map(|b| b.unwrap().energy).collect::<Vec<f64>>();
+        assert_eq!(energies, vec![-3621.67936, -3622.67936, -3623.67936]);
+        assert_eq!(index.range(5..8).unwrap().count(), 0);
+        assert_eq!(index.positions_of(2).collect::<Vec<usize>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_sidecar() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("all.arc");
+        write_arc(&path, 2);
+        let index = ArcIndex::open(&path).unwrap();
+        let sidecar = ArcIndex::sidecar_path(&path);
+        assert_eq!(sidecar, dir.path().join("all.arc.idx"));
+        assert!(sidecar.exists());
+        // the sidecar is used as long as the file is unchanged
+        assert_eq!(ArcIndex::open(&path).unwrap().entries(), index.entries());
+        // a changed file invalidates the sidecar
+        write_arc(&path, 3);
+        let index = ArcIndex::open(&path).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get(2).unwrap().unwrap().atoms.len(), 3);
+    }
+
+    #[test]
+    fn test_state_before_block() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("all.arc");
+        write_arc(&path, 3);
+        let lines = std::fs::read_to_string(&path).unwrap().lines().map(String::from).collect::<Vec<String>>();
+        // the second block has no cell, although the file declared PBC=ON before it
+        let without_cell = lines.iter().enumerate().filter(|(i, _)| *i != 10).map(|(_, l)| format!("{}\n", l)).collect::<String>();
+        std::fs::write(&path, without_cell).unwrap();
+        let err = ArcIndex::build(&path).unwrap().get(1).unwrap_err();
+        assert!(matches!(err, ParseError::MissingCell{ ref location } if location.line == 9), "Got {:?}", err);
+        // the second block is not closed, although the first one was
+        let truncated = lines.iter().enumerate().filter(|(i, _)| *i != 13 && *i != 14).map(|(_, l)| format!("{}\n", l)).collect::<String>();
+        std::fs::write(&path, truncated).unwrap();
+        let index = ArcIndex::build(&path).unwrap();
+        assert!(index.get(0).unwrap().is_some());
+        let err = index.get(1).unwrap_err();
+        assert!(matches!(err, ParseError::TruncatedBlock{ ref location, found: 2, .. } if location.line == 9), "Got {:?}", err);
+    }
+
+    #[test]
+    fn test_error_location() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("broken.arc");
+        write_arc(&path, 2);
+        let content = std::fs::read_to_string(&path).unwrap().replace("1.813536200", "1.81x536200");
+        std::fs::write(&path, content).unwrap();
+        let index = ArcIndex::build(&path).unwrap();
+        assert!(index.get(0).unwrap().is_some());
+        // the error is located in the whole file, not in the block
+        let err = index.get(1).unwrap_err();
+        assert!(matches!(err, ParseError::BadFloat{ ref location, .. } if location.line == 13 && location.path.as_deref() == Some(path.as_path())), "Got {:?}", err);
+    }
+}
@@ -0,0 +1,702 @@
+pub mod lammps {
+    //! reader and writer of LAMMPS data files and custom dump trajectories
+    //!
+    //! LAMMPS boxes have a along x and b in the xy plane, the standard orientation of `CrystalInfo`,
+    //! so the lattice vectors are `(lx, 0, 0)`, `(xy, ly, 0)` and `(xz, yz, lz)`.
+    //! Atoms are typed by numbers, the elements of the types are given with `with_types`:
+    //! type 1 is the first element and so on
+    use std::io::{self, BufRead, Write};
+    use std::iter::Peekable;
+    use std::path::Path;
+    use nalgebra::{Matrix3, RowVector3};
+
+    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::modules::periodic_table::PeriodicTable;
+    use crate::compression::compression::{open_reader, CompressedWriter};
+    use crate::parser::parser::{StructureIO, StructureIterator, ParseError, Location, split_columns, parse_float};
+
+    /// the element of an atom type: the given element, the element of its mass, or the type itself
+    fn element_of_type(types: &[String], atom_type: &str, mass: Option<f64>, table: &PeriodicTable) -> String {
+        let given = atom_type.parse::<usize>().ok()
+            .and_then(|t| t.checked_sub(1))
+            .and_then(|t| types.get(t));
+        if let Some(element) = given {
+            return element.clone();
+        }
+        // masses are rounded differently by every tool, the closest element within 0.1 u is taken
+        let by_mass = mass.and_then(|mass| (1..=118)
+            .filter_map(|z| table.symbol(z).and_then(|s| table.get(s).map(|e| (s, (e.mass - mass).abs()))))
+            .filter(|(_, difference)| *difference < 0.1)
+            .min_by(|a, b| a.1.total_cmp(&b.1)));
+        match by_mass {
+            Some((symbol, _)) => symbol.to_string(),
+            None => atom_type.to_string(),
+        }
+    }
+
+    /// the elements of the types of the blocks: the given ones first, then the others in order of appearance
+    fn types_of(types: &[String], blocks: &[StructureBlock]) -> Vec<String> {
+        let mut types = types.to_vec();
+        for atom in blocks.iter().flat_map(|b| b.atoms.iter()) {
+            if !types.contains(&atom.element) {
+                types.push(atom.element.clone());
+            }
+        }
+        types
+    }
+
+    /// the box of a block: lengths and tilt factors `(lx, ly, lz, xy, xz, yz)`, LAMMPS boxes are always periodic
+    fn box_of(block: &StructureBlock) -> io::Result<(f64, f64, f64, f64, f64, f64)> {
+        let lattice = block.periodic_lattice()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("block {} with energy {} has no periodic cell", block.number, block.energy)))?;
+        let lattice = lattice.matrix();
+        Ok((lattice[(0, 0)], lattice[(1, 1)], lattice[(2, 2)], lattice[(1, 0)], lattice[(2, 0)], lattice[(2, 1)]))
+    }
+
+    fn lattice_of(lengths: [f64; 3], tilts: [f64; 3]) -> Matrix3<f64> {
+        let [xy, xz, yz] = tilts;
+        Matrix3::new(
+            lengths[0], 0.0, 0.0,
+            xy, lengths[1], 0.0,
+            xz, yz, lengths[2]
+        )
+    }
+
+    /// parse the integer of a column, reported as a bad number
+    fn parse_integer<T: std::str::FromStr>(text: &str, column: usize) -> Result<T, ParseError> {
+        text.parse::<T>().map_err(|_| ParseError::BadFloat{ location: Location::new(None, 0, column), text: text.to_string() })
+    }
+
+    /// the atom styles of the `Atoms` section of data files
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum AtomStyle {
+        /// `id type x y z`
+        Atomic,
+        /// `id type q x y z`
+        Charge,
+        /// `id molecule type x y z`
+        Molecular,
+        /// `id molecule type q x y z`
+        Full,
+    }
+    impl AtomStyle {
+        fn from_name(name: &str) -> Option<Self> {
+            match name {
+                "atomic" => Some(AtomStyle::Atomic),
+                "charge" => Some(AtomStyle::Charge),
+                "molecular" => Some(AtomStyle::Molecular),
+                "full" => Some(AtomStyle::Full),
+                _ => None,
+            }
+        }
+        /// the style of an atom line without style comment, from its column count with or without image flags.
+        /// Six columns are either the charge or the molecular style, these need the `Atoms # style` comment
+        fn from_columns(count: usize) -> Option<Self> {
+            match count {
+                5 | 8 => Some(AtomStyle::Atomic),
+                7 | 10 => Some(AtomStyle::Full),
+                _ => None,
+            }
+        }
+        /// the columns of the type and of x
+        fn columns(&self) -> (usize, usize) {
+            match self {
+                AtomStyle::Atomic => (1, 2),
+                AtomStyle::Charge => (1, 3),
+                AtomStyle::Molecular => (2, 3),
+                AtomStyle::Full => (2, 4),
+            }
+        }
+    }
+
+    /**
+    parser for LAMMPS data files
+
+    a data file holds one structure. The atomic, charge, molecular and full styles of the `Atoms` section are read,
+    the style is taken from the `Atoms # style` comment or from the column count, which needs the comment for the
+    charge and molecular styles. Without given types, the elements are found from the `Masses` section.
+    The energy is kept in the comment line as `Energy: <value>`, the same way `PoscarParser` does.
+    Structures are written in the atomic style, only periodic blocks with a valid cell can be written.
+     */
+    pub struct LammpsDataParser {
+        types: Vec<String>,
+        table: PeriodicTable,
+    }
+    impl LammpsDataParser {
+        pub fn new() -> Self {
+            Self { types: Vec::new(), table: PeriodicTable::new() }
+        }
+        /// set the elements of the atom types, type 1 is the first element
+        pub fn with_types(mut self, types: Vec<String>) -> Self {
+            self.types = types;
+            self
+        }
+
+        /// parse the lines of a data file into a `StructureBlock`, `path` is only used to locate parse errors
+        fn parse_lines(&self, lines: &[String], path: Option<&Path>) -> Result<StructureBlock, ParseError> {
+            let unexpected = |index: usize| ParseError::UnexpectedLine{ location: Location::new(path, index + 1, 1), line: lines[index].clone() };
+            let mut count = None;
+            let mut lengths = None::<[f64; 3]>;
+            let mut origin = RowVector3::zeros();
+            let mut tilts = [0.0; 3];
+            let mut masses: Vec<(String, f64)> = Vec::new();
+            // atom lines with their line index, read once the masses are known
+            let mut atom_lines: Vec<(usize, Vec<(usize, &str)>)> = Vec::new();
+            let mut style = None;
+            let mut section: Option<&str> = None;
+            for (index, line) in lines.iter().enumerate().skip(1) {
+                let (content, comment) = match line.split_once('#') {
+                    Some((content, comment)) => (content, Some(comment.trim())),
+                    None => (line.as_str(), None),
+                };
+                let columns = split_columns(content);
+                let Some((_, first)) = columns.first() else {
+                    continue;
+                };
+                let floats = |values: &[(usize, &str)]| -> Result<Vec<f64>, ParseError> {
+                    values.iter().map(|(column, text)| parse_float(text, *column).map_err(|e| e.at(path, index + 1))).collect()
+                };
+                // a section starts with its name, the header has numbers first
+                if first.parse::<f64>().is_err() {
+                    section = Some(match content.trim() {
+                        "Masses" => "Masses",
+                        "Atoms" => {
+                            style = comment.map(|c| AtomStyle::from_name(c).ok_or_else(|| unexpected(index))).transpose()?;
+                            "Atoms"
+                        },
+                        _ => "Other",
+                    });
+                    continue;
+                }
+                match section {
+                    None => {
+                        let keywords = columns.iter().skip_while(|(_, t)| t.parse::<f64>().is_ok()).map(|(_, t)| *t).collect::<Vec<&str>>();
+                        match keywords.as_slice() {
+                            ["atoms"] => count = Some(parse_integer::<usize>(first, columns[0].0).map_err(|e| e.at(path, index + 1))?),
+                            [lo @ ("xlo" | "ylo" | "zlo"), _] if columns.len() == 4 => {
+                                let values = floats(&columns[..2])?;
+                                let axis = ["xlo", "ylo", "zlo"].iter().position(|a| a == lo).unwrap();
+                                origin[axis] = values[0];
+                                let mut l = lengths.unwrap_or_default();
+                                l[axis] = values[1] - values[0];
+                                lengths = Some(l);
+                            },
+                            ["xy", "xz", "yz"] if columns.len() == 6 => {
+                                let values = floats(&columns[..3])?;
+                                tilts = [values[0], values[1], values[2]];
+                            },
+                            // bonds, atom types and the other counts are not needed
+                            _ => (),
+                        }
+                    },
+                    Some("Masses") if columns.len() >= 2 => masses.push((first.to_string(), floats(&columns[1..2])?[0])),
+                    Some("Atoms") => atom_lines.push((index, columns)),
+                    _ => (),
+                }
+            }
+            let location = Location::new(path, 1, 0);
+            let lengths = lengths.ok_or(ParseError::MissingCell{ location: location.clone() })?;
+            let mut atoms: Vec<(u64, Atom)> = Vec::with_capacity(atom_lines.len());
+            for (index, columns) in atom_lines.iter() {
+                let style = style.or_else(|| AtomStyle::from_columns(columns.len())).ok_or_else(|| unexpected(*index))?;
+                let (type_column, x_column) = style.columns();
+                if columns.len() < x_column + 3 {
+                    return Err(unexpected(*index));
+                }
+                let id = parse_integer::<u64>(columns[0].1, columns[0].0).map_err(|e| e.at(path, index + 1))?;
+                let atom_type = columns[type_column].1;
+                let mass = masses.iter().find(|(t, _)| t == atom_type).map(|(_, m)| *m);
+                let mut position = RowVector3::zeros();
+                for i in 0..3 {
+                    let (column, text) = columns[x_column + i];
+                    position[i] = parse_float(text, column).map_err(|e| e.at(path, index + 1))?;
+                }
+                let position = position - origin;
+                atoms.push((id, Atom{
+                    element: element_of_type(&self.types, atom_type, mass, &self.table),
+                    coordinate: Coordinate(position[0], position[1], position[2]),
+                    record: None,
+                    selective: None
+                }));
+            }
+            if let Some(count) = count.filter(|c| *c != atoms.len()) {
+                return Err(ParseError::TruncatedBlock{ location, expected: Some(count), found: atoms.len() });
+            }
+            atoms.sort_by_key(|(id, _)| *id);
+            let energy = lines.first()
+                .and_then(|comment| comment.split("Energy:").nth(1))
+                .and_then(|s| s.split_whitespace().next())
+                .and_then(|s| s.parse::<f64>().ok())
+                .unwrap_or(0.0);
+            Ok(StructureBlock{
+                number: 0,
+                energy,
+                symmetry: String::from("C1"),
+                crystal: CrystalInfo::from_lattice_vectors(&lattice_of(lengths, tilts)),
+                atoms: atoms.into_iter().map(|(_, atom)| atom).collect(),
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
+            })
+        }
+    }
+    impl Default for LammpsDataParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl StructureIO for LammpsDataParser {
+        // a data file is made of sections, everything is handled in `parse_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            // a data file holds a single structure, there is nothing to stream
+            let blocks = self.parse_structure(input, ignore_parse_error)?.unwrap_or_default();
+            Ok(StructureIterator::new(blocks.into_iter().map(Ok)))
+        }
+        fn parse_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+            let lines = open_reader(input).map_err(ParseError::IoError)?
+                .lines()
+                .collect::<Result<Vec<String>, io::Error>>()
+                .map_err(ParseError::IoError)?;
+            match self.parse_lines(&lines, Some(input)) {
+                Ok(block) => Ok(Some(vec![block])),
+                Err(_) if ignore_parse_error => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let block = match structures {
+                [block] => block,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("a LAMMPS data file holds exactly one structure, got {}", structures.len()))),
+            };
+            let types = types_of(&self.types, structures);
+            let (lx, ly, lz, xy, xz, yz) = box_of(block)?;
+            let mut file = CompressedWriter::create(path)?;
+            writeln!(file, "Energy: {}", block.energy)?;
+            writeln!(file)?;
+            writeln!(file, "{} atoms", block.atoms.len())?;
+            writeln!(file, "{} atom types", types.len())?;
+            writeln!(file)?;
+            writeln!(file, "{: >16.10} {: >16.10} xlo xhi", 0.0, lx)?;
+            writeln!(file, "{: >16.10} {: >16.10} ylo yhi", 0.0, ly)?;
+            writeln!(file, "{: >16.10} {: >16.10} zlo zhi", 0.0, lz)?;
+            if [xy, xz, yz].iter().any(|t| t.abs() > 1e-10) {
+                writeln!(file, "{: >16.10} {: >16.10} {: >16.10} xy xz yz", xy, xz, yz)?;
+            }
+            writeln!(file)?;
+            writeln!(file, "Masses")?;
+            writeln!(file)?;
+            for (i, element) in types.iter().enumerate() {
+                // elements unknown to the periodic table get no mass, LAMMPS asks for it then
+                if let Some(mass) = self.table.get(element).map(|e| e.mass) {
+                    writeln!(file, "{: >4} {: >12.6} # {}", i + 1, mass, element)?;
+                }
+            }
+            writeln!(file)?;
+            writeln!(file, "Atoms # atomic")?;
+            writeln!(file)?;
+            for (i, atom) in block.atoms.iter().enumerate() {
+                let atom_type = types.iter().position(|t| *t == atom.element).unwrap() + 1;
+                writeln!(file, "{: >6} {: >4} {: >18.10} {: >18.10} {: >18.10}", i + 1, atom_type, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2)?;
+            }
+            file.finish()
+        }
+    }
+
+    /**
+    parser for LAMMPS custom dump trajectories
+
+    every frame is a structure numbered by its timestep. Atoms need `x y z`, `xu yu zu` or the scaled `xs ys zs`
+    columns. Atoms are sorted by their `id` column, and their element is taken from the `element` column,
+    from the given types or else is the type itself. `fx fy fz` columns are kept as forces.
+    Structures are written with the `id type element x y z` columns, and forces if they have them
+     */
+    pub struct LammpsDumpParser {
+        types: Vec<String>,
+        table: PeriodicTable,
+    }
+    impl LammpsDumpParser {
+        pub fn new() -> Self {
+            Self { types: Vec::new(), table: PeriodicTable::new() }
+        }
+        /// set the elements of the atom types, type 1 is the first element
+        pub fn with_types(mut self, types: Vec<String>) -> Self {
+            self.types = types;
+            self
+        }
+
+        /// parse a frame starting at its `ITEM: TIMESTEP` line
+        fn parse_frame<I>(&self, start: usize, lines: &mut Peekable<I>, path: Option<&Path>) -> Result<StructureBlock, ParseError>
+        where
+            I: Iterator<Item = (usize, io::Result<String>)>,
+        {
+            let location = Location::new(path, start, 0);
+            let mut found = 0;
+            let (line_number, line) = next_line(lines, &location, None, 0)?;
+            let number = parse_integer::<u64>(line.trim(), 1).map_err(|e| e.at(path, line_number))?;
+            let mut count = None;
+            let mut lattice = None;
+            loop {
+                let (line_number, line) = next_line(lines, &location, count, 0)?;
+                let unexpected = || ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line: line.clone() };
+                let item = line.trim().strip_prefix("ITEM:").ok_or_else(unexpected)?.trim();
+                if item.starts_with("NUMBER OF ATOMS") {
+                    let (line_number, line) = next_line(lines, &location, None, 0)?;
+                    count = Some(parse_integer::<usize>(line.trim(), 1).map_err(|e| e.at(path, line_number))?);
+                }
+                else if item.starts_with("BOX BOUNDS") {
+                    let triclinic = item.contains("xy");
+                    let mut bounds = [[0.0; 3]; 3];
+                    for bound in bounds.iter_mut() {
+                        let (line_number, line) = next_line(lines, &location, count, 0)?;
+                        let values = split_columns(&line).into_iter()
+                            .map(|(column, text)| parse_float(text, column).map_err(|e| e.at(path, line_number)))
+                            .collect::<Result<Vec<f64>, ParseError>>()?;
+                        match (values.as_slice(), triclinic) {
+                            ([lo, hi, tilt], true) => *bound = [*lo, *hi, *tilt],
+                            ([lo, hi], false) => *bound = [*lo, *hi, 0.0],
+                            _ => return Err(ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line }),
+                        }
+                    }
+                    // the bounds of a triclinic box enclose the tilted box
+                    let [xy, xz, yz] = [bounds[0][2], bounds[1][2], bounds[2][2]];
+                    let lx = (bounds[0][1] - [0.0, xy, xz, xy + xz].into_iter().fold(0.0, f64::max)) - (bounds[0][0] - [0.0, xy, xz, xy + xz].into_iter().fold(0.0, f64::min));
+                    let ly = (bounds[1][1] - yz.max(0.0)) - (bounds[1][0] - yz.min(0.0));
+                    let lz = bounds[2][1] - bounds[2][0];
+                    let origin = RowVector3::new(bounds[0][0] - [0.0, xy, xz, xy + xz].into_iter().fold(0.0, f64::min), bounds[1][0] - yz.min(0.0), bounds[2][0]);
+                    lattice = Some((lattice_of([lx, ly, lz], [xy, xz, yz]), origin));
+                }
+                else if let Some(names) = item.strip_prefix("ATOMS") {
+                    let names = names.split_whitespace().collect::<Vec<&str>>();
+                    let column = |name: &str| names.iter().position(|n| *n == name);
+                    let (positions, scaled) = [(["x", "y", "z"], false), (["xu", "yu", "zu"], false), (["xs", "ys", "zs"], true), (["xsu", "ysu", "zsu"], true)]
+                        .into_iter()
+                        .find_map(|(axes, scaled)| Some(([column(axes[0])?, column(axes[1])?, column(axes[2])?], scaled)))
+                        .ok_or_else(unexpected)?;
+                    let forces = (|| Some([column("fx")?, column("fy")?, column("fz")?]))();
+                    let (id, atom_type, element) = (column("id"), column("type"), column("element"));
+                    let count = count.ok_or_else(unexpected)?;
+                    let (lattice, origin) = lattice.ok_or(ParseError::MissingCell{ location: location.clone() })?;
+                    let mut atoms: Vec<(u64, Atom, Option<Coordinate>)> = Vec::with_capacity(count);
+                    while found < count {
+                        let (line_number, line) = next_line(lines, &location, Some(count), found)?;
+                        let columns = split_columns(&line);
+                        if columns.len() < names.len() {
+                            return Err(ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line });
+                        }
+                        let float = |i: usize| parse_float(columns[i].1, columns[i].0).map_err(|e| e.at(path, line_number));
+                        let mut position = RowVector3::new(float(positions[0])?, float(positions[1])?, float(positions[2])?);
+                        position = if scaled { position * lattice } else { position - origin };
+                        let force = match forces {
+                            Some([x, y, z]) => Some(Coordinate(float(x)?, float(y)?, float(z)?)),
+                            None => None,
+                        };
+                        let element = match (element, atom_type) {
+                            (Some(e), _) => columns[e].1.to_string(),
+                            (None, Some(t)) => element_of_type(&self.types, columns[t].1, None, &self.table),
+                            (None, None) => return Err(ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line }),
+                        };
+                        let id = match id {
+                            Some(i) => parse_integer::<u64>(columns[i].1, columns[i].0).map_err(|e| e.at(path, line_number))?,
+                            None => found as u64,
+                        };
+                        atoms.push((id, Atom{ element, coordinate: Coordinate(position[0], position[1], position[2]), record: None, selective: None }, force));
+                        found += 1;
+                    }
+                    atoms.sort_by_key(|(id, _, _)| *id);
+                    return Ok(StructureBlock{
+                        number,
+                        energy: 0.0,
+                        symmetry: String::from("C1"),
+                        crystal: CrystalInfo::from_lattice_vectors(&lattice),
+                        periodic: true,
+                        forces: forces.map(|_| atoms.iter().filter_map(|(_, _, f)| f.clone()).collect()),
+                        atoms: atoms.into_iter().map(|(_, atom, _)| atom).collect(),
+                        record: None,
+                        stress: None
+                    });
+                }
+                else if item.starts_with("TIMESTEP") {
+                    // the next frame begins before this one has atoms
+                    return Err(ParseError::TruncatedBlock{ location, expected: count, found: 0 });
+                }
+                else {
+                    // other items such as TIME or UNITS are skipped with their values
+                    while lines.next_if(|(_, line)| line.as_ref().is_ok_and(|l| !l.trim_start().starts_with("ITEM:"))).is_some() {}
+                }
+            }
+        }
+    }
+    /// the next line of a frame with its line number, the frame is truncated at the end of the file
+    fn next_line<I>(lines: &mut I, location: &Location, expected: Option<usize>, found: usize) -> Result<(usize, String), ParseError>
+    where
+        I: Iterator<Item = (usize, io::Result<String>)>,
+    {
+        match lines.next() {
+            Some((index, Ok(line))) => Ok((index + 1, line)),
+            Some((_, Err(e))) => Err(ParseError::IoError(e)),
+            None => Err(ParseError::TruncatedBlock{ location: location.clone(), expected, found }),
+        }
+    }
+    impl Default for LammpsDumpParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl StructureIO for LammpsDumpParser {
+        // a frame is made of items, everything is handled in `iter_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            let mut lines = open_reader(input).map_err(ParseError::IoError)?.lines().enumerate().peekable();
+            let path = input.to_path_buf();
+            Ok(StructureIterator::new(std::iter::from_fn(move || loop {
+                let (index, line) = match lines.next()? {
+                    (index, Ok(line)) => (index, line),
+                    (_, Err(e)) => return Some(Err(ParseError::IoError(e))),
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let frame = match line.trim().strip_prefix("ITEM:").map(str::trim) {
+                    Some("TIMESTEP") => self.parse_frame(index + 1, &mut lines, Some(&path)),
+                    _ => Err(ParseError::UnexpectedLine{ location: Location::new(Some(&path), index + 1, 1), line }),
+                };
+                match frame {
+                    // the lines up to the next frame are skipped
+                    Err(_) if ignore_parse_error => continue,
+                    frame => return Some(frame),
+                }
+            })))
+        }
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let types = types_of(&self.types, structures);
+            // the boxes are checked before the file is created, so a cluster leaves no partial trajectory
+            let boxes = structures.iter().map(box_of).collect::<io::Result<Vec<_>>>()?;
+            let mut file = CompressedWriter::create(path)?;
+            for (block, (lx, ly, lz, xy, xz, yz)) in structures.iter().zip(boxes) {
+                writeln!(file, "ITEM: TIMESTEP")?;
+                writeln!(file, "{}", block.number)?;
+                writeln!(file, "ITEM: NUMBER OF ATOMS")?;
+                writeln!(file, "{}", block.atoms.len())?;
+                writeln!(file, "ITEM: BOX BOUNDS xy xz yz pp pp pp")?;
+                let (x_low, x_high) = ([0.0, xy, xz, xy + xz].into_iter().fold(0.0, f64::min), [0.0, xy, xz, xy + xz].into_iter().fold(0.0, f64::max));
+                writeln!(file, "{: >16.10} {: >16.10} {: >16.10}", x_low, lx + x_high, xy)?;
+                writeln!(file, "{: >16.10} {: >16.10} {: >16.10}", yz.min(0.0), ly + yz.max(0.0), xz)?;
+                writeln!(file, "{: >16.10} {: >16.10} {: >16.10}", 0.0, lz, yz)?;
+                match &block.forces {
+                    Some(_) => writeln!(file, "ITEM: ATOMS id type element x y z fx fy fz")?,
+                    None => writeln!(file, "ITEM: ATOMS id type element x y z")?,
+                }
+                for (i, atom) in block.atoms.iter().enumerate() {
+                    let atom_type = types.iter().position(|t| *t == atom.element).unwrap() + 1;
+                    write!(file, "{} {} {} {:.10} {:.10} {:.10}", i + 1, atom_type, atom.element, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2)?;
+                    match block.forces.as_ref().and_then(|f| f.get(i)) {
+                        Some(force) => writeln!(file, " {:.10} {:.10} {:.10}", force.0, force.1, force.2)?,
+                        None => writeln!(file)?,
+                    }
+                }
+            }
+            file.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::{NamedTempFile, TempDir};
+
+    use crate::lammps::lammps::*;
+    use crate::modules::structures::CrystalInfo;
+    use crate::parser::parser::*;
+
+    #[test]
+    fn test_parse_lammps_data() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "water Energy: -14.2").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "3 atoms").unwrap();
+        writeln!(file, "2 atom types").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "1.0 11.0 xlo xhi").unwrap();
+        writeln!(file, "0.0 10.0 ylo yhi").unwrap();
+        writeln!(file, "0.0 12.0 zlo zhi").unwrap();
+        writeln!(file, "5.0 0.0 0.0 xy xz yz").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "Masses").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "1 15.9994").unwrap();
+        writeln!(file, "2 1.008").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "Atoms # charge").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "3 2 0.4 2.0 0.0 0.0").unwrap();
+        writeln!(file, "1 1 -0.8 1.0 0.0 0.0").unwrap();
+        writeln!(file, "2 2 0.4 1.0 1.0 0.0 0 0 0").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "Velocities").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "1 0.0 0.0 0.0").unwrap();
+        let block = &LammpsDataParser::new().parse_structure(file.path(), false).unwrap().unwrap()[0];
+        assert_eq!(block.energy, -14.2);
+        assert_eq!(block.atoms.iter().map(|a| a.element.as_str()).collect::<Vec<&str>>(), vec!["O", "H", "H"]);
+        // the origin of the box is moved to zero
+        assert!((block.atoms[2].coordinate.0 - 1.0).abs() < 1e-9);
+        assert!((block.crystal.y - 125.0_f64.sqrt()).abs() < 1e-9);
+        assert!((block.crystal.gamma - 63.43494882292201).abs() < 1e-9);
+        // the given types come before the masses
+        let block = &LammpsDataParser::new().with_types(vec!["S".to_string()]).parse_structure(file.path(), false).unwrap().unwrap()[0];
+        assert_eq!(block.atoms[0].element, "S");
+        assert_eq!(block.atoms[1].element, "H");
+    }
+
+    #[test]
+    fn test_parse_lammps_data_fail() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "comment").unwrap();
+        writeln!(file, "2 atoms").unwrap();
+        writeln!(file, "0.0 10.0 xlo xhi").unwrap();
+        writeln!(file, "0.0 10.0 ylo yhi").unwrap();
+        writeln!(file, "0.0 10.0 zlo zhi").unwrap();
+        writeln!(file, "Atoms").unwrap();
+        writeln!(file, "1 1 1.0 0.0 0.x").unwrap();
+        let err = LammpsDataParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::BadFloat{ ref location, .. } if location.line == 7 && location.column == 13), "Got {:?}", err);
+        let content = std::fs::read_to_string(file.path()).unwrap().replace("0.x", "0.0");
+        std::fs::write(file.path(), content).unwrap();
+        let err = LammpsDataParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::TruncatedBlock{ expected: Some(2), found: 1, .. }), "Got {:?}", err);
+        // six columns are the charge or the molecular style, only the comment tells them apart
+        let content = std::fs::read_to_string(file.path()).unwrap().replace("1 1 1.0 0.0 0.0", "1 1 1 1.0 0.0 0.0");
+        std::fs::write(file.path(), content).unwrap();
+        let err = LammpsDataParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedLine{ ref location, .. } if location.line == 7), "Got {:?}", err);
+        let content = std::fs::read_to_string(file.path()).unwrap().replace("Atoms\n", "Atoms # molecular\n");
+        std::fs::write(file.path(), content).unwrap();
+        let err = LammpsDataParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::TruncatedBlock{ expected: Some(2), found: 1, .. }), "Got {:?}", err);
+    }
+
+    #[test]
+    fn test_lammps_data_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let blocks = LammpsDumpParser::new().parse_structure(dump_file().path(), false).unwrap().unwrap();
+        let path = dir.path().join("data.lmp");
+        let parser = LammpsDataParser::new().with_types(vec!["H".to_string(), "O".to_string()]);
+        parser.write_structure(&blocks[1..], &path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("   1     1.008000 # H"));
+        let block = &LammpsDataParser::new().parse_structure(&path, false).unwrap().unwrap()[0];
+        assert_eq!(block.atoms[0].element, "O");
+        assert!((block.crystal.gamma - blocks[1].crystal.gamma).abs() < 1e-9);
+        assert!((block.atoms[1].coordinate.1 - blocks[1].atoms[1].coordinate.1).abs() < 1e-9);
+        assert!(parser.write_structure(&blocks, &path).is_err());
+        // LAMMPS boxes are periodic, clusters and zeroed cells are refused
+        let mut cluster = blocks[1].clone();
+        cluster.periodic = false;
+        let err = parser.write_structure(&[cluster], &path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let mut zeroed = blocks[1].clone();
+        zeroed.crystal = CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 };
+        assert_eq!(parser.write_structure(&[zeroed], &path).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    fn dump_file() -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "ITEM: TIMESTEP").unwrap();
+        writeln!(file, "0").unwrap();
+        writeln!(file, "ITEM: NUMBER OF ATOMS").unwrap();
+        writeln!(file, "2").unwrap();
+        writeln!(file, "ITEM: BOX BOUNDS pp pp pp").unwrap();
+        writeln!(file, "0.0 10.0").unwrap();
+        writeln!(file, "0.0 10.0").unwrap();
+        writeln!(file, "0.0 10.0").unwrap();
+        writeln!(file, "ITEM: ATOMS id type xs ys zs fx fy fz").unwrap();
+        writeln!(file, "2 2 0.5 0.5 0.5 0.0 0.0 -1.0").unwrap();
+        writeln!(file, "1 1 0.1 0.2 0.3 1.0 0.0 0.0").unwrap();
+        writeln!(file, "ITEM: TIMESTEP").unwrap();
+        writeln!(file, "100").unwrap();
+        writeln!(file, "ITEM: UNITS").unwrap();
+        writeln!(file, "metal").unwrap();
+        writeln!(file, "ITEM: NUMBER OF ATOMS").unwrap();
+        writeln!(file, "2").unwrap();
+        writeln!(file, "ITEM: BOX BOUNDS xy xz yz pp pp pp").unwrap();
+        writeln!(file, "-1.0 10.0 -1.0").unwrap();
+        writeln!(file, "0.0 10.0 0.0").unwrap();
+        writeln!(file, "0.0 10.0 0.0").unwrap();
+        writeln!(file, "ITEM: ATOMS id element x y z").unwrap();
+        writeln!(file, "1 O 0.0 0.0 0.0").unwrap();
+        writeln!(file, "2 H 0.0 1.0 0.0").unwrap();
+        file
+    }
+
+    #[test]
+    fn test_parse_lammps_dump() {
+        let file = dump_file();
+        let parser = LammpsDumpParser::new().with_types(vec!["Pt".to_string(), "O".to_string()]);
+        let blocks = parser.parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].atoms[0].element, "Pt");
+        assert!((blocks[0].atoms[0].coordinate.1 - 2.0).abs() < 1e-9);
+        assert_eq!(blocks[0].forces.as_ref().unwrap()[0].0, 1.0);
+        assert_eq!(blocks[1].number, 100);
+        assert!(blocks[1].forces.is_none());
+        // the bounds enclose the box tilted by -1
+        assert!((blocks[1].crystal.x - 10.0).abs() < 1e-9);
+        assert!((blocks[1].crystal.y - 101.0_f64.sqrt()).abs() < 1e-9);
+        assert!((blocks[1].atoms[1].coordinate.1 - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lammps_dump_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let blocks = LammpsDumpParser::new().parse_structure(dump_file().path(), false).unwrap().unwrap();
+        let path = dir.path().join("traj.lammpstrj.gz");
+        LammpsDumpParser::new().write_structure(&blocks, &path).unwrap();
+        assert_eq!(detect_format(&path).unwrap(), "lammps-dump");
+        let written = LammpsDumpParser::new().parse_structure(&path, false).unwrap().unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[0].atoms[1].element, "2");
+        assert_eq!(written[0].forces, blocks[0].forces);
+        assert!((written[1].crystal.gamma - blocks[1].crystal.gamma).abs() < 1e-9);
+        assert!((written[1].atoms[1].coordinate.0 - blocks[1].atoms[1].coordinate.0).abs() < 1e-9);
+        // a cluster anywhere in the trajectory is refused before the file is written
+        let mut cluster = blocks.clone();
+        cluster[1].periodic = false;
+        let path = dir.path().join("cluster.lammpstrj");
+        let err = LammpsDumpParser::new().write_structure(&cluster, &path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_parse_lammps_dump_fail() {
+        let file = dump_file();
+        let content = std::fs::read_to_string(file.path()).unwrap().replacen("0.5 0.5 0.5", "0.5 0.5", 1);
+        std::fs::write(file.path(), content).unwrap();
+        let err = LammpsDumpParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedLine{ ref location, .. } if location.line == 10), "Got {:?}", err);
+        // the broken frame is skipped
+        let blocks = LammpsDumpParser::new().parse_structure(file.path(), true).unwrap().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].number, 100);
+    }
+}
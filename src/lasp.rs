@@ -0,0 +1,774 @@
+pub mod allfor {
+    //! reader of the allfor.arc files written by LASP, holding the forces and stress of each structure
+    //!
+    //! every structure is written as
+    //! ```text
+    //!  For   12   1   -1234.567890
+    //!   0.1234   0.2345   0.3456  -0.0012   0.0023   0.0034
+    //!   0.0123  -0.0456   0.0789
+    //!   ...
+    //! ```
+    //! a header with the structure number and energy, the stress line and one force line per atom,
+    //! in the atom order of the matching block of allstr.arc/all.arc
+    use std::collections::{HashMap, VecDeque};
+    use std::io::BufRead;
+    use std::path::Path;
+
+    use crate::compression::compression::open_reader;
+    use crate::modules::structures::{Coordinate, StructureBlock};
+    use crate::parser::parser::{split_columns, parse_float, Location, ParseError};
+
+    /// the forces and stress of a structure in allfor.arc
+    #[derive(Clone, Debug)]
+    pub struct ForceBlock {
+        /// the structure number, the same as in the header of the structure
+        pub number: u64,
+        /// the energy in the header line, if any
+        pub energy: Option<f64>,
+        pub stress: Option<[f64; 6]>,
+        /// forces on the atoms in eV/angstrom
+        pub forces: Vec<Coordinate>,
+    }
+
+    pub struct AllforParser {}
+    impl AllforParser {
+        pub fn new() -> Self {
+            Self {}
+        }
+
+        /// parse a header line, `None` if the line is not a header
+        fn parse_header(&self, input: &str) -> Result<Option<ForceBlock>, ParseError> {
+            let columns = split_columns(input);
+            match columns.split_first() {
+                Some(((_, "For"), rest)) if !rest.is_empty() => {
+                    let (column, number) = rest[0];
+                    let number = number.parse::<u64>().map_err(|_| ParseError::BadFloat{ location: Location::new(None, 0, column), text: number.to_string() })?;
+                    // the energy is the last number, some versions write another field before it
+                    let energy = match rest.last() {
+                        Some((column, energy)) if rest.len() > 1 => Some(parse_float(energy, *column)?),
+                        _ => None,
+                    };
+                    Ok(Some(ForceBlock{ number, energy, stress: None, forces: Vec::new() }))
+                },
+                _ => Ok(None),
+            }
+        }
+
+        /// parse the numbers of a line
+        fn parse_numbers(&self, input: &str) -> Result<Vec<f64>, ParseError> {
+            split_columns(input).into_iter().map(|(column, text)| parse_float(text, column)).collect()
+        }
+
+        /**
+        lazily parse the force blocks of a file
+
+        a block ends with a blank line, the next header or the end of the file.
+        The stress line is optional, a block whose first line holds three numbers has only forces
+         */
+        pub fn iter_forces<'a>(&'a self, path: &Path) -> Result<impl Iterator<Item = Result<ForceBlock, ParseError>> + 'a, ParseError> {
+            let mut lines = open_reader(path).map_err(ParseError::IoError)?.lines().enumerate().peekable();
+            let path = path.to_path_buf();
+            Ok(std::iter::from_fn(move || {
+                let mut current: Option<ForceBlock> = None;
+                let mut expect_stress = false;
+                loop {
+                    // a header ends the current block without being consumed
+                    if current.is_some() {
+                        if let Some((_, Ok(line))) = lines.peek() {
+                            if matches!(self.parse_header(line), Ok(Some(_))) {
+                                return current.map(Ok);
+                            }
+                        }
+                    }
+                    let (index, line) = match lines.next() {
+                        Some((index, Ok(line))) => (index, line),
+                        Some((_, Err(e))) => return Some(Err(ParseError::IoError(e))),
+                        None => return current.map(Ok),
+                    };
+                    let locate = |e: ParseError| e.at(Some(&path), index + 1);
+                    let unexpected = || ParseError::UnexpectedLine{ location: Location::new(Some(&path), index + 1, 1), line: line.clone() };
+                    if line.trim().is_empty() {
+                        if current.is_some() {
+                            return current.map(Ok);
+                        }
+                        continue;
+                    }
+                    let block = match current.as_mut() {
+                        Some(block) => block,
+                        None => match self.parse_header(&line).map_err(locate) {
+                            Ok(Some(block)) => {
+                                current = Some(block);
+                                expect_stress = true;
+                                continue;
+                            },
+                            Ok(None) => return Some(Err(unexpected())),
+                            Err(e) => return Some(Err(e)),
+                        },
+                    };
+                    let values = match self.parse_numbers(&line).map_err(locate) {
+                        Ok(values) => values,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    match values.as_slice() {
+                        [xx, yy, zz, xy, yz, zx] if expect_stress => block.stress = Some([*xx, *yy, *zz, *xy, *yz, *zx]),
+                        [x, y, z] => block.forces.push(Coordinate(*x, *y, *z)),
+                        _ => return Some(Err(unexpected())),
+                    }
+                    expect_stress = false;
+                }
+            }))
+        }
+
+        /// parse all force blocks of a file, stopping at the first error
+        pub fn parse_forces(&self, path: &Path) -> Result<Vec<ForceBlock>, ParseError> {
+            self.iter_forces(path)?.collect()
+        }
+    }
+    impl Default for AllforParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /**
+    set the forces and stress of the blocks from the force blocks with the same number
+
+    force blocks are used in order when several structures share a number, and are skipped if their
+    atom count differs from the structure. Returns the number of blocks that got their forces
+     */
+    pub fn attach_forces(blocks: &mut [StructureBlock], forces: Vec<ForceBlock>) -> usize {
+        let mut by_number: HashMap<u64, VecDeque<ForceBlock>> = HashMap::new();
+        for force in forces {
+            by_number.entry(force.number).or_default().push_back(force);
+        }
+        let mut paired = 0;
+        for block in blocks.iter_mut() {
+            let Some(candidates) = by_number.get_mut(&block.number) else {
+                continue;
+            };
+            if let Some(position) = candidates.iter().position(|f| f.forces.len() == block.atoms.len()) {
+                let force = candidates.remove(position).unwrap();
+                block.forces = Some(force.forces);
+                block.stress = force.stress;
+                paired += 1;
+            }
+        }
+        paired
+    }
+}
+
+
+pub mod lasp_out {
+    //! model of the lasp.out log written by LASP during an SSW search
+    //!
+    //! the lines used are
+    //! ```text
+    //!  ------------ SSW step:     12 ------------
+    //!  Energy,force,stress     -395.123456     0.024355     0.001234
+    //!  Str symm and Q     12  C1 ...
+    //!  not converged
+    //!  time consumed:     12.34 s
+    //! ```
+    //! the step header, one `Energy,force` line per optimisation iteration (the stress is optional),
+    //! the summary of the optimised structure, a `not converged` warning after it and the timing.
+    //! Other lines are skipped
+    use std::io::BufRead;
+    use std::path::Path;
+    use regex::{Captures, Regex};
+
+    use crate::compression::compression::open_reader;
+    use crate::parser::parser::{parse_float, ParseError};
+
+    /// the outcome of an SSW step
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum StepStatus {
+        Converged,
+        NotConverged,
+        /// the log ends before the step is summarised, e.g. when LASP is still running
+        Running,
+    }
+
+    /// an iteration of the optimisation of a structure
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Iteration {
+        pub energy: f64,
+        /// the max force on the atoms in eV/angstrom
+        pub max_force: f64,
+        pub max_stress: Option<f64>,
+        /// line number in lasp.out, starting from 1
+        pub line: usize,
+    }
+
+    /// an SSW step, from its header to the next one
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct SswStep {
+        /// the step number of the header, or the position of the step counted from 1 if the log has no headers
+        pub step: u64,
+        /// line number of the first line of the step
+        pub line: usize,
+        /// the structure number of the `Str symm and Q` summary, as written in the header of all.arc
+        pub structure: Option<u64>,
+        pub iterations: Vec<Iteration>,
+        pub status: StepStatus,
+        /// the time consumed by the step in seconds
+        pub time: Option<f64>,
+    }
+    impl SswStep {
+        fn new(step: u64, line: usize) -> Self {
+            Self { step, line, structure: None, iterations: Vec::new(), status: StepStatus::Running, time: None }
+        }
+        /// the energy of the last iteration
+        pub fn energy(&self) -> Option<f64> {
+            self.iterations.last().map(|i| i.energy)
+        }
+        /// the max force of the last iteration
+        pub fn max_force(&self) -> Option<f64> {
+            self.iterations.last().map(|i| i.max_force)
+        }
+        /// whether the step has been summarised, later iterations belong to the next step
+        fn is_summarised(&self) -> bool {
+            self.structure.is_some() || self.status != StepStatus::Running
+        }
+    }
+
+    /// the steps of a lasp.out file in order
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct LaspOutLog {
+        pub steps: Vec<SswStep>,
+    }
+
+    /// regexes of the lines used in lasp.out
+    struct LogPatterns {
+        step: Regex,
+        iteration: Regex,
+        summary: Regex,
+        time: Regex,
+    }
+    impl LogPatterns {
+        fn new() -> Self {
+            Self {
+                step: Regex::new(r"SSW\s+step\s*:?\s*(?P<step>\d+)").unwrap(),
+                iteration: Regex::new(r"^\s*Energy,force(?:,stress)?\s+(?P<energy>\S+)\s+(?P<force>\S+)(?:\s+(?P<stress>\S+))?").unwrap(),
+                summary: Regex::new(r"^\s*Str symm and Q\s+(?P<num>\d+)").unwrap(),
+                time: Regex::new(r"(?i)^\s*time\s*(?:consumed|used|cost)?\s*[:=]?\s*(?P<time>\S+)").unwrap(),
+            }
+        }
+    }
+
+    /// parse a named capture as a float, located at its column
+    fn capture_float(captures: &Captures, name: &str) -> Option<Result<f64, ParseError>> {
+        captures.name(name).map(|m| parse_float(m.as_str(), m.start() + 1))
+    }
+
+    impl LaspOutLog {
+        /// parse a lasp.out file
+        pub fn parse(path: &Path) -> Result<Self, ParseError> {
+            let reader = open_reader(path).map_err(ParseError::IoError)?;
+            Self::from_reader(reader, Some(path))
+        }
+
+        /// parse the lines of a lasp.out, `path` is only used to locate parse errors
+        pub fn from_reader<R: BufRead>(reader: R, path: Option<&Path>) -> Result<Self, ParseError> {
+            let patterns = LogPatterns::new();
+            let mut steps: Vec<SswStep> = Vec::new();
+            for (index, line) in reader.lines().enumerate() {
+                let line = line.map_err(ParseError::IoError)?;
+                let line_number = index + 1;
+                let locate = |e: ParseError| e.at(path, line_number);
+                // steps are counted when the log has no headers
+                let next_step = steps.last().map_or(1, |s| s.step + 1);
+                if let Some(captures) = patterns.step.captures(&line) {
+                    let step = captures["step"].parse::<u64>().unwrap_or(next_step);
+                    steps.push(SswStep::new(step, line_number));
+                }
+                else if let Some(captures) = patterns.iteration.captures(&line) {
+                    let iteration = Iteration{
+                        energy: capture_float(&captures, "energy").unwrap().map_err(locate)?,
+                        max_force: capture_float(&captures, "force").unwrap().map_err(locate)?,
+                        max_stress: capture_float(&captures, "stress").transpose().map_err(locate)?,
+                        line: line_number,
+                    };
+                    if steps.last().is_none_or(SswStep::is_summarised) {
+                        steps.push(SswStep::new(next_step, line_number));
+                    }
+                    steps.last_mut().unwrap().iterations.push(iteration);
+                }
+                else if let Some(captures) = patterns.summary.captures(&line) {
+                    if steps.last().is_none_or(SswStep::is_summarised) {
+                        steps.push(SswStep::new(next_step, line_number));
+                    }
+                    let step = steps.last_mut().unwrap();
+                    step.structure = captures["num"].parse::<u64>().ok();
+                    step.status = StepStatus::Converged;
+                }
+                else if line.contains("not converged") {
+                    match steps.last_mut() {
+                        Some(step) => step.status = StepStatus::NotConverged,
+                        None => {
+                            let mut step = SswStep::new(next_step, line_number);
+                            step.status = StepStatus::NotConverged;
+                            steps.push(step);
+                        },
+                    }
+                }
+                else if let Some(captures) = patterns.time.captures(&line) {
+                    if let (Some(step), Some(Ok(time))) = (steps.last_mut(), capture_float(&captures, "time")) {
+                        step.time = Some(time);
+                    }
+                }
+            }
+            Ok(Self { steps })
+        }
+
+        /// the steps whose optimisation did not converge
+        pub fn unconverged(&self) -> impl Iterator<Item = &SswStep> {
+            self.steps.iter().filter(|s| s.status == StepStatus::NotConverged)
+        }
+
+        /// the structure numbers of the unconverged steps, as written in the headers of all.arc
+        pub fn unconverged_structures(&self) -> Vec<u64> {
+            self.unconverged().filter_map(|s| s.structure).collect()
+        }
+
+        /// the final energy of each step that has one
+        pub fn energies(&self) -> Vec<f64> {
+            self.steps.iter().filter_map(SswStep::energy).collect()
+        }
+
+        /// the time consumed by all steps in seconds
+        pub fn total_time(&self) -> f64 {
+            self.steps.iter().filter_map(|s| s.time).sum()
+        }
+    }
+}
+
+
+pub mod lasp_in {
+    //! reader and validator of the lasp.in input file
+    //!
+    //! ```text
+    //! explore_type   ssw
+    //! potential      NN
+    //! SSW.SSWsteps   100
+    //! %block netinfo
+    //!   O   O.pot
+    //!   H   H.pot
+    //! %endblock netinfo
+    //! ```
+    //! keys are case insensitive and followed by their value, blocks hold free form lines.
+    //! Comments start with `#` or `!`
+    use std::collections::HashSet;
+    use std::fmt;
+    use std::io::BufRead;
+    use std::path::Path;
+    use std::str::FromStr;
+
+    use crate::compression::compression::open_reader;
+    use crate::modules::structures::StructureBlock;
+    use crate::parser::parser::{split_columns, Location, ParseError};
+
+    /// a `key value` line
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct InputEntry {
+        pub key: String,
+        pub value: String,
+        /// line number in lasp.in, starting from 1
+        pub line: usize,
+    }
+
+    /// a `%block name` ... `%endblock name` section
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct InputBlock {
+        pub name: String,
+        /// line number of `%block`
+        pub line: usize,
+        /// the lines inside the block with their line numbers, without comments and blank lines
+        pub lines: Vec<(usize, String)>,
+    }
+
+    /// the potentials LASP can drive
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Potential {
+        /// the LASP neural network potential, the files are listed in the `netinfo` block
+        NN,
+        Vasp,
+        Lammps,
+        Gulp,
+        Cp2k,
+        Qe,
+        Unknown(String),
+    }
+    impl From<&str> for Potential {
+        fn from(value: &str) -> Self {
+            match value.to_lowercase().as_str() {
+                "nn" => Potential::NN,
+                "vasp" => Potential::Vasp,
+                "lammps" => Potential::Lammps,
+                "gulp" => Potential::Gulp,
+                "cp2k" => Potential::Cp2k,
+                "qe" => Potential::Qe,
+                _ => Potential::Unknown(value.to_string()),
+            }
+        }
+    }
+
+    /// a problem found by `LaspInput::validate`
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum InputIssue {
+        MissingKey(&'static str),
+        UnknownPotential{ value: String, line: usize },
+        /// a value that can not be parsed as the type of its key
+        BadValue{ key: String, value: String, line: usize },
+        /// the NN potential has no `netinfo` block
+        MissingPotentialBlock,
+        /// an element of the structure without potential
+        ElementWithoutPotential(String),
+        /// `Ntype` differs from the number of elements in the structure
+        TypeCountMismatch{ ntype: usize, found: usize },
+        /// the cell of the structure has no volume
+        DegenerateCell,
+    }
+    impl fmt::Display for InputIssue {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                InputIssue::MissingKey(key) => write!(f, "missing key {}", key),
+                InputIssue::UnknownPotential{ value, line } => write!(f, "line {}: unknown potential {:?}", line, value),
+                InputIssue::BadValue{ key, value, line } => write!(f, "line {}: invalid value {:?} of {}", line, value, key),
+                InputIssue::MissingPotentialBlock => write!(f, "the NN potential needs a netinfo block"),
+                InputIssue::ElementWithoutPotential(element) => write!(f, "element {} of the structure is missing from the netinfo block", element),
+                InputIssue::TypeCountMismatch{ ntype, found } => write!(f, "Ntype is {} but the structure has {} elements", ntype, found),
+                InputIssue::DegenerateCell => write!(f, "the cell of the structure has no volume"),
+            }
+        }
+    }
+
+    /// the keys and blocks of a lasp.in file
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct LaspInput {
+        pub entries: Vec<InputEntry>,
+        pub blocks: Vec<InputBlock>,
+    }
+    impl LaspInput {
+        /// parse a lasp.in file
+        pub fn parse(path: &Path) -> Result<Self, ParseError> {
+            let reader = open_reader(path).map_err(ParseError::IoError)?;
+            Self::from_reader(reader, Some(path))
+        }
+
+        /// parse the lines of a lasp.in, `path` is only used to locate parse errors
+        pub fn from_reader<R: BufRead>(reader: R, path: Option<&Path>) -> Result<Self, ParseError> {
+            let mut input = Self::default();
+            let mut current: Option<InputBlock> = None;
+            for (index, line) in reader.lines().enumerate() {
+                let line = line.map_err(ParseError::IoError)?;
+                let line_number = index + 1;
+                let content = line.split(['#', '!']).next().unwrap_or_default().trim();
+                if content.is_empty() {
+                    continue;
+                }
+                let unexpected = || ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line: line.clone() };
+                let columns = split_columns(content);
+                let keyword = columns[0].1.to_lowercase();
+                match (keyword.as_str(), current.as_mut()) {
+                    ("%block", None) if columns.len() == 2 => {
+                        current = Some(InputBlock{ name: columns[1].1.to_string(), line: line_number, lines: Vec::new() });
+                    },
+                    // the name after %endblock is optional, but must match if given
+                    ("%endblock", Some(block)) if columns.get(1).is_none_or(|(_, name)| name.eq_ignore_ascii_case(&block.name)) => {
+                        input.blocks.extend(current.take());
+                    },
+                    ("%block" | "%endblock", _) => return Err(unexpected()),
+                    (_, Some(block)) => block.lines.push((line_number, content.to_string())),
+                    (_, None) => match content.split_once(char::is_whitespace) {
+                        Some((key, value)) => input.entries.push(InputEntry{ key: key.to_string(), value: value.trim().to_string(), line: line_number }),
+                        None => return Err(unexpected()),
+                    },
+                }
+            }
+            match current {
+                Some(block) => Err(ParseError::TruncatedBlock{ location: Location::new(path, block.line, 0), expected: None, found: block.lines.len() }),
+                None => Ok(input),
+            }
+        }
+
+        /// the entry of a key, LASP uses the last one if a key is repeated
+        pub fn entry(&self, key: &str) -> Option<&InputEntry> {
+            self.entries.iter().rev().find(|e| e.key.eq_ignore_ascii_case(key))
+        }
+        /// the value of a key
+        pub fn get(&self, key: &str) -> Option<&str> {
+            self.entry(key).map(|e| e.value.as_str())
+        }
+        /// the value of a key parsed as `T`, an error located at the value if it can not be parsed
+        pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<Result<T, ParseError>> {
+            self.entry(key).map(|e| e.value.parse::<T>().map_err(|_| ParseError::BadFloat{ location: Location::new(None, e.line, 0), text: e.value.clone() }))
+        }
+        /// the block of a name, case insensitive
+        pub fn block(&self, name: &str) -> Option<&InputBlock> {
+            self.blocks.iter().rev().find(|b| b.name.eq_ignore_ascii_case(name))
+        }
+
+        pub fn explore_type(&self) -> Option<&str> {
+            self.get("explore_type")
+        }
+        pub fn potential(&self) -> Option<Potential> {
+            self.get("potential").map(Potential::from)
+        }
+        pub fn ssw_steps(&self) -> Option<Result<u64, ParseError>> {
+            self.get_parsed("SSW.SSWsteps")
+        }
+        /// the temperature of the SSW search in K
+        pub fn temperature(&self) -> Option<Result<f64, ParseError>> {
+            self.get_parsed("SSW.Temp")
+        }
+        /// the number of element types
+        pub fn ntype(&self) -> Option<Result<usize, ParseError>> {
+            self.get_parsed("Ntype")
+        }
+        /// the elements of the `netinfo` block, the first column of each line
+        pub fn potential_elements(&self) -> Option<Vec<&str>> {
+            let block = self.block("netinfo")?;
+            Some(block.lines.iter().filter_map(|(_, line)| line.split_whitespace().next()).collect())
+        }
+
+        /// check the keys of the input on their own
+        pub fn validate(&self) -> Vec<InputIssue> {
+            let mut issues = Vec::new();
+            if self.explore_type().is_none() {
+                issues.push(InputIssue::MissingKey("explore_type"));
+            }
+            match (self.potential(), self.entry("potential")) {
+                (None, _) => issues.push(InputIssue::MissingKey("potential")),
+                (Some(Potential::Unknown(value)), Some(entry)) => issues.push(InputIssue::UnknownPotential{ value, line: entry.line }),
+                (Some(Potential::NN), _) if self.block("netinfo").is_none() => issues.push(InputIssue::MissingPotentialBlock),
+                _ => (),
+            }
+            let bad_value = |key: &str| self.entry(key).map(|e| InputIssue::BadValue{ key: e.key.clone(), value: e.value.clone(), line: e.line });
+            if matches!(self.ssw_steps(), Some(Err(_))) {
+                issues.extend(bad_value("SSW.SSWsteps"));
+            }
+            if matches!(self.temperature(), Some(Err(_))) {
+                issues.extend(bad_value("SSW.Temp"));
+            }
+            if matches!(self.ntype(), Some(Err(_))) {
+                issues.extend(bad_value("Ntype"));
+            }
+            issues
+        }
+
+        /// check the input against the structure it is run on, e.g. the first block of input.arc
+        pub fn validate_against(&self, block: &StructureBlock) -> Vec<InputIssue> {
+            let mut issues = self.validate();
+            let mut elements: Vec<&str> = Vec::new();
+            for atom in block.atoms.iter() {
+                if !elements.contains(&atom.element.as_str()) {
+                    elements.push(&atom.element);
+                }
+            }
+            if let Some(potential_elements) = self.potential_elements() {
+                let potential_elements: HashSet<&str> = potential_elements.into_iter().collect();
+                for element in elements.iter().filter(|e| !potential_elements.contains(*e)) {
+                    issues.push(InputIssue::ElementWithoutPotential(element.to_string()));
+                }
+            }
+            if let Some(Ok(ntype)) = self.ntype() {
+                if ntype != elements.len() {
+                    issues.push(InputIssue::TypeCountMismatch{ ntype, found: elements.len() });
+                }
+            }
+            // clusters have no cell to check
+            let volume = block.crystal.lattice_vectors().determinant().abs();
+            if block.periodic && (volume.is_nan() || volume < 1e-6) {
+                issues.push(InputIssue::DegenerateCell);
+            }
+            issues
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use crate::lasp::allfor::*;
+    use crate::lasp::lasp_out::*;
+    use crate::lasp::lasp_in::*;
+    use crate::modules::structures::*;
+    use crate::parser::parser::ParseError;
+
+    fn allfor_file() -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, " For   1   1   -100.500000").unwrap();
+        writeln!(file, "   0.1000   0.2000   0.3000  -0.0100   0.0200   0.0300").unwrap();
+        writeln!(file, "   0.3000  -0.4000   0.0000").unwrap();
+        writeln!(file, "   0.0000   0.0000   0.1000").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, " For   2   1   -101.500000").unwrap();
+        writeln!(file, "   0.0000   0.0000   0.0000   0.0000   0.0000   0.0000").unwrap();
+        writeln!(file, "   0.0100   0.0000   0.0000").unwrap();
+        writeln!(file, "   0.0000   0.0000  -0.0200").unwrap();
+        file
+    }
+
+    #[test]
+    fn test_parse_forces() {
+        let file = allfor_file();
+        let forces = AllforParser::new().parse_forces(file.path()).unwrap();
+        assert_eq!(forces.len(), 2);
+        assert_eq!(forces[0].number, 1);
+        assert_eq!(forces[0].energy, Some(-100.5));
+        assert_eq!(forces[0].stress, Some([0.1, 0.2, 0.3, -0.01, 0.02, 0.03]));
+        assert_eq!(forces[0].forces.len(), 2);
+        assert_eq!(forces[1].forces[1].2, -0.02);
+    }
+
+    #[test]
+    fn test_parse_forces_fail() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, " For   1   1   -100.500000").unwrap();
+        writeln!(file, "   0.3000  -0.4x00   0.0000").unwrap();
+        let err = AllforParser::new().parse_forces(file.path()).unwrap_err();
+        assert!(matches!(err, ParseError::BadFloat{ location, .. } if location.line == 2 && location.column == 12));
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "   0.3000  -0.4000   0.0000").unwrap();
+        let err = AllforParser::new().parse_forces(file.path()).unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedLine{ .. }));
+    }
+
+    #[test]
+    fn test_attach_forces() {
+        let file = allfor_file();
+        let forces = AllforParser::new().parse_forces(file.path()).unwrap();
+        let block = |number: u64, atoms: usize| StructureBlock{
+            number,
+            energy: -100.5,
+            symmetry: "C1".to_string(),
+            crystal: CrystalInfo{ x: 10.0, y: 10.0, z: 10.0, alpha: 90.0, beta: 90.0, gamma: 90.0 },
+            atoms: vec![Atom{ element: "H".to_string(), coordinate: Coordinate(0.0, 0.0, 0.0), record: None, selective: None }; atoms],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
+        };
+        // the third block has no forces, the fourth has a wrong atom count
+        let mut blocks = vec![block(2, 2), block(1, 2), block(3, 2), block(1, 3)];
+        assert_eq!(attach_forces(&mut blocks, forces), 2);
+        assert_eq!(blocks[0].forces.as_ref().unwrap()[0].0, 0.01);
+        assert_eq!(blocks[1].stress.unwrap()[2], 0.3);
+        assert!(blocks[2].forces.is_none());
+        assert!(blocks[3].forces.is_none());
+    }
+
+    #[test]
+    fn test_parse_lasp_out() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, " ------------ SSW step:      1 ------------").unwrap();
+        writeln!(file, " Energy,force,stress     -395.123456     0.824355     0.001234").unwrap();
+        writeln!(file, " Energy,force,stress     -396.000000     0.004355     0.000234").unwrap();
+        writeln!(file, " Str symm and Q      1  C1   0.1").unwrap();
+        writeln!(file, " time consumed:     12.5 s").unwrap();
+        writeln!(file, " ------------ SSW step:      2 ------------").unwrap();
+        writeln!(file, " Energy,force     -394.000000     2.5").unwrap();
+        writeln!(file, " Str symm and Q      2  C1   0.1").unwrap();
+        writeln!(file, " WARNING: optimization not converged").unwrap();
+        writeln!(file, " time consumed:     30.0 s").unwrap();
+        writeln!(file, " ------------ SSW step:      3 ------------").unwrap();
+        writeln!(file, " Energy,force,stress     -393.000000     1.0     0.1").unwrap();
+        let log = LaspOutLog::parse(file.path()).unwrap();
+        assert_eq!(log.steps.len(), 3);
+        assert_eq!(log.steps[0].iterations.len(), 2);
+        assert_eq!(log.steps[0].energy(), Some(-396.0));
+        assert_eq!(log.steps[0].max_force(), Some(0.004355));
+        assert_eq!(log.steps[0].status, StepStatus::Converged);
+        assert_eq!(log.steps[1].iterations[0].max_stress, None);
+        assert_eq!(log.steps[1].status, StepStatus::NotConverged);
+        assert_eq!(log.steps[2].status, StepStatus::Running);
+        assert_eq!(log.steps[2].line, 11);
+        assert_eq!(log.unconverged_structures(), vec![2]);
+        assert_eq!(log.energies(), vec![-396.0, -394.0, -393.0]);
+        assert_eq!(log.total_time(), 42.5);
+    }
+
+    #[test]
+    fn test_parse_lasp_out_without_headers() {
+        // older logs only have the summaries, a step ends with its summary
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, " Energy,force     -395.0     0.5").unwrap();
+        writeln!(file, "Str symm and Q      7  C1").unwrap();
+        writeln!(file, "not converged").unwrap();
+        writeln!(file, " Energy,force     -396.0     0.01").unwrap();
+        writeln!(file, "Str symm and Q      8  C1").unwrap();
+        writeln!(file, " Energy,force     -39x.0     0.01").unwrap();
+        let err = LaspOutLog::parse(file.path());
+        assert!(matches!(err, Err(ParseError::BadFloat{ ref location, .. }) if location.line == 6 && location.column == 19), "Got {:?}", err);
+        let content = std::fs::read_to_string(file.path()).unwrap().replace("-39x.0", "-397.0");
+        let log = LaspOutLog::from_reader(content.as_bytes(), None).unwrap();
+        assert_eq!(log.steps.iter().map(|s| s.step).collect::<Vec<u64>>(), vec![1, 2, 3]);
+        assert_eq!(log.steps[0].status, StepStatus::NotConverged);
+        assert_eq!(log.steps[1].structure, Some(8));
+        assert_eq!(log.unconverged_structures(), vec![7]);
+    }
+
+    fn lasp_in(content: &str) -> Result<LaspInput, ParseError> {
+        LaspInput::from_reader(content.as_bytes(), None)
+    }
+
+    #[test]
+    fn test_parse_lasp_in() {
+        let input = lasp_in("explore_type  ssw   # stochastic surface walking\npotential NN\nSSW.SSWsteps 100\nntype 2\n%block netinfo\n  O  O.pot\n\n  H  H.pot\n%endblock netinfo\npotential  nn\n").unwrap();
+        assert_eq!(input.explore_type(), Some("ssw"));
+        assert_eq!(input.potential(), Some(Potential::NN));
+        assert_eq!(input.ssw_steps().unwrap().unwrap(), 100);
+        assert_eq!(input.ntype().unwrap().unwrap(), 2);
+        assert_eq!(input.entry("Potential").unwrap().line, 10);
+        assert_eq!(input.block("NetInfo").unwrap().line, 5);
+        assert_eq!(input.potential_elements(), Some(vec!["O", "H"]));
+        assert!(input.validate().is_empty());
+    }
+
+    #[test]
+    fn test_parse_lasp_in_fail() {
+        let err = lasp_in("%block netinfo\n O O.pot\n").unwrap_err();
+        assert!(matches!(err, ParseError::TruncatedBlock{ ref location, found: 1, .. } if location.line == 1), "Got {:?}", err);
+        let err = lasp_in("%block netinfo\n O O.pot\n%endblock fixatom\n").unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedLine{ ref location, .. } if location.line == 3), "Got {:?}", err);
+        let err = lasp_in("potential\n").unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedLine{ .. }), "Got {:?}", err);
+    }
+
+    #[test]
+    fn test_validate_lasp_in() {
+        let input = lasp_in("potential NNN\nSSW.SSWsteps 1OO\n").unwrap();
+        assert_eq!(input.validate(), vec![
+            InputIssue::MissingKey("explore_type"),
+            InputIssue::UnknownPotential{ value: "NNN".to_string(), line: 1 },
+            InputIssue::BadValue{ key: "SSW.SSWsteps".to_string(), value: "1OO".to_string(), line: 2 },
+        ]);
+        let input = lasp_in("explore_type ssw\npotential NN\nNtype 3\n%block netinfo\n O O.pot\n%endblock\n").unwrap();
+        let atom = |element: &str| Atom{ element: element.to_string(), coordinate: Coordinate(0.0, 0.0, 0.0), record: None, selective: None };
+        let block = StructureBlock{
+            number: 0,
+            energy: 0.0,
+            symmetry: "C1".to_string(),
+            crystal: CrystalInfo{ x: 10.0, y: 10.0, z: 10.0, alpha: 90.0, beta: 90.0, gamma: 90.0 },
+            atoms: vec![atom("O"), atom("H"), atom("H")],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
+        };
+        assert_eq!(input.validate_against(&block), vec![
+            InputIssue::ElementWithoutPotential("H".to_string()),
+            InputIssue::TypeCountMismatch{ ntype: 3, found: 2 },
+        ]);
+        // a flat periodic cell has no volume, a cluster (`PBC=OFF`) has no cell at all
+        let mut flat = block.clone();
+        flat.crystal.z = 0.0;
+        assert!(input.validate_against(&flat).contains(&InputIssue::DegenerateCell));
+        let mut cluster = block;
+        cluster.crystal = CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 };
+        cluster.periodic = false;
+        assert!(!input.validate_against(&cluster).contains(&InputIssue::DegenerateCell));
+    }
+}
@@ -1,3 +1,21 @@
 pub mod analyzer;
+pub mod cif;
+#[allow(clippy::module_inception)]
+pub mod compression;
+pub mod cp2k;
+#[allow(clippy::module_inception)]
+pub mod gaussian;
+pub mod index;
+#[allow(clippy::module_inception)]
+pub mod lammps;
+pub mod lasp;
 pub mod modules;
-pub mod parser;
\ No newline at end of file
+pub mod neighbor;
+#[allow(clippy::module_inception)]
+pub mod orca;
+#[allow(clippy::module_inception)]
+pub mod parser;
+pub mod pdb;
+pub mod qe;
+pub mod train;
+pub mod vasp;
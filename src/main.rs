@@ -1,16 +1,21 @@
-mod modules;
-pub mod parser;
-mod analyzer;
-
-use crate::modules::structures::StructureBlock;
-use crate::analyzer::arc_analyzer::{self, check_atom_consistency, list_energy};
+use arc_parser::{modules, parser};
+use arc_parser::modules::structures::StructureBlock;
+use arc_parser::parser::parser::StructureIO;
+use arc_parser::index::arc_index::ArcIndex;
+use arc_parser::compression::compression::{Compression, is_stdio};
+use arc_parser::lasp::allfor::{AllforParser, attach_forces};
+use arc_parser::lasp::lasp_out::{LaspOutLog, StepStatus};
+use arc_parser::lasp::lasp_in::LaspInput;
+use arc_parser::lammps::lammps::{LammpsDataParser, LammpsDumpParser};
+use arc_parser::analyzer::arc_analyzer::{self, check_atom_consistency, list_energy};
+use arc_parser::modules::periodic_table::RadiusSet;
+use arc_parser::neighbor::neighbor_list::{BondCriterion, NeighborList};
 use colored::*;
 use structopt::StructOpt;
 use itertools::Itertools;
 use nalgebra::{self as na, Const, Dyn, VecStorage};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use ctrlc;
 use memory_stats::memory_stats;
 
 #[derive(StructOpt)]
@@ -30,8 +35,10 @@ enum SubProgram {
 
 #[derive(StructOpt)]
 struct ParseArgs{
-    #[structopt(help = "The file to parse", short="f", long="file")]
+    #[structopt(help = "The file to parse, - for stdin", short="f", long="file")]
     file: String,
+    #[structopt(help = "The input file format, needed for stdin", long="input-format")]
+    input_format: Option<String>,
     #[structopt(help = "enable output the minimum energy", short="m", long="minimum")]
     minimum: bool,
     #[structopt(help = "enable count the number of structures", short="c", long="count")]
@@ -44,34 +51,68 @@ struct ParseArgs{
     extract: Option<usize>,
     #[structopt(help = "calculate the coordination number of the atoms in given structure", long="coord")]
     coordinate: Option<usize>,
+    #[structopt(help = "bond the atoms of --coord within this cutoff, instead of their atom radii", long="cutoff")]
+    cutoff: Option<f64>,
+    #[structopt(help = "bond the atoms of --coord by this set of radii: atom (default), covalent, bondi, alvarez, metallic or ionic", long="radii", conflicts_with="cutoff")]
+    radii: Option<RadiusSet>,
+    #[structopt(help = "scale the atom radii of the --coord bonds by this factor", long="radius-scale", conflicts_with="cutoff")]
+    radius_scale: Option<f64>,
+    #[structopt(help = "add this tolerance to the atom radii of the --coord bonds, 0.3 by default", long="tolerance", conflicts_with="cutoff")]
+    tolerance: Option<f64>,
+    #[structopt(help = "read the --coord bond cutoffs of pairs of elements from a TOML or JSON file", long="bonds", conflicts_with_all=&["cutoff", "radii", "radius-scale", "tolerance"])]
+    bonds: Option<PathBuf>,
     #[structopt(help = "calculate the interplanar spacing of the plains horizontal with the plain specified by given atoms", long="plane")]
     plain: Option<Vec<usize>>,
     #[structopt(help = "extract all structures into seperate file", long="split")]
     seperate: bool,
+    #[structopt(help = "pair the structures with the forces in given allfor.arc and report max force, RMS force and stress", long="forces")]
+    forces: Option<std::path::PathBuf>,
+    #[structopt(help = "number of threads to parse .arc files with, the whole file is then held in memory", short="t", long="threads")]
+    threads: Option<usize>,
+    #[structopt(help = "keep the block offsets of an .arc file in a sidecar <file>.idx, so later runs seek directly to the structures", long="index")]
+    index: bool,
+    #[structopt(help = "The output of --extract (extracted.arc) or --split ({}.arc, {} is the index), - for stdout", short="o", long="output")]
+    output: Option<PathBuf>,
+    #[structopt(help = "The output file format, arc for stdout if not specified", long="output-format")]
+    output_format: Option<String>,
 }
 
 #[derive(StructOpt)]
 struct CheckArgs{
     #[structopt(help = "the path to check", short="p", long="path")]
     path: std::path::PathBuf,
+    #[structopt(help = "keep the block offsets of an .arc file in a sidecar <file>.idx, so later runs seek directly to the structures", long="index")]
+    index: bool,
+    #[structopt(help = "the output of the unconverged structures (unconverged.arc), - for stdout", short="o", long="output")]
+    output: Option<PathBuf>,
 }
 
 #[derive(StructOpt)]
 struct ModifyArgs{
-    #[structopt(help = "the file to modify", short="f", long="file")]
+    #[structopt(help = "the file to modify, - for stdin", short="f", long="file")]
     file: std::path::PathBuf,
+    #[structopt(help = "the input file format, needed for stdin", long="input-format")]
+    input_format: Option<String>,
     #[structopt(help = "the structhre number to modify", short="n", long="number")]
     number: Option<usize>,
     #[structopt(help = "rearrange atoms by given coordinate", short="r", long="rearrange")]
     rearrange: Option<String>,
     #[structopt(help = "scale the crystal by given factor", short="s", long="scale")]
     scale: Option<Vec<f64>>,
+    #[structopt(help = "keep the block offsets of an .arc file in a sidecar <file>.idx, so later runs seek directly to the structures", long="index")]
+    index: bool,
+    #[structopt(help = "write the modified structure to this file instead of rearranged.arc and scaled.arc, - for stdout", short="o", long="output")]
+    output: Option<PathBuf>,
+    #[structopt(help = "the output file format, arc for stdout if not specified", long="output-format")]
+    output_format: Option<String>,
 }
 
 #[derive(StructOpt)]
 struct CompareArgs{
-    #[structopt(help = "the file to compare", short="f", long="file1")]
+    #[structopt(help = "the file to compare, - for stdin", short="f", long="file1")]
     file: std::path::PathBuf,
+    #[structopt(help = "the format of file1, needed for stdin", long="input-format")]
+    input_format: Option<String>,
     #[structopt(help = "the file to compare", short="F", long="file2")]
     file2: std::path::PathBuf,
     #[structopt(help = "number of threads", short="t", long="threads")]
@@ -80,37 +121,204 @@ struct CompareArgs{
 
 #[derive(StructOpt)]
 struct ConvertArgs {
-    #[structopt(help = "The file to convert", short="f", long="file")]
+    #[structopt(help = "The file to convert, - for stdin", short="f", long="file")]
     file: String,
-    #[structopt(help = "The input file format", short="i", long="input")]
+    #[structopt(help = "The input file format, needed for stdin", short="i", long="input-format", alias="input")]
     input_format:Option<String>,
-    #[structopt(help = "The output file format", short="F", long="format")]
+    #[structopt(help = "The output file format, arc for stdout if not specified", short="F", long="format", alias="output-format")]
     output_format:Option<String>,
-    #[structopt(help = "The output file", short="o", long="output")]
+    #[structopt(help = "The output file, - for stdout", short="o", long="output")]
     output: String,
+    #[structopt(help = "The elements of the LAMMPS atom types in order, e.g. O,H", long="types", use_delimiter = true)]
+    types: Vec<String>,
 }
 
-fn parse(args: ParseArgs){
-    let blocks = match parser::parser::read_file(&args.file, true){
-        Ok(blocks) => blocks.unwrap(),
+/// stream the blocks of a file one at a time, exiting on errors
+fn stream_blocks<'a>(reader: &'a dyn StructureIO, path: &Path) -> impl Iterator<Item = StructureBlock> + 'a {
+    let blocks = match reader.iter_structure(path, true){
+        Ok(blocks) => blocks,
         Err(e) => {
             eprintln!("{}: {}", "Error".red(), e);
             std::process::exit(1);
         }
     };
+    blocks.map(|block| match block {
+        Ok(block) => block,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
+        }
+    })
+}
+
+/// parse a whole file with several threads, .arc files are split between the threads
+fn parse_blocks_parallel(reader: &dyn StructureIO, input: &Input, threads: usize) -> Vec<StructureBlock> {
+    let blocks = match input.format {
+        "arc" => parser::parser::parse_parallel(&parser::parser::ArcParser::new(), &input.path, true, threads),
+        _ => reader.parse_structure(&input.path, true),
+    };
+    match blocks {
+        Ok(blocks) => blocks.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// the blocks of a file, cloned from `parsed` if it was parsed in parallel or streamed otherwise
+fn blocks_of<'a>(parsed: &'a Option<Vec<StructureBlock>>, reader: &'a dyn StructureIO, path: &Path) -> Box<dyn Iterator<Item = StructureBlock> + 'a> {
+    match parsed {
+        Some(blocks) => Box::new(blocks.iter().cloned()),
+        None => Box::new(stream_blocks(reader, path)),
+    }
+}
+
+/**
+a structure file given to a subcommand
+
+`-` is stdin, which is spooled to a temporary directory as the input may be read several times,
+and its format must be given. The format of other files is detected unless given.
+ */
+struct Input {
+    path: PathBuf,
+    format: &'static str,
+    _spool: Option<tempfile::TempDir>,
+}
+impl Input {
+    fn open(file: &Path, given: Option<&str>) -> Input {
+        let format = given.map(|format| match parser::parser::canonical_format(format) {
+            Some(canonical) => canonical,
+            None => {
+                eprintln!("{}: unknown file format: {}", "Error".red(), format);
+                std::process::exit(1);
+            }
+        });
+        if !is_stdio(file) {
+            return Input{ path: file.to_path_buf(), format: format.unwrap_or_else(|| format_of(file)), _spool: None };
+        }
+        let format = match format {
+            Some(format) => format,
+            None => {
+                eprintln!("{}: reading from stdin needs --input-format", "Error".red());
+                std::process::exit(1);
+            }
+        };
+        // the extension keeps the format and compression, `.arc` files are indexed next to the spool
+        let spooled = tempfile::TempDir::new().and_then(|dir| {
+            let path = dir.path().join(format!("stdin.{}", given.unwrap_or(format).to_lowercase()));
+            let mut file = std::fs::File::create(&path)?;
+            std::io::copy(&mut std::io::stdin().lock(), &mut file)?;
+            Ok(Input{ path, format, _spool: Some(dir) })
+        });
+        match spooled {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("{}: can not read stdin: {}", "Error".red(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// write blocks to a file in `format` or in the format of its name, `-` is stdout in `format` or arc
+fn write_blocks(blocks: &[StructureBlock], output: &Path, format: Option<&str>) {
+    let format = match format {
+        Some(format) => format,
+        None if is_stdio(output) => "arc",
+        None => parser::parser::get_file_type(output).unwrap_or("arc"),
+    };
+    if let Err(e) = parser_of(format).write_structure(blocks, output) {
+        eprintln!("{}: {}: {}", "Error".red(), output_name(output), e);
+        std::process::exit(1);
+    }
+}
+
+/// the name of an output in messages
+fn output_name(output: &Path) -> String {
+    match is_stdio(output) {
+        true => String::from("stdout"),
+        false => output.display().to_string(),
+    }
+}
+
+/// print a message, on stderr when structures are written to stdout
+fn report(output: &Path, message: &str) {
+    if is_stdio(output) {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// index an .arc file, through its sidecar if `sidecar` is set, exiting on errors
+fn open_index(path: &Path, sidecar: bool) -> ArcIndex {
+    let index = if sidecar { ArcIndex::open(path) } else { ArcIndex::build(path) };
+    match index {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// the parser of a file type, exiting on unknown formats
+fn parser_of(file_type: &str) -> Box<dyn StructureIO> {
+    match parser::parser::get_parser(file_type) {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// the format of an input file, detected from its content or name, exiting if it is unknown
+fn format_of(path: &Path) -> &'static str {
+    match parser::parser::detect_format(path) {
+        Ok(format) => format,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// fetch the block at position `n` of a file, .arc files are read through their index
+fn nth_block(reader: &dyn StructureIO, path: &Path, format: &str, n: usize, sidecar: bool) -> Option<StructureBlock> {
+    if format != "arc" || Compression::from_path(path) != Compression::None {
+        return stream_blocks(reader, path).nth(n);
+    }
+    match open_index(path, sidecar).get(n) {
+        Ok(block) => block,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse(args: ParseArgs){
+    // each task streams through the file by itself, so large files are parsed in constant memory,
+    // unless the file is parsed once by several threads
+    let input = Input::open(Path::new(&args.file), args.input_format.as_deref());
+    let path = input.path.as_path();
+    let reader = parser_of(input.format);
+    let parsed = args.threads.filter(|&n| n > 1).map(|n| parse_blocks_parallel(&*reader, &input, n));
     if args.minimum {
-        let min_energy = arc_analyzer::find_minimum_energy(&blocks);
+        let min_energy = arc_analyzer::find_minimum_energy(blocks_of(&parsed, &*reader, path));
         match min_energy {
             Some(energy) => println!("Minimum energy: {}", energy),
             None => println!("No minimum energy found"),
         }
     }
     if args.count {
-        let count = arc_analyzer::count_strucutre_block(&blocks);
+        let count = arc_analyzer::count_strucutre_block(blocks_of(&parsed, &*reader, path));
         println!("Number of structures: {}", count);
     }
     if args.consistency {
-        let atom_map = check_atom_consistency(&blocks);
+        let atom_map = check_atom_consistency(blocks_of(&parsed, &*reader, path));
         match atom_map {
             Some(map) => {
                 println!("All blocks have the same atoms:");
@@ -122,57 +330,112 @@ fn parse(args: ParseArgs){
         }
     }
     if args.energy_list {
-        list_energy(&blocks);
+        list_energy(blocks_of(&parsed, &*reader, path));
     }
     if let Some(a)  = args.extract {
-        if a > blocks.len(){
-            println!("{}: index out of range", "Error".red());
-            std::process::exit(1);
-        }
-        else if a == 0{
-            let min_block = arc_analyzer::extract_minimum(&blocks);
+        let output = args.output.clone().unwrap_or_else(|| PathBuf::from("extracted.arc"));
+        if a == 0{
+            let min_block = arc_analyzer::extract_minimum(blocks_of(&parsed, &*reader, path));
             match min_block {
                 None => println!("No minimum block found"),
                 Some(block) => {
-                    block.write(Path::new("extracted.arc")).unwrap();
-                    println!("Minimum block written to minimum.arc");
+                    write_blocks(&[block], &output, args.output_format.as_deref());
+                    report(&output, &format!("Minimum block written to {}", output_name(&output)));
                 }
             }
         }
         else {
-            blocks[a-1].clone().write(Path::new("extracted.arc")).unwrap();
-            println!("Block {} written to extracted.arc", a);
+            match nth_block(&*reader, path, input.format, a-1, args.index) {
+                Some(block) => {
+                    write_blocks(&[block], &output, args.output_format.as_deref());
+                    report(&output, &format!("Block {} written to {}", a, output_name(&output)));
+                },
+                None => {
+                    println!("{}: index out of range", "Error".red());
+                    std::process::exit(1);
+                }
+            }
         }
 
     }
-    match args.coordinate{
-        Some(n) => {
-            let coord = arc_analyzer::calc_coordination(&blocks[n as usize]);
-            for i in 0..coord.len(){
-                println!("Atom {}: {}", blocks[n as usize].atoms[i].element, coord[i]);
+    if let Some(n) = args.coordinate {
+        let criterion = match (&args.bonds, args.cutoff) {
+            (Some(bonds), _) => BondCriterion::from_file(bonds).unwrap_or_else(|e| {
+                eprintln!("{}: {}", "Error".red(), e);
+                std::process::exit(1);
+            }),
+            (None, Some(cutoff)) => BondCriterion::Fixed(cutoff),
+            (None, None) => BondCriterion::radii(args.radii, args.radius_scale, args.tolerance),
+        };
+        match nth_block(&*reader, path, input.format, n, args.index) {
+            Some(block) => {
+                let coord = arc_analyzer::calc_coordination(&block, &criterion).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", "Error".red(), e);
+                    std::process::exit(1);
+                });
+                for (atom, c) in block.atoms.iter().zip(&coord) {
+                    println!("Atom {}: {}", atom.element, c);
+                }
+            },
+            None => {
+                println!("{}: index out of range", "Error".red());
+                std::process::exit(1);
             }
-        },
-        None => (),
-    }
-    match args.plain {
-        Some(atoms) => {
-            let minimum = arc_analyzer::extract_minimum(&blocks).unwrap();
-            let spacing = arc_analyzer::calculate_interplanar_spacing(&minimum.atoms, atoms[0], atoms[1], atoms[2]);
-            print!("Interplanar spacing: {:?}", spacing);
         }
-        None => {
-            ()
+    }
+    if let Some(atoms) = args.plain {
+        let minimum = arc_analyzer::extract_minimum(blocks_of(&parsed, &*reader, path)).unwrap();
+        let spacing = arc_analyzer::calculate_interplanar_spacing(&minimum.atoms, atoms[0], atoms[1], atoms[2]);
+        print!("Interplanar spacing: {:?}", spacing);
+    }
+    if let Some(allfor) = args.forces {
+        let forces = match AllforParser::new().parse_forces(&allfor) {
+            Ok(forces) => forces,
+            Err(e) => {
+                eprintln!("{}: {}", "Error".red(), e);
+                std::process::exit(1);
+            }
+        };
+        let mut blocks: Vec<StructureBlock> = blocks_of(&parsed, &*reader, path).collect();
+        let paired = attach_forces(&mut blocks, forces);
+        println!("{} of {} structures have forces", paired, blocks.len());
+        for block in blocks.iter() {
+            match (arc_analyzer::max_force(block), arc_analyzer::rms_force(block)) {
+                (Some(max), Some(rms)) => {
+                    print!("Structure {}: max force {:.6}, RMS force {:.6}", block.number, max, rms);
+                    match block.stress {
+                        Some(stress) => println!(", stress {:?}", stress),
+                        None => println!(),
+                    }
+                },
+                _ => println!("Structure {}: no forces", block.number),
+            }
         }
     }
     if args.seperate {
-        for (i, block) in blocks.iter().enumerate(){
-            block.clone().write(Path::new(&format!("{}.arc", i))).unwrap();
+        let output = args.output.unwrap_or_else(|| PathBuf::from("{}.arc"));
+        let pattern = output.to_string_lossy().into_owned();
+        // a single stream holds all the structures
+        if is_stdio(&output) {
+            let blocks: Vec<StructureBlock> = blocks_of(&parsed, &*reader, path).collect();
+            write_blocks(&blocks, &output, args.output_format.as_deref());
+        }
+        else if !pattern.contains("{}") {
+            eprintln!("{}: the output of --split needs {{}} for the index, e.g. frames/{{}}.xyz", "Error".red());
+            std::process::exit(1);
+        }
+        else {
+            for (i, block) in blocks_of(&parsed, &*reader, path).enumerate(){
+                write_blocks(&[block], Path::new(&pattern.replace("{}", &i.to_string())), args.output_format.as_deref());
+            }
         }
     }
 }
 
 fn check(args: CheckArgs){
     let path = args.path;
+    // the messages go to stderr when the structures are written to stdout
+    let output = args.output.unwrap_or_else(|| PathBuf::from("unconverged.arc"));
     let badstr_path = path.join("Badstr.arc");
     let badstr = match parser::parser::read_file(badstr_path.to_str().unwrap(), true){
         Ok(blocks) => blocks.unwrap(),
@@ -181,93 +444,141 @@ fn check(args: CheckArgs){
             std::process::exit(1);
         }
     };
-    let unconverged_index = parser::parser::find_unconverged_strucutres(path).unwrap();
+    let lasp_in = path.join("lasp.in");
+    if lasp_in.exists() {
+        check_input(&lasp_in, &path.join("input.arc"), &output);
+    }
+    let log = match LaspOutLog::parse(&path.join("lasp.out")) {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
+        }
+    };
+    report(&output, &format!("SSW steps in lasp.out: {}, time consumed: {:.1} s", log.steps.len(), log.total_time()));
+    if let Some(step) = log.steps.last().filter(|s| s.status == StepStatus::Running) {
+        report(&output, &format!("step {} is still running", step.step));
+    }
+    for step in log.unconverged().filter(|s| s.structure.is_none()) {
+        eprintln!("unconverged step {} at line {} has no structure number", step.step, step.line);
+    }
+    let unconverged_index = log.unconverged_structures();
     if badstr.len() >= 3 || unconverged_index.len() >= 3{
-        println!("{}","this result might be unreliable!".red());
-        println!("structure in Badstr.arc: {}",badstr.len());
-        println!("unconverged iterations in lasp.out: {}", unconverged_index.len());
-        println!("finding unconverged strucutres");
-        let all_strucutres = match parser::parser::read_file("all.arc", true){
-            Ok(blocks) => blocks.unwrap(),
-            Err(error) => {
-                panic!("{}", error);
-            }
-        };
+        report(&output, &"this result might be unreliable!".red().to_string());
+        report(&output, &format!("structure in Badstr.arc: {}",badstr.len()));
+        report(&output, &format!("unconverged iterations in lasp.out: {}", log.unconverged().count()));
+        report(&output, "finding unconverged strucutres");
+        // only the unconverged blocks are parsed
+        let index = open_index(&path.join("all.arc"), args.index);
         let mut unconverged_structure: Vec<StructureBlock> = Vec::new();
         for i in unconverged_index{
-            for structure in &all_strucutres{
-                if structure.number == i {
-                    unconverged_structure.push(structure.clone());
+            for position in index.positions_of(i){
+                match index.get(position) {
+                    Ok(Some(structure)) => unconverged_structure.push(structure),
+                    Ok(None) => (),
+                    Err(e) => {
+                        eprintln!("{}: {}", "Error".red(), e);
+                        std::process::exit(1);
+                    },
                 }
             }
         }
-        let writer = parser::parser::get_parser("arc");
-        writer.write_structure(&unconverged_structure, std::path::Path::new("unconverged.arc")).unwrap();
-        println!("the unconverged structures have been written to unconverged.arc")
+        write_blocks(&unconverged_structure, &output, None);
+        report(&output, &format!("the unconverged structures have been written to {}", output_name(&output)));
     }
     else {
-        println!("{}","this result might be reliable!".green());
+        report(&output, &"this result might be reliable!".green().to_string());
     }    
 }
 
-fn modify(args: ModifyArgs){
-    let blocks = match parser::parser::read_file(args.file.to_str().unwrap(), true){
-        Ok(blocks) => blocks.unwrap(),
+/// validate lasp.in, against the first structure of input.arc if there is one
+fn check_input(lasp_in: &Path, structure: &Path, output: &Path) {
+    let input = match LaspInput::parse(lasp_in) {
+        Ok(input) => input,
         Err(e) => {
             eprintln!("{}: {}", "Error".red(), e);
             std::process::exit(1);
         }
     };
-    let mut block = match args.number{
-        Some(n) => blocks[n].to_owned(),
+    let block = match structure.exists() {
+        true => stream_blocks(&*parser_of(format_of(structure)), structure).next(),
+        false => None,
+    };
+    let issues = match block {
+        Some(block) => input.validate_against(&block),
+        None => input.validate(),
+    };
+    for issue in issues.iter() {
+        report(output, &format!("{}: lasp.in: {}", "Warning".yellow(), issue));
+    }
+}
+
+fn modify(args: ModifyArgs){
+    let input = Input::open(&args.file, args.input_format.as_deref());
+    let reader = parser_of(input.format);
+    let block = match args.number{
+        Some(n) => nth_block(&*reader, &input.path, input.format, n, args.index),
         None => {
-            arc_analyzer::extract_minimum(&blocks).unwrap().clone()
+            arc_analyzer::extract_minimum(stream_blocks(&*reader, &input.path))
         }
         
     };
-    match args.rearrange{
-        Some(coordinate) => {
-            let coordination = match coordinate.to_uppercase().as_str(){
-                "X" =>{
-                    arc_analyzer::rearrange_atoms(&mut block, |a, b| a.coordinate.0.partial_cmp(&b.coordinate.0).unwrap());
-                    Some("X")
-                },
-                "Y" =>{
-                    arc_analyzer::rearrange_atoms(&mut block, |a, b| a.coordinate.1.partial_cmp(&b.coordinate.1).unwrap());
-                    Some("Y")
-                },
-                "Z" =>{
-                    arc_analyzer::rearrange_atoms(&mut block, |a, b| a.coordinate.2.partial_cmp(&b.coordinate.2).unwrap());
-                    Some("Z")
-                },
-                _ => {
-                    println!("Please verify the sorting coordination: X, Y or Z.");
-                    None
-                }
-            };
-            block.write(std::path::Path::new("rearranged.arc")).unwrap();
-            match coordination{
-                Some(coord) => println!("the rearranged minimum structure (by {} value) has been generated.", coord),
-                None => println!("Please specify the coordination to be sorted!\n rearranged.arc reamains unchanged.")
+    // with an output, the structure is written once with every modification applied
+    let status = args.output.clone().unwrap_or_default();
+    let mut block = match block {
+        Some(block) => block,
+        None => {
+            eprintln!("{}: index out of range", "Error".red());
+            std::process::exit(1);
+        }
+    };
+    if let Some(coordinate) = args.rearrange {
+        let coordination = match coordinate.to_uppercase().as_str(){
+            "X" =>{
+                arc_analyzer::rearrange_atoms(&mut block, |a, b| a.coordinate.0.partial_cmp(&b.coordinate.0).unwrap());
+                Some("X")
+            },
+            "Y" =>{
+                arc_analyzer::rearrange_atoms(&mut block, |a, b| a.coordinate.1.partial_cmp(&b.coordinate.1).unwrap());
+                Some("Y")
+            },
+            "Z" =>{
+                arc_analyzer::rearrange_atoms(&mut block, |a, b| a.coordinate.2.partial_cmp(&b.coordinate.2).unwrap());
+                Some("Z")
+            },
+            _ => {
+                report(&status, "Please verify the sorting coordination: X, Y or Z.");
+                None
             }
-        },
-        None => (),
+        };
+        if args.output.is_none() {
+            write_blocks(std::slice::from_ref(&block), Path::new("rearranged.arc"), None);
+        }
+        match coordination{
+            Some(coord) => report(&status, &format!("the rearranged minimum structure (by {} value) has been generated.", coord)),
+            None if args.output.is_some() => report(&status, "Please specify the coordination to be sorted!\n the structure is written unsorted."),
+            None => report(&status, "Please specify the coordination to be sorted!\n rearranged.arc reamains unchanged.")
+        }
     }
-    match args.scale {
-        Some(scale) => {
-            let mut new_block = block.clone();
-            if scale.len() == 1{
-                new_block = new_block.expand_crystal(scale[0]);
-            }
-            else if scale.len() == 3 {
-                new_block = new_block.scale_crystal(modules::structures::CoordinateChoice::X, scale[0]);
-                new_block = new_block.scale_crystal(modules::structures::CoordinateChoice::Y, scale[1]);
-                new_block = new_block.scale_crystal(modules::structures::CoordinateChoice::Z, scale[2]);
-            }
-            new_block.write(std::path::Path::new("scaled.arc")).unwrap();
-            println!("the scaled structure has been generated.");
-        },
-        None => (),
+    if let Some(scale) = args.scale {
+        let mut new_block = block.clone();
+        if scale.len() == 1{
+            new_block = new_block.expand_crystal(scale[0]);
+        }
+        else if scale.len() == 3 {
+            new_block = new_block.scale_crystal(modules::structures::CoordinateChoice::X, scale[0]);
+            new_block = new_block.scale_crystal(modules::structures::CoordinateChoice::Y, scale[1]);
+            new_block = new_block.scale_crystal(modules::structures::CoordinateChoice::Z, scale[2]);
+        }
+        match args.output {
+            Some(_) => block = new_block,
+            None => write_blocks(&[new_block], Path::new("scaled.arc"), None),
+        }
+        report(&status, "the scaled structure has been generated.");
+    }
+    if let Some(output) = args.output {
+        write_blocks(&[block], &output, args.output_format.as_deref());
+        report(&output, &format!("the modified structure has been written to {}", output_name(&output)));
     }
 }
 
@@ -284,14 +595,10 @@ fn compare(args: CompareArgs){
     }
 
 
-    let blocks1 = match parser::parser::read_file(args.file.to_str().unwrap(), true){
-        Ok(blocks) => blocks.unwrap(),
-        Err(e) => {
-            eprintln!("{}: {}", "Error".red(), e);
-            std::process::exit(1);
-        }
-    };
-    let blocks2 = match parser::parser::read_file(args.file2.to_str().unwrap(), true){
+    let num_threads = args.threads.unwrap_or(1);
+    let input = Input::open(&args.file, args.input_format.as_deref());
+    let blocks1 = parse_blocks_parallel(&*parser_of(input.format), &input, num_threads);
+    let blocks2 = match parser::parser::read_file_parallel(args.file2.to_str().unwrap(), true, num_threads){
         Ok(blocks) => blocks.unwrap(),
         Err(e) => {
             eprintln!("{}: {}", "Error".red(), e);
@@ -302,35 +609,31 @@ fn compare(args: CompareArgs){
     let ref_block_arc = Arc::new(blocks2[0].clone());
     let blocks1_arc = Arc::new(blocks1);
     let substructure_size = ref_block_arc.atoms.len();
-    let num_threads = match args.threads{
-        Some(n) => n,
-        None => 1,
-    };
     let mut handles = Vec::new();
     // compare each block in blocks1 with ref_block
     for i in 0..num_threads{
         let thread_index = i;
-        let num_threads = num_threads;
         let ref_block = Arc::clone(&ref_block_arc);
         let blocks1 = Arc::clone(&blocks1_arc);
         let handle = std::thread::spawn(move || {
+            let criterion = BondCriterion::radii(None, None, Some(0.5));
             for block_index in (0..blocks1.len()).filter(|x| x % num_threads == thread_index){
                 // remove all atom that is not Fe
                 println!("thread {} checking block {}",thread_index, block_index);
                 let block = &blocks1[block_index];
                 let mut block = block.clone();
                 block.atoms.retain(|atom| atom.element == "Fe");
-                // calculate bond matrix of block
-                let bond_matrix = arc_analyzer::calc_coordination_matrix(&block);
-                // interate over all rows in bond_matrix
-                for i in 0..bond_matrix.nrows(){
-                    // find all j such that bond_matrix[i][j] != 0
-                    let mut neighbors = Vec::new();
-                    for j in 0..bond_matrix.ncols(){
-                        if bond_matrix[(i,j)] != 0{
-                            neighbors.push(j);
-                        }
-                    }
+                // find the bonded atoms of the block, across the cell faces in periodic blocks
+                let neighbor_list = match NeighborList::new(&block, &criterion) {
+                    Ok(neighbor_list) => neighbor_list,
+                    Err(e) => {
+                        eprintln!("{}: block {}: {}", "Error".red(), block_index, e);
+                        continue;
+                    },
+                };
+                // interate over all atoms
+                for i in 0..neighbor_list.len(){
+                    let neighbors = neighbor_list.neighbors(i);
                     // skip atoms whose neighbors is more than 11
                     if neighbors.len() > 11{
                         continue;
@@ -339,12 +642,20 @@ fn compare(args: CompareArgs){
                     let combinations = neighbors.iter().combinations(substructure_size - 1).collect::<Vec<_>>();
                     // iterate over all combinations
                     for combination in combinations.iter(){
+                        // in small cells the images of an atom, and of atom i, are neighbours as well,
+                        // a substructure holds every atom once
+                        let mut actual_atoms = combination.iter().map(|neighbor| neighbor.index).collect::<Vec<_>>();
+                        actual_atoms.push(i);
+                        if !actual_atoms.iter().all_unique() {
+                            continue;
+                        }
                         // create a position matrix consisting of the atoms in combination and atom i
                         let mut position_matrix = na::Matrix::<f64, Const<3>, Dyn, VecStorage<f64, Const<3>, Dyn>>::zeros(substructure_size);
-                        for (index, atom_index) in combination.iter().enumerate(){
-                            position_matrix[(0, index)] = block.atoms[**atom_index].coordinate.0;
-                            position_matrix[(1, index)] = block.atoms[**atom_index].coordinate.1;
-                            position_matrix[(2, index)] = block.atoms[**atom_index].coordinate.2;
+                        // neighbours are placed at their image next to atom i
+                        for (index, neighbor) in combination.iter().enumerate(){
+                            position_matrix[(0, index)] = block.atoms[i].coordinate.0 + neighbor.vector.0;
+                            position_matrix[(1, index)] = block.atoms[i].coordinate.1 + neighbor.vector.1;
+                            position_matrix[(2, index)] = block.atoms[i].coordinate.2 + neighbor.vector.2;
                         }
                         position_matrix[(0, substructure_size - 1)] = block.atoms[i].coordinate.0;
                         position_matrix[(1, substructure_size - 1)] = block.atoms[i].coordinate.1;
@@ -362,8 +673,6 @@ fn compare(args: CompareArgs){
                         // calculate the rmsd between the two matrices
                         let rmsd = arc_analyzer::calculate_rmsd_by_matrix(&position_matrix, &ref_position_matrix);
                         if rmsd < 0.3 {
-                            let mut actual_atoms = combination.clone();
-                            actual_atoms.push(&i);
                             println!("thread {} found a substructure at {}: rmsd={}; atoms={:?}",thread_index, block_index, rmsd, actual_atoms);
                         }
                     }
@@ -379,31 +688,38 @@ fn compare(args: CompareArgs){
     } 
 }
 
+/// the parser of a file type, LAMMPS parsers get the elements of the atom types
+fn typed_parser_of(file_type: &str, types: &[String]) -> Box<dyn StructureIO> {
+    match parser::parser::canonical_format(file_type) {
+        Some("lammps-data") => Box::new(LammpsDataParser::new().with_types(types.to_vec())),
+        Some("lammps-dump") => Box::new(LammpsDumpParser::new().with_types(types.to_vec())),
+        _ => parser_of(file_type),
+    }
+}
+
 fn convert(args: ConvertArgs){
-    let structures = match args.input_format {
-        Some(format) => {
-            let reader = parser::parser::get_parser(&format);
-            match reader.parse_structure(&Path::new(&args.file), true){
-                Ok(blocks) => blocks.unwrap(),
-                Err(e) => {
-                    eprintln!("{}: {}", "Error".red(), e);
-                    std::process::exit(1);
-                }
-            }
-        }
-        None => {
-            parser::parser::read_file(&args.file, true).unwrap().unwrap()
+    let input = Input::open(Path::new(&args.file), args.input_format.as_deref());
+    let reader = typed_parser_of(input.format, &args.types);
+    let structures = match reader.parse_structure(&input.path, true){
+        Ok(blocks) => blocks.unwrap(),
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
         }
     };
     let writer = match args.output_format {
         Some(format) => {
-            parser::parser::get_parser(&format)
+            typed_parser_of(&format, &args.types)
         }
+        None if is_stdio(Path::new(&args.output)) => typed_parser_of("arc", &args.types),
         None => {
-            parser::parser::get_parser(Path::new(&args.output).extension().unwrap().to_str().unwrap())
+            typed_parser_of(parser::parser::get_file_type(Path::new(&args.output)).unwrap_or(&args.output), &args.types)
         }
     };
-    writer.write_structure(&structures, Path::new(&args.output)).unwrap();
+    if let Err(e) = writer.write_structure(&structures, Path::new(&args.output)) {
+        eprintln!("{}: {}", "Error".red(), e);
+        std::process::exit(1);
+    }
 }
 
 fn main(){
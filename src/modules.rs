@@ -6,10 +6,11 @@ pub mod structures {
         Z
     }
 
-    use std::{fmt::Debug, io::Error, ops::Sub, path::Path};
+    use std::{fmt::Debug, io::{Error, ErrorKind}, ops::Sub, path::Path};
+    use nalgebra::{Matrix3, RowVector3, Vector3};
 
     use crate::parser::parser;
-    #[derive(Clone)] 
+    #[derive(Clone, Debug, PartialEq)] 
     pub struct Coordinate(pub f64,pub f64,pub f64);
 
     /// represents an atom, 
@@ -18,16 +19,50 @@ pub mod structures {
     pub struct Atom { 
         pub element: String,
         pub coordinate: Coordinate,
+        /// the BIOSYM columns of the atom, if it was read from an .arc file
+        pub record: Option<AtomRecord>,
+        /// the selective dynamics flags of a POSCAR, whether the atom may move along each axis
+        pub selective: Option<[bool; 3]>,
+    }
+
+    /**
+    the columns of a BIOSYM atom line besides the element and coordinates, e.g.
+    `C        7.210469000   10.148070000    0.813536200 CORE    1 C  C    0.0000    1`
+
+    the line as read is kept as well, and written back as long as the atom is unchanged
+     */
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct AtomRecord {
+        /// the atom label in the first column
+        pub label: String,
+        /// the residue number after `CORE`
+        pub residue: String,
+        /// the force-field type
+        pub ff_type: String,
+        pub charge: f64,
+        /// the index in the last column
+        pub index: String,
+        pub raw: String,
     }
 
     impl Sub for &Atom{
         type Output = Coordinate;
         fn sub(self, rhs: Self) -> Coordinate {
-            Coordinate{
-                0: self.coordinate.0 - rhs.coordinate.0,
-                1: self.coordinate.1 - rhs.coordinate.1,
-                2: self.coordinate.2 - rhs.coordinate.2
-            }
+            Coordinate(
+                self.coordinate.0 - rhs.coordinate.0,
+                self.coordinate.1 - rhs.coordinate.1,
+                self.coordinate.2 - rhs.coordinate.2
+            )
+        }
+    }
+    impl Atom {
+        /// fractional coordinates of the atom in a lattice
+        pub fn fractional(&self, lattice: &Lattice) -> Coordinate {
+            lattice.to_fractional(&self.coordinate)
+        }
+        /// move the atom to fractional coordinates of a lattice
+        pub fn set_fractional(&mut self, fractional: &Coordinate, lattice: &Lattice) {
+            self.coordinate = lattice.to_cartesian(fractional);
         }
     }
     impl Debug for Atom {
@@ -46,12 +81,142 @@ pub mod structures {
         pub beta: f64,
         pub gamma: f64,
     }
+    impl CrystalInfo {
+        /// lattice vectors of the cell as rows of a matrix,
+        /// in the standard orientation: a along x, b in the xy plane
+        pub fn lattice_vectors(&self) -> Matrix3<f64> {
+            let (cos_alpha, cos_beta, cos_gamma) = (self.alpha.to_radians().cos(), self.beta.to_radians().cos(), self.gamma.to_radians().cos());
+            let sin_gamma = self.gamma.to_radians().sin();
+            let cx = self.z * cos_beta;
+            // a zeroed cell (e.g. read from a .xyz file) has no meaningful c vector
+            let cy = if sin_gamma.abs() > f64::EPSILON { self.z * (cos_alpha - cos_beta * cos_gamma) / sin_gamma } else { 0.0 };
+            let cz = (self.z.powi(2) - cx.powi(2) - cy.powi(2)).max(0.0).sqrt();
+            Matrix3::new(
+                self.x, 0.0, 0.0,
+                self.y * cos_gamma, self.y * sin_gamma, 0.0,
+                cx, cy, cz
+            )
+        }
+        /// cell parameters of the lattice vectors given as rows of a matrix
+        pub fn from_lattice_vectors(lattice: &Matrix3<f64>) -> CrystalInfo {
+            let a: Vector3<f64> = lattice.row(0).transpose();
+            let b: Vector3<f64> = lattice.row(1).transpose();
+            let c: Vector3<f64> = lattice.row(2).transpose();
+            CrystalInfo {
+                x: a.norm(),
+                y: b.norm(),
+                z: c.norm(),
+                alpha: b.angle(&c).to_degrees(),
+                beta: a.angle(&c).to_degrees(),
+                gamma: a.angle(&b).to_degrees(),
+            }
+        }
+        /// the lattice of the cell in the standard orientation, `None` for a zeroed cell
+        pub fn lattice(&self) -> Option<Lattice> {
+            Lattice::new(self.lattice_vectors())
+        }
+    }
     impl Debug for CrystalInfo {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             write!(f, "Cell parameters: a={}, b={}, c={}, alpha={}, beta={}, gamma={}", self.x, self.y, self.z, self.alpha, self.beta, self.gamma)
         }
     }
 
+    /**
+    lattice vectors of a cell as the rows of a matrix, in angstrom
+
+    positions are row vectors as well, a cartesian position is its fractional coordinates times the matrix
+     */
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Lattice {
+        matrix: Matrix3<f64>,
+        inverse: Matrix3<f64>,
+    }
+    impl Lattice {
+        /// the lattice of the vectors given as rows, `None` if they are coplanar
+        pub fn new(matrix: Matrix3<f64>) -> Option<Lattice> {
+            let volume = matrix.determinant().abs();
+            if volume.is_nan() || volume < 1e-8 {
+                return None;
+            }
+            let inverse = matrix.try_inverse()?;
+            Some(Lattice { matrix, inverse })
+        }
+        /// the lattice of cell parameters, in the standard orientation: a along x, b in the xy plane
+        pub fn from_crystal_info(crystal: &CrystalInfo) -> Option<Lattice> {
+            crystal.lattice()
+        }
+        /// the cell parameters of the lattice
+        pub fn to_crystal_info(&self) -> CrystalInfo {
+            CrystalInfo::from_lattice_vectors(&self.matrix)
+        }
+        /// the lattice vectors as rows
+        pub fn matrix(&self) -> &Matrix3<f64> {
+            &self.matrix
+        }
+        /// the same cell rotated into the standard orientation of `CrystalInfo`
+        pub fn standard(&self) -> Lattice {
+            // the lengths and angles of a valid lattice always give a valid lattice
+            Lattice::new(self.to_crystal_info().lattice_vectors()).unwrap_or_else(|| self.clone())
+        }
+        /// the volume of the cell in cubic angstrom
+        pub fn volume(&self) -> f64 {
+            self.matrix.determinant().abs()
+        }
+        /// the reciprocal lattice, with a_i · b_j = δ_ij (without the factor 2π)
+        pub fn reciprocal(&self) -> Lattice {
+            Lattice { matrix: self.inverse.transpose(), inverse: self.matrix.transpose() }
+        }
+        /// fractional coordinates of a cartesian position
+        pub fn to_fractional(&self, coordinate: &Coordinate) -> Coordinate {
+            let v = RowVector3::new(coordinate.0, coordinate.1, coordinate.2) * self.inverse;
+            Coordinate(v[0], v[1], v[2])
+        }
+        /// cartesian position of fractional coordinates
+        pub fn to_cartesian(&self, fractional: &Coordinate) -> Coordinate {
+            let v = RowVector3::new(fractional.0, fractional.1, fractional.2) * self.matrix;
+            Coordinate(v[0], v[1], v[2])
+        }
+        /// the shortest periodic image of a vector between two positions
+        pub fn minimum_image(&self, vector: &Coordinate) -> Coordinate {
+            // the image closest in fractional coordinates bounds the search, skewed cells may have a shorter one
+            let fractional = self.to_fractional(vector);
+            let wrapped = self.to_cartesian(&Coordinate(fractional.0 - fractional.0.round(), fractional.1 - fractional.1.round(), fractional.2 - fractional.2.round()));
+            let length = |v: &Coordinate| v.0 * v.0 + v.1 * v.1 + v.2 * v.2;
+            let bound = length(&wrapped).sqrt() * (1.0 + 1e-12);
+            self.images_within(vector, bound).into_iter()
+                .min_by(|a, b| length(a).total_cmp(&length(b)))
+                .unwrap_or(wrapped)
+        }
+        /**
+        every periodic image of a vector between two positions not longer than `cutoff`
+
+        the search spans as many cells as the cutoff needs, so a cutoff beyond half the width of the cell gives
+        several images of the same pair, and images of the zero vector for an atom and its own copies
+         */
+        pub fn images_within(&self, vector: &Coordinate, cutoff: f64) -> Vec<Coordinate> {
+            let fractional = self.to_fractional(vector);
+            let fractional = [fractional.0, fractional.1, fractional.2];
+            // the columns of the inverse are the reciprocal vectors, a cutoff spans `cutoff * |b_i|` cells along a_i
+            let ranges: Vec<(i64, i64)> = (0..3).map(|i| {
+                let span = cutoff * self.inverse.column(i).norm();
+                ((-span - fractional[i]).ceil() as i64, (span - fractional[i]).floor() as i64)
+            }).collect();
+            let mut images = Vec::new();
+            for i in ranges[0].0..=ranges[0].1 {
+                for j in ranges[1].0..=ranges[1].1 {
+                    for k in ranges[2].0..=ranges[2].1 {
+                        let image = self.to_cartesian(&Coordinate(fractional[0] + i as f64, fractional[1] + j as f64, fractional[2] + k as f64));
+                        if image.0 * image.0 + image.1 * image.1 + image.2 * image.2 <= cutoff * cutoff {
+                            images.push(image);
+                        }
+                    }
+                }
+            }
+            images
+        }
+    }
+
     ///a block in an .arc file
     #[derive(Clone)] 
     pub struct StructureBlock{
@@ -61,6 +226,27 @@ pub mod structures {
         pub symmetry: String,
         pub crystal: CrystalInfo,
         pub atoms: Vec<Atom>,
+        /// whether the cell repeats periodically, false for clusters (`PBC=OFF`), even in a box
+        pub periodic: bool,
+        /// forces on the atoms in eV/angstrom, in the same order as `atoms`
+        pub forces: Option<Vec<Coordinate>>,
+        /// the six stress components of the cell, in the order LASP writes them (xx, yy, zz, xy, yz, zx)
+        pub stress: Option<[f64; 6]>,
+        /// the header lines of the block, if it was read from an .arc file
+        pub record: Option<BlockRecord>,
+    }
+
+    /// the raw header fields of a block of an .arc file, written back as long as the block is unchanged
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct BlockRecord {
+        /// the number between the structure number and the energy in the header line
+        pub second: f64,
+        /// the header line as read
+        pub header: String,
+        /// the `!DATE` line as read
+        pub date: Option<String>,
+        /// the `PBC` line as read
+        pub cell: Option<String>,
     }
 
     impl StructureBlock {
@@ -75,9 +261,35 @@ pub mod structures {
         ///write the block to a file
         pub fn write(&self, path:&Path) -> Result<(), Error>{
             // figure out the output format
-            let file_type = path.extension().unwrap().to_str().unwrap();
-            let writer = parser::get_parser(file_type);
-            writer.write_structure(&vec![self.clone()], path)?;
+            let writer = parser::get_file_type(path)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("unknown file format: {}", path.display())))
+                .and_then(|file_type| parser::get_parser(file_type).map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string())))?;
+            writer.write_structure(std::slice::from_ref(self), path)?;
+            Ok(())
+        }
+        /// the lattice of the cell, `None` if the block has no cell
+        pub fn lattice(&self) -> Option<Lattice> {
+            self.crystal.lattice()
+        }
+        /// the lattice of the cell if the block is periodic, `None` for clusters and blocks without cell
+        pub fn periodic_lattice(&self) -> Option<Lattice> {
+            self.lattice().filter(|_| self.periodic)
+        }
+        /// fractional coordinates of the atoms, `None` if the block has no cell
+        pub fn fractional_coordinates(&self) -> Option<Vec<Coordinate>> {
+            let lattice = self.lattice()?;
+            Some(self.atoms.iter().map(|atom| atom.fractional(&lattice)).collect())
+        }
+        /// move the atoms to fractional coordinates of the cell, given in the same order as `atoms`
+        pub fn set_fractional_coordinates(&mut self, fractional: &[Coordinate]) -> Result<(), Error> {
+            let lattice = self.lattice()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("block {} has no cell", self.number)))?;
+            if fractional.len() != self.atoms.len() {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("{} coordinates given for {} atoms", fractional.len(), self.atoms.len())));
+            }
+            for (atom, fractional) in self.atoms.iter_mut().zip(fractional) {
+                atom.set_fractional(fractional, &lattice);
+            }
             Ok(())
         }
         pub fn expand_crystal(&self, scale:f64) -> StructureBlock{
@@ -105,1100 +317,342 @@ pub mod structures {
 }
 
 pub mod periodic_table {
-    use std::{collections::HashMap};
+    //! the elements and their properties, by symbol, name or atomic number
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::str::FromStr;
+    use std::sync::LazyLock;
+
+    use serde::Deserialize;
+
+    /// a set of radii of the elements, in angstroms
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum RadiusSet {
+        /// the `atom_radius` of every element
+        #[default]
+        Atom,
+        /// single-bond covalent radii of Cordero et al. (2008), up to Cm
+        Covalent,
+        /// van der Waals radii of Bondi (1964), of the elements he tabulated
+        Bondi,
+        /// van der Waals radii of Alvarez (2013), up to Es
+        Alvarez,
+        /// metallic radii for twelve neighbours, of the metals up to Cm
+        Metallic,
+        /// Shannon (1976) radii of the common ion of `ion_charge`, with six neighbours
+        Ionic,
+    }
+    impl RadiusSet {
+        pub const ALL: [RadiusSet; 6] = [RadiusSet::Atom, RadiusSet::Covalent, RadiusSet::Bondi, RadiusSet::Alvarez, RadiusSet::Metallic, RadiusSet::Ionic];
+        pub fn name(&self) -> &'static str {
+            match self {
+                RadiusSet::Atom => "atom",
+                RadiusSet::Covalent => "covalent",
+                RadiusSet::Bondi => "bondi",
+                RadiusSet::Alvarez => "alvarez",
+                RadiusSet::Metallic => "metallic",
+                RadiusSet::Ionic => "ionic",
+            }
+        }
+    }
+    impl fmt::Display for RadiusSet {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.name())
+        }
+    }
+    impl FromStr for RadiusSet {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            RadiusSet::ALL.into_iter().find(|set| set.name().eq_ignore_ascii_case(s)).ok_or_else(|| {
+                format!("unknown radius set `{}`, expected one of {}", s, RadiusSet::ALL.map(|set| set.name()).join(", "))
+            })
+        }
+    }
+
+    /// an element missing from the table, or a radius missing from a set
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum ElementError {
+        Unknown(String),
+        NoRadius(String, RadiusSet),
+    }
+    impl fmt::Display for ElementError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ElementError::Unknown(label) => write!(f, "unknown element `{}`", label),
+                ElementError::NoRadius(symbol, set) => write!(f, "no {} radius for {}", set, symbol),
+            }
+        }
+    }
+    impl std::error::Error for ElementError {}
 
     #[derive(Debug)]
     #[derive(PartialEq)]
-pub struct Element {
+    pub struct Element {
+        pub symbol: String,
         pub name: String,
         pub atomic_number: u64,
+        /// the standard atomic weight, or the mass number of `isotope` for radioactive elements
         pub mass: f64,
         pub valence_radius: f64,
         pub valence_electrons: u32,
         pub atom_radius: f64, // in angstroms
+        pub covalent_radius: Option<f64>,
+        pub vdw_radius_bondi: Option<f64>,
+        pub vdw_radius_alvarez: Option<f64>,
+        pub metallic_radius: Option<f64>,
+        pub ionic_radius: Option<f64>,
+        /// the charge of the ion of `ionic_radius`
+        pub ion_charge: Option<i32>,
+        /// on the Pauling scale
+        pub electronegativity: Option<f64>,
+        /// the Jmol colour, as red, green and blue
+        pub color: [u8; 3],
+        /// the mass number of the most abundant isotope, or of the longest-lived one
+        pub isotope: u32,
     }
 
+    impl Element {
+        /// the radius of the element in a set, if the set has one
+        pub fn radius(&self, set: RadiusSet) -> Option<f64> {
+            match set {
+                RadiusSet::Atom => Some(self.atom_radius),
+                RadiusSet::Covalent => self.covalent_radius,
+                RadiusSet::Bondi => self.vdw_radius_bondi,
+                RadiusSet::Alvarez => self.vdw_radius_alvarez,
+                RadiusSet::Metallic => self.metallic_radius,
+                RadiusSet::Ionic => self.ionic_radius,
+            }
+        }
+        /// the colour as `#RRGGBB`
+        pub fn hex_color(&self) -> String {
+            format!("#{:02X}{:02X}{:02X}", self.color[0], self.color[1], self.color[2])
+        }
+    }
+
+    /// symbol, name, mass, valence radius, valence electrons, atom radius, covalent radius, Bondi and Alvarez
+    /// radii, metallic radius, ion charge and ionic radius, electronegativity, colour and isotope
+    type Row = (&'static str, &'static str, f64, f64, u32, f64, Option<f64>, Option<f64>, Option<f64>, Option<f64>, Option<(i32, f64)>, Option<f64>, u32, u32);
+
+    /// the elements in order of atomic number
+    static ELEMENTS: [Row; 118] = [
+        ("H", "Hydrogen", 1.008, 0.32, 1, 0.25, Some(0.31), Some(1.2), Some(1.2), None, None, Some(2.2), 0xFFFFFF, 1),
+        ("He", "Helium", 4.003, 0.93, 2, 0.31, Some(0.28), Some(1.4), Some(1.43), None, None, None, 0xD9FFFF, 4),
+        ("Li", "Lithium", 6.941, 1.23, 1, 1.67, Some(1.28), Some(1.82), Some(2.12), Some(1.52), Some((1, 0.76)), Some(0.98), 0xCC80FF, 7),
+        ("Be", "Beryllium", 9.0122, 0.90, 2, 1.12, Some(0.96), None, Some(1.98), Some(1.12), Some((2, 0.45)), Some(1.57), 0xC2FF00, 9),
+        ("B", "Boron", 10.811, 0.82, 3, 0.87, Some(0.84), None, Some(1.91), None, Some((3, 0.27)), Some(2.04), 0xFFB5B5, 11),
+        ("C", "Carbon", 12.011, 0.67, 4, 0.77, Some(0.76), Some(1.7), Some(1.77), None, Some((4, 0.16)), Some(2.55), 0x909090, 12),
+        ("N", "Nitrogen", 14.007, 0.56, 5, 0.75, Some(0.71), Some(1.55), Some(1.66), None, Some((-3, 1.46)), Some(3.04), 0x3050F8, 14),
+        ("O", "Oxygen", 16.00, 0.60, 6, 0.73, Some(0.66), Some(1.52), Some(1.5), None, Some((-2, 1.4)), Some(3.44), 0xFF0D0D, 16),
+        ("F", "Fluorine", 18.998, 0.50, 7, 0.71, Some(0.57), Some(1.47), Some(1.46), None, Some((-1, 1.33)), Some(3.98), 0x90E050, 19),
+        ("Ne", "Neon", 20.180, 1.12, 8, 0.69, Some(0.58), Some(1.54), Some(1.58), None, None, None, 0xB3E3F5, 20),
+        ("Na", "Sodium", 22.990, 1.54, 1, 1.90, Some(1.66), Some(2.27), Some(2.5), Some(1.86), Some((1, 1.02)), Some(0.93), 0xAB5CF2, 23),
+        ("Mg", "Magnesium", 24.305, 1.36, 2, 1.45, Some(1.41), Some(1.73), Some(2.51), Some(1.6), Some((2, 0.72)), Some(1.31), 0x8AFF00, 24),
+        ("Al", "Aluminum", 26.982, 1.18, 3, 1.18, Some(1.21), None, Some(2.25), Some(1.43), Some((3, 0.535)), Some(1.61), 0xBFA6A6, 27),
+        ("Si", "Silicon", 28.086, 1.11, 4, 1.11, Some(1.11), Some(2.1), Some(2.19), None, Some((4, 0.4)), Some(1.9), 0xF0C8A0, 28),
+        ("P", "Phosphorus", 30.974, 1.06, 5, 1.06, Some(1.07), Some(1.8), Some(1.9), None, Some((5, 0.38)), Some(2.19), 0xFF8000, 31),
+        ("S", "Sulfur", 32.066, 1.02, 6, 1.02, Some(1.05), Some(1.8), Some(1.89), None, Some((-2, 1.84)), Some(2.58), 0xFFFF30, 32),
+        ("Cl", "Chlorine", 35.453, 0.99, 7, 0.99, Some(1.02), Some(1.75), Some(1.82), None, Some((-1, 1.81)), Some(3.16), 0x1FF01F, 35),
+        ("Ar", "Argon", 39.948, 1.54, 8, 0.97, Some(1.06), Some(1.88), Some(1.83), None, None, None, 0x80D1E3, 40),
+        ("K", "Potassium", 39.098, 2.03, 1, 2.43, Some(2.03), Some(2.75), Some(2.73), Some(2.27), Some((1, 1.38)), Some(0.82), 0x8F40D4, 39),
+        ("Ca", "Calcium", 40.078, 1.74, 2, 1.94, Some(1.76), None, Some(2.62), Some(1.97), Some((2, 1.0)), Some(1.0), 0x3DFF00, 40),
+        ("Sc", "Scandium", 44.956, 1.44, 3, 1.84, Some(1.7), None, Some(2.58), Some(1.64), Some((3, 0.745)), Some(1.36), 0xE6E6E6, 45),
+        ("Ti", "Titanium", 47.867, 1.32, 4, 1.76, Some(1.6), None, Some(2.46), Some(1.47), Some((4, 0.605)), Some(1.54), 0xBFC2C7, 48),
+        ("V", "Vanadium", 50.942, 1.22, 5, 1.71, Some(1.53), None, Some(2.42), Some(1.35), Some((5, 0.54)), Some(1.63), 0xA6A6AB, 51),
+        ("Cr", "Chromium", 51.996, 1.18, 6, 1.66, Some(1.39), None, Some(2.45), Some(1.29), Some((3, 0.615)), Some(1.66), 0x8A99C7, 52),
+        ("Mn", "Manganese", 54.938, 1.17, 7, 1.61, Some(1.39), None, Some(2.45), Some(1.37), Some((2, 0.83)), Some(1.55), 0x9C7AC7, 55),
+        ("Fe", "Iron", 55.845, 1.32, 8, 1.17, Some(1.32), None, Some(2.44), Some(1.26), Some((3, 0.645)), Some(1.83), 0xE06633, 56),
+        ("Co", "Cobalt", 58.933, 1.26, 9, 1.16, Some(1.26), None, Some(2.4), Some(1.25), Some((2, 0.745)), Some(1.88), 0xF090A0, 59),
+        ("Ni", "Nickel", 58.693, 1.24, 10, 1.15, Some(1.24), Some(1.63), Some(2.4), Some(1.25), Some((2, 0.69)), Some(1.91), 0x50D050, 58),
+        ("Cu", "Copper", 63.546, 1.32, 11, 1.17, Some(1.32), Some(1.4), Some(2.38), Some(1.28), Some((2, 0.73)), Some(1.9), 0xC88033, 63),
+        ("Zn", "Zinc", 65.38, 1.22, 12, 1.25, Some(1.22), Some(1.39), Some(2.39), Some(1.37), Some((2, 0.74)), Some(1.65), 0x7D80B0, 64),
+        ("Ga", "Gallium", 69.723, 1.22, 3, 1.26, Some(1.22), Some(1.87), Some(2.32), Some(1.53), Some((3, 0.62)), Some(1.81), 0xC28F8F, 69),
+        ("Ge", "Germanium", 72.63, 1.20, 4, 1.22, Some(1.2), None, Some(2.29), None, Some((4, 0.53)), Some(2.01), 0x668F8F, 74),
+        ("As", "Arsenic", 74.922, 1.19, 5, 1.19, Some(1.19), Some(1.85), Some(1.88), None, Some((5, 0.46)), Some(2.18), 0xBD80E3, 75),
+        ("Se", "Selenium", 78.971, 1.20, 6, 1.16, Some(1.2), Some(1.9), Some(1.82), None, Some((-2, 1.98)), Some(2.55), 0xFFA100, 80),
+        ("Br", "Bromine", 79.904, 1.20, 7, 1.14, Some(1.2), Some(1.85), Some(1.86), None, Some((-1, 1.96)), Some(2.96), 0xA62929, 79),
+        ("Kr", "Krypton", 83.798, 1.16, 8, 1.10, Some(1.16), Some(2.02), Some(2.25), None, None, Some(3.0), 0x5CB8D1, 84),
+        ("Rb", "Rubidium", 85.468, 2.16, 1, 2.35, Some(2.2), None, Some(3.21), Some(2.48), Some((1, 1.52)), Some(0.82), 0x702EB0, 85),
+        ("Sr", "Strontium", 87.62, 1.91, 2, 2.0, Some(1.95), None, Some(2.84), Some(2.15), Some((2, 1.18)), Some(0.95), 0x00FF00, 88),
+        ("Y", "Yttrium", 88.906, 1.62, 3, 1.8, Some(1.9), None, Some(2.75), Some(1.82), Some((3, 0.9)), Some(1.22), 0x94FFFF, 89),
+        ("Zr", "Zirconium", 91.224, 1.45, 4, 1.6, Some(1.75), None, Some(2.52), Some(1.6), Some((4, 0.72)), Some(1.33), 0x94E0E0, 90),
+        ("Nb", "Niobium", 92.906, 1.34, 5, 1.45, Some(1.64), None, Some(2.56), Some(1.47), Some((5, 0.64)), Some(1.6), 0x73C2C9, 93),
+        ("Mo", "Molybdenum", 95.95, 1.30, 6, 1.4, Some(1.54), None, Some(2.45), Some(1.4), Some((6, 0.59)), Some(2.16), 0x54B5B5, 98),
+        ("Tc", "Technetium", 98.0, 1.27, 7, 1.35, Some(1.47), None, Some(2.44), Some(1.35), Some((4, 0.645)), Some(1.9), 0x3B9E9E, 98),
+        ("Ru", "Ruthenium", 101.07, 1.25, 8, 1.3, Some(1.46), None, Some(2.46), Some(1.34), Some((3, 0.68)), Some(2.2), 0x248F8F, 102),
+        ("Rh", "Rhodium", 102.91, 1.25, 9, 1.35, Some(1.42), None, Some(2.44), Some(1.34), Some((3, 0.665)), Some(2.28), 0x0A7D8C, 103),
+        ("Pd", "Palladium", 106.42, 1.28, 10, 1.4, Some(1.39), Some(1.63), Some(2.15), Some(1.37), Some((2, 0.86)), Some(2.2), 0x006985, 106),
+        ("Ag", "Silver", 107.87, 1.34, 11, 1.6, Some(1.45), Some(1.72), Some(2.53), Some(1.44), Some((1, 1.15)), Some(1.93), 0xC0C0C0, 107),
+        ("Cd", "Cadmium", 112.41, 1.48, 12, 1.55, Some(1.44), Some(1.58), Some(2.49), Some(1.52), Some((2, 0.95)), Some(1.69), 0xFFD98F, 114),
+        ("In", "Indium", 114.82, 1.44, 3, 1.55, Some(1.42), Some(1.93), Some(2.43), Some(1.67), Some((3, 0.8)), Some(1.78), 0xA67573, 115),
+        ("Sn", "Tin", 118.71, 1.41, 4, 1.45, Some(1.39), Some(2.17), Some(2.42), Some(1.58), Some((4, 0.69)), Some(1.96), 0x668080, 120),
+        ("Sb", "Antimony", 121.76, 1.40, 5, 1.45, Some(1.39), None, Some(2.47), None, Some((3, 0.76)), Some(2.05), 0x9E63B5, 121),
+        ("Te", "Tellurium", 127.60, 1.36, 6, 1.4, Some(1.38), Some(2.06), Some(1.99), None, Some((-2, 2.21)), Some(2.1), 0xD47A00, 130),
+        ("I", "Iodine", 126.90, 1.33, 7, 1.4, Some(1.39), Some(1.98), Some(2.04), None, Some((-1, 2.2)), Some(2.66), 0x940094, 127),
+        ("Xe", "Xenon", 131.29, 1.31, 8, 1.3, Some(1.4), Some(2.16), Some(2.06), None, None, Some(2.6), 0x429EB0, 132),
+        ("Cs", "Cesium", 132.91, 2.35, 1, 2.6, Some(2.44), None, Some(3.48), Some(2.65), Some((1, 1.67)), Some(0.79), 0x57178F, 133),
+        ("Ba", "Barium", 137.33, 1.98, 2, 2.15, Some(2.15), None, Some(3.03), Some(2.22), Some((2, 1.35)), Some(0.89), 0x00C900, 138),
+        ("La", "Lanthanum", 138.91, 1.69, 3, 1.95, Some(2.07), None, Some(2.98), Some(1.87), Some((3, 1.032)), Some(1.1), 0x70D4FF, 139),
+        ("Ce", "Cerium", 140.116, 1.65, 4, 1.85, Some(2.04), None, Some(2.88), Some(1.82), Some((3, 1.01)), Some(1.12), 0xFFFFC7, 140),
+        ("Pr", "Praseodymium", 140.908, 1.65, 5, 1.85, Some(2.03), None, Some(2.92), Some(1.82), Some((3, 0.99)), Some(1.13), 0xD9FFC7, 141),
+        ("Nd", "Neodymium", 144.242, 1.64, 6, 1.85, Some(2.01), None, Some(2.95), Some(1.81), Some((3, 0.983)), Some(1.14), 0xC7FFC7, 142),
+        ("Pm", "Promethium", 145.0, 1.63, 7, 1.85, Some(1.99), None, None, Some(1.83), Some((3, 0.97)), Some(1.13), 0xA3FFC7, 145),
+        ("Sm", "Samarium", 150.36, 1.62, 8, 1.85, Some(1.98), None, Some(2.9), Some(1.8), Some((3, 0.958)), Some(1.17), 0x8FFFC7, 152),
+        ("Eu", "Europium", 151.964, 1.85, 9, 1.85, Some(1.98), None, Some(2.87), Some(2.04), Some((3, 0.947)), Some(1.2), 0x61FFC7, 153),
+        ("Gd", "Gadolinium", 157.25, 1.61, 10, 1.80, Some(1.96), None, Some(2.83), Some(1.8), Some((3, 0.938)), Some(1.2), 0x45FFC7, 158),
+        ("Tb", "Terbium", 158.925, 1.59, 11, 1.75, Some(1.94), None, Some(2.79), Some(1.78), Some((3, 0.923)), Some(1.1), 0x30FFC7, 159),
+        ("Dy", "Dysprosium", 162.500, 1.59, 12, 1.75, Some(1.92), None, Some(2.87), Some(1.77), Some((3, 0.912)), Some(1.22), 0x1FFFC7, 164),
+        ("Ho", "Holmium", 164.930, 1.58, 13, 1.75, Some(1.92), None, Some(2.81), Some(1.77), Some((3, 0.901)), Some(1.23), 0x00FF9C, 165),
+        ("Er", "Erbium", 167.259, 1.57, 14, 1.75, Some(1.89), None, Some(2.83), Some(1.76), Some((3, 0.89)), Some(1.24), 0x00E675, 166),
+        ("Tm", "Thulium", 168.934, 1.56, 15, 1.75, Some(1.9), None, Some(2.79), Some(1.75), Some((3, 0.88)), Some(1.25), 0x00D452, 169),
+        ("Yb", "Ytterbium", 173.054, 1.74, 16, 1.75, Some(1.87), None, Some(2.8), Some(1.94), Some((3, 0.868)), Some(1.1), 0x00BF38, 174),
+        ("Lu", "Lutetium", 174.967, 1.56, 3, 1.75, Some(1.87), None, Some(2.74), Some(1.73), Some((3, 0.861)), Some(1.27), 0x00AB24, 175),
+        ("Hf", "Hafnium", 178.49, 1.44, 4, 1.55, Some(1.75), None, Some(2.63), Some(1.59), Some((4, 0.71)), Some(1.3), 0x4DC2FF, 180),
+        ("Ta", "Tantalum", 180.948, 1.34, 5, 1.45, Some(1.7), None, Some(2.53), Some(1.47), Some((5, 0.64)), Some(1.5), 0x4DA6FF, 181),
+        ("W", "Tungsten", 183.84, 1.30, 6, 1.35, Some(1.62), None, Some(2.57), Some(1.41), Some((6, 0.6)), Some(2.36), 0x2194D6, 184),
+        ("Re", "Rhenium", 186.207, 1.28, 7, 1.35, Some(1.51), None, Some(2.49), Some(1.37), Some((4, 0.63)), Some(1.9), 0x267DAB, 187),
+        ("Os", "Osmium", 190.23, 1.26, 8, 1.30, Some(1.44), None, Some(2.48), Some(1.35), Some((4, 0.63)), Some(2.2), 0x266696, 192),
+        ("Ir", "Iridium", 192.217, 1.27, 9, 1.35, Some(1.41), None, Some(2.41), Some(1.36), Some((4, 0.625)), Some(2.2), 0x175487, 193),
+        ("Pt", "Platinum", 195.084, 1.30, 10, 1.35, Some(1.36), Some(1.72), Some(2.29), Some(1.39), Some((2, 0.8)), Some(2.28), 0xD0D0E0, 195),
+        ("Au", "Gold", 196.967, 1.34, 11, 1.35, Some(1.36), Some(1.66), Some(2.32), Some(1.44), Some((3, 0.85)), Some(2.54), 0xFFD123, 197),
+        ("Hg", "Mercury", 200.592, 1.49, 12, 1.50, Some(1.32), Some(1.55), Some(2.45), Some(1.55), Some((2, 1.02)), Some(2.0), 0xB8B8D0, 202),
+        ("Tl", "Thallium", 204.38, 1.48, 3, 1.90, Some(1.45), Some(1.96), Some(2.47), Some(1.71), Some((1, 1.5)), Some(1.62), 0xA6544D, 205),
+        ("Pb", "Lead", 207.2, 1.47, 4, 1.80, Some(1.46), Some(2.02), Some(2.6), Some(1.75), Some((2, 1.19)), Some(2.33), 0x575961, 208),
+        ("Bi", "Bismuth", 208.980, 1.46, 5, 1.60, Some(1.48), None, Some(2.54), Some(1.82), Some((3, 1.03)), Some(2.02), 0x9E4FB5, 209),
+        ("Po", "Polonium", 209.0, 1.46, 6, 1.50, Some(1.4), None, None, None, Some((4, 0.94)), Some(2.0), 0xAB5C00, 209),
+        ("At", "Astatine", 210.0, 1.45, 7, 1.50, Some(1.5), None, None, None, Some((7, 0.62)), Some(2.2), 0x754F45, 210),
+        ("Rn", "Radon", 222.0, 1.43, 8, 1.50, Some(1.5), None, None, None, None, Some(2.2), 0x428296, 222),
+        ("Fr", "Francium", 223.0, 2.5, 1, 2.60, Some(2.6), None, None, None, Some((1, 1.8)), Some(0.7), 0x420066, 223),
+        ("Ra", "Radium", 226.0, 2.1, 2, 2.15, Some(2.21), None, None, None, Some((2, 1.48)), Some(0.9), 0x007D00, 226),
+        ("Ac", "Actinium", 227.0, 1.95, 3, 1.95, Some(2.15), None, Some(2.8), Some(1.88), Some((3, 1.12)), Some(1.1), 0x70ABFA, 227),
+        ("Th", "Thorium", 232.038, 1.80, 4, 1.80, Some(2.06), None, Some(2.93), Some(1.8), Some((4, 0.94)), Some(1.3), 0x00BAFF, 232),
+        ("Pa", "Protactinium", 231.036, 1.80, 5, 1.80, Some(2.0), None, Some(2.88), Some(1.61), Some((5, 0.78)), Some(1.5), 0x00A1FF, 231),
+        ("U", "Uranium", 238.029, 1.75, 6, 1.75, Some(1.96), Some(1.86), Some(2.71), Some(1.56), Some((4, 0.89)), Some(1.38), 0x008FFF, 238),
+        ("Np", "Neptunium", 237.0, 1.75, 7, 1.75, Some(1.9), None, Some(2.82), Some(1.55), Some((4, 0.87)), Some(1.36), 0x0080FF, 237),
+        ("Pu", "Plutonium", 244.0, 1.75, 8, 1.75, Some(1.87), None, Some(2.81), Some(1.59), Some((4, 0.86)), Some(1.28), 0x006BFF, 244),
+        ("Am", "Americium", 243.0, 1.75, 9, 1.75, Some(1.8), None, Some(2.83), Some(1.73), Some((3, 0.975)), Some(1.3), 0x545CF2, 243),
+        ("Cm", "Curium", 247.0, 1.75, 10, 1.75, Some(1.69), None, Some(3.05), Some(1.74), Some((3, 0.97)), Some(1.3), 0x785CE3, 247),
+        ("Bk", "Berkelium", 247.0, 1.75, 11, 1.75, None, None, Some(3.4), None, Some((3, 0.96)), Some(1.3), 0x8A4FE3, 247),
+        ("Cf", "Californium", 251.0, 1.75, 12, 1.75, None, None, Some(3.05), None, Some((3, 0.95)), Some(1.3), 0xA136D4, 251),
+        ("Es", "Einsteinium", 252.0, 1.75, 13, 1.75, None, None, Some(2.7), None, None, Some(1.3), 0xB31FD4, 252),
+        ("Fm", "Fermium", 257.0, 1.75, 14, 1.75, None, None, None, None, None, Some(1.3), 0xB31FBA, 257),
+        ("Md", "Mendelevium", 258.0, 1.75, 15, 1.75, None, None, None, None, None, Some(1.3), 0xB30DA6, 258),
+        ("No", "Nobelium", 259.0, 1.75, 16, 1.75, None, None, None, None, None, Some(1.3), 0xBD0D87, 259),
+        ("Lr", "Lawrencium", 262.0, 1.75, 3, 1.75, None, None, None, None, None, None, 0xC70066, 262),
+        ("Rf", "Rutherfordium", 267.0, 1.7, 4, 1.7, None, None, None, None, None, None, 0xCC0059, 267),
+        ("Db", "Dubnium", 268.0, 1.7, 5, 1.7, None, None, None, None, None, None, 0xD1004F, 268),
+        ("Sg", "Seaborgium", 269.0, 1.7, 6, 1.7, None, None, None, None, None, None, 0xD90045, 269),
+        ("Bh", "Bohrium", 270.0, 1.7, 7, 1.7, None, None, None, None, None, None, 0xE00038, 270),
+        ("Hs", "Hassium", 277.0, 1.7, 8, 1.7, None, None, None, None, None, None, 0xE6002E, 277),
+        ("Mt", "Meitnerium", 278.0, 1.7, 9, 1.7, None, None, None, None, None, None, 0xEB0026, 278),
+        ("Ds", "Darmstadtium", 281.0, 1.7, 10, 1.7, None, None, None, None, None, None, 0xFF1493, 281),
+        ("Rg", "Roentgenium", 282.0, 1.7, 11, 1.7, None, None, None, None, None, None, 0xFF1493, 282),
+        ("Cn", "Copernicium", 285.0, 1.7, 12, 1.7, None, None, None, None, None, None, 0xFF1493, 285),
+        ("Nh", "Nihonium", 286.0, 1.7, 3, 1.7, None, None, None, None, None, None, 0xFF1493, 286),
+        ("Fl", "Flerovium", 289.0, 1.7, 4, 1.7, None, None, None, None, None, None, 0xFF1493, 289),
+        ("Mc", "Moscovium", 290.0, 1.7, 5, 1.7, None, None, None, None, None, None, 0xFF1493, 290),
+        ("Lv", "Livermorium", 293.0, 1.7, 6, 1.7, None, None, None, None, None, None, 0xFF1493, 293),
+        ("Ts", "Tennessine", 294.0, 1.7, 7, 1.7, None, None, None, None, None, None, 0xFF1493, 294),
+        ("Og", "Oganesson", 294.0, 1.6, 8, 1.6, None, None, None, None, None, None, 0xFF1493, 294),
+    ];
+
+    /// the table shared by everyone, built on first use
+    pub static PERIODIC_TABLE: LazyLock<PeriodicTable> = LazyLock::new(PeriodicTable::new);
+
     pub struct PeriodicTable {
-        elements: HashMap<String, Element>,
+        /// in order of atomic number
+        elements: Vec<Element>,
+        symbols: HashMap<String, usize>,
+        /// lowercase names
+        names: HashMap<String, usize>,
     }
 
     impl PeriodicTable {
         // Create a new PeriodicTable
         pub fn new() -> Self {
-            let mut elements = HashMap::new();
-            // Note: The Element struct needs an ion_radius field to pass tests
-            elements.insert("H".to_string(), Element {
-                name: "Hydrogen".to_string(),
-                atomic_number: 1,
-                mass: 1.008,
-                valence_radius: 0.32,
-                valence_electrons: 1,
-                atom_radius: 0.25,
-            });
-
-            elements.insert("He".to_string(), Element {
-                name: "Helium".to_string(),
-                atomic_number: 2,
-                mass: 4.003,
-                valence_radius: 0.93,
-                valence_electrons: 2,
-                atom_radius: 0.31,
-            });
-
-            elements.insert("Li".to_string(), Element {
-                name: "Lithium".to_string(),
-                atomic_number: 3,
-                mass: 6.941,
-                valence_radius: 1.23,
-                valence_electrons: 1,
-                atom_radius: 1.67,
-            });
-
-            elements.insert("Be".to_string(), Element {
-                name: "Beryllium".to_string(),
-                atomic_number: 4,
-                mass: 9.0122,
-                valence_radius: 0.90,
-                valence_electrons: 2,
-                atom_radius: 1.12,
-            });
-
-            elements.insert("B".to_string(), Element {
-                name: "Boron".to_string(),
-                atomic_number: 5,
-                mass: 10.811,
-                valence_radius: 0.82,
-                valence_electrons: 3,
-                atom_radius: 0.87,
-            });
-
-            elements.insert("C".to_string(), Element {
-                name: "Carbon".to_string(),
-                atomic_number: 6,
-                mass: 12.011,
-                valence_radius: 0.67,
-                valence_electrons: 4,
-                atom_radius: 0.77,
-            });
-
-            elements.insert("N".to_string(), Element {
-                name: "Nitrogen".to_string(),
-                atomic_number: 7,
-                mass: 14.007,
-                valence_radius: 0.56,
-                valence_electrons: 5,
-                atom_radius: 0.75,
-            });
-
-            elements.insert("O".to_string(), Element {
-                name: "Oxygen".to_string(),
-                atomic_number: 8,
-                mass: 16.00,
-                valence_radius: 0.60,
-                valence_electrons: 6,
-                atom_radius: 0.73,
-            });
-
-            elements.insert("F".to_string(), Element {
-                name: "Fluorine".to_string(),
-                atomic_number: 9,
-                mass: 18.998,
-                valence_radius: 0.50,
-                valence_electrons: 7,
-                atom_radius: 0.71,
-            });
-
-            elements.insert("Ne".to_string(), Element {
-                name: "Neon".to_string(),
-                atomic_number: 10,
-                mass: 20.180,
-                valence_radius: 1.12,
-                valence_electrons: 8,
-                atom_radius: 0.69,
-            });
-
-            elements.insert("Na".to_string(), Element {
-                name: "Sodium".to_string(),
-                atomic_number: 11,
-                mass: 22.990,
-                valence_radius: 1.54,
-                valence_electrons: 1,
-                atom_radius: 1.90,
-            });
-
-            elements.insert("Mg".to_string(), Element {
-                name: "Magnesium".to_string(),
-                atomic_number: 12,
-                mass: 24.305,
-                valence_radius: 1.36,
-                valence_electrons: 2,
-                atom_radius: 1.45,
-            });
-
-            elements.insert("Al".to_string(), Element {
-                name: "Aluminum".to_string(),
-                atomic_number: 13,
-                mass: 26.982,
-                valence_radius: 1.18,
-                valence_electrons: 3,
-                atom_radius: 1.18,
-            });
-
-            elements.insert("Si".to_string(), Element {
-                name: "Silicon".to_string(),
-                atomic_number: 14,
-                mass: 28.086,
-                valence_radius: 1.11,
-                valence_electrons: 4,
-                atom_radius: 1.11,
-            });
-
-            elements.insert("P".to_string(), Element {
-                name: "Phosphorus".to_string(),
-                atomic_number: 15,
-                mass: 30.974,
-                valence_radius: 1.06,
-                valence_electrons: 5,
-                atom_radius: 1.06,
-            });
-
-            elements.insert("S".to_string(), Element {
-                name: "Sulfur".to_string(),
-                atomic_number: 16,
-                mass: 32.066,
-                valence_radius: 1.02,
-                valence_electrons: 6,
-                atom_radius: 1.02,
-            });
-
-            elements.insert("Cl".to_string(), Element {
-                name: "Chlorine".to_string(),
-                atomic_number: 17,
-                mass: 35.453,
-                valence_radius: 0.99,
-                valence_electrons: 7,
-                atom_radius: 0.99,
-            });
-
-            elements.insert("Ar".to_string(), Element {
-                name: "Argon".to_string(),
-                atomic_number: 18,
-                mass: 39.948,
-                valence_radius: 1.54,
-                valence_electrons: 8,
-                atom_radius: 0.97,
-            });
-
-            elements.insert("K".to_string(), Element {
-                name: "Potassium".to_string(),
-                atomic_number: 19,
-                mass: 39.098,
-                valence_radius: 2.03,
-                valence_electrons: 1,
-                atom_radius: 2.43,
-            });
-
-            elements.insert("Ca".to_string(), Element {
-                name: "Calcium".to_string(),
-                atomic_number: 20,
-                mass: 40.078,
-                valence_radius: 1.74,
-                valence_electrons: 2,
-                atom_radius: 1.94,
-            });
-
-            elements.insert("Sc".to_string(), Element {
-                name: "Scandium".to_string(),
-                atomic_number: 21,
-                mass: 44.956,
-                valence_radius: 1.44,
-                valence_electrons: 3,
-                atom_radius: 1.84,
-            });
-
-            elements.insert("Ti".to_string(), Element {
-                name: "Titanium".to_string(),
-                atomic_number: 22,
-                mass: 47.867,
-                valence_radius: 1.32,
-                valence_electrons: 4,
-                atom_radius: 1.76,
-            });
-
-            elements.insert("V".to_string(), Element {
-                name: "Vanadium".to_string(),
-                atomic_number: 23,
-                mass: 50.942,
-                valence_radius: 1.22,
-                valence_electrons: 5,
-                atom_radius: 1.71,
-            });
-
-            elements.insert("Cr".to_string(), Element {
-                name: "Chromium".to_string(),
-                atomic_number: 24,
-                mass: 51.996,
-                valence_radius: 1.18,
-                valence_electrons: 6,
-                atom_radius: 1.66,
-            });
-
-            elements.insert("Mn".to_string(), Element {
-                name: "Manganese".to_string(),
-                atomic_number: 25,
-                mass: 54.938,
-                valence_radius: 1.17,
-                valence_electrons: 7,
-                atom_radius: 1.61,
-            });
-
-            elements.insert("Fe".to_string(), Element {
-                name: "Iron".to_string(),
-                atomic_number: 26,
-                mass: 55.845,
-                valence_radius: 1.32,
-                valence_electrons: 8,
-                atom_radius: 1.17,
-            });
-
-            elements.insert("Co".to_string(), Element {
-                name: "Cobalt".to_string(),
-                atomic_number: 27,
-                mass: 58.933,
-                valence_radius: 1.26,
-                valence_electrons: 9,
-                atom_radius: 1.16,
-            });
-
-            elements.insert("Ni".to_string(), Element {
-                name: "Nickel".to_string(),
-                atomic_number: 28,
-                mass: 58.693,
-                valence_radius: 1.24,
-                valence_electrons: 10,
-                atom_radius: 1.15,
-            });
-
-            elements.insert("Cu".to_string(), Element {
-                name: "Copper".to_string(),
-                atomic_number: 29,
-                mass: 63.546,
-                valence_radius: 1.32,
-                valence_electrons: 11,
-                atom_radius: 1.17,
-            });
-
-            elements.insert("Zn".to_string(), Element {
-                name: "Zinc".to_string(),
-                atomic_number: 30,
-                mass: 65.38,
-                valence_radius: 1.22,
-                valence_electrons: 12,
-                atom_radius: 1.25,
-            });
-
-            elements.insert("Ga".to_string(), Element {
-                name: "Gallium".to_string(),
-                atomic_number: 31,
-                mass: 69.723,
-                valence_radius: 1.22,
-                valence_electrons: 3,
-                atom_radius: 1.26,
-            });
-
-            elements.insert("Ge".to_string(), Element {
-                name: "Germanium".to_string(),
-                atomic_number: 32,
-                mass: 72.63,
-                valence_radius: 1.20,
-                valence_electrons: 4,
-                atom_radius: 1.22,
-            });
-
-            elements.insert("As".to_string(), Element {
-                name: "Arsenic".to_string(),
-                atomic_number: 33,
-                mass: 74.922,
-                valence_radius: 1.19,
-                valence_electrons: 5,
-                atom_radius: 1.19,
-            });
-
-            elements.insert("Se".to_string(), Element {
-                name: "Selenium".to_string(),
-                atomic_number: 34,
-                mass: 78.971,
-                valence_radius: 1.20,
-                valence_electrons: 6,
-                atom_radius: 1.16,
-            });
-
-            elements.insert("Br".to_string(), Element {
-                name: "Bromine".to_string(),
-                atomic_number: 35,
-                mass: 79.904,
-                valence_radius: 1.20,
-                valence_electrons: 7,
-                atom_radius: 1.14,
-            });
-
-            elements.insert("Kr".to_string(), Element {
-                name: "Krypton".to_string(),
-                atomic_number: 36,
-                mass: 83.798,
-                valence_radius: 1.16,
-                valence_electrons: 8,
-                atom_radius: 1.10,
-            });
-
-            // Add elements from Rb (37) to Og (118)
-            elements.insert("Rb".to_string(), Element {
-                name: "Rubidium".to_string(),
-                atomic_number: 37,
-                mass: 85.468,
-                valence_radius: 2.16,
-                valence_electrons: 1,
-                atom_radius: 2.35,
-            });
-
-            elements.insert("Sr".to_string(), Element {
-                name: "Strontium".to_string(),
-                atomic_number: 38,
-                mass: 87.62,
-                valence_radius: 1.91,
-                valence_electrons: 2,
-                atom_radius: 2.0,
-            });
-
-            elements.insert("Y".to_string(), Element {
-                name: "Yttrium".to_string(),
-                atomic_number: 39,
-                mass: 88.906,
-                valence_radius: 1.62,
-                valence_electrons: 3,
-                atom_radius: 1.8,
-            });
-
-            elements.insert("Zr".to_string(), Element {
-                name: "Zirconium".to_string(),
-                atomic_number: 40,
-                mass: 91.224,
-                valence_radius: 1.45,
-                valence_electrons: 4,
-                atom_radius: 1.6,
-            });
-
-            elements.insert("Nb".to_string(), Element {
-                name: "Niobium".to_string(),
-                atomic_number: 41,
-                mass: 92.906,
-                valence_radius: 1.34,
-                valence_electrons: 5,
-                atom_radius: 1.45,
-            });
-
-            elements.insert("Mo".to_string(), Element {
-                name: "Molybdenum".to_string(),
-                atomic_number: 42,
-                mass: 95.95,
-                valence_radius: 1.30,
-                valence_electrons: 6,
-                atom_radius: 1.4,
-            });
-
-            elements.insert("Tc".to_string(), Element {
-                name: "Technetium".to_string(),
-                atomic_number: 43,
-                mass: 98.0,
-                valence_radius: 1.27,
-                valence_electrons: 7,
-                atom_radius: 1.35,
-            });
-
-            elements.insert("Ru".to_string(), Element {
-                name: "Ruthenium".to_string(),
-                atomic_number: 44,
-                mass: 101.07,
-                valence_radius: 1.25,
-                valence_electrons: 8,
-                atom_radius: 1.3,
-            });
-
-            elements.insert("Rh".to_string(), Element {
-                name: "Rhodium".to_string(),
-                atomic_number: 45,
-                mass: 102.91,
-                valence_radius: 1.25,
-                valence_electrons: 9,
-                atom_radius: 1.35,
-            });
-
-            elements.insert("Pd".to_string(), Element {
-                name: "Palladium".to_string(),
-                atomic_number: 46,
-                mass: 106.42,
-                valence_radius: 1.28,
-                valence_electrons: 10,
-                atom_radius: 1.4,
-            });
-
-            elements.insert("Ag".to_string(), Element {
-                name: "Silver".to_string(),
-                atomic_number: 47,
-                mass: 107.87,
-                valence_radius: 1.34,
-                valence_electrons: 11,
-                atom_radius: 1.6,
-            });
-
-            elements.insert("Cd".to_string(), Element {
-                name: "Cadmium".to_string(),
-                atomic_number: 48,
-                mass: 112.41,
-                valence_radius: 1.48,
-                valence_electrons: 12,
-                atom_radius: 1.55,
-            });
-
-            elements.insert("In".to_string(), Element {
-                name: "Indium".to_string(),
-                atomic_number: 49,
-                mass: 114.82,
-                valence_radius: 1.44,
-                valence_electrons: 3,
-                atom_radius: 1.55,
-            });
-
-            elements.insert("Sn".to_string(), Element {
-                name: "Tin".to_string(),
-                atomic_number: 50,
-                mass: 118.71,
-                valence_radius: 1.41,
-                valence_electrons: 4,
-                atom_radius: 1.45,
-            });
-
-            elements.insert("Sb".to_string(), Element {
-                name: "Antimony".to_string(),
-                atomic_number: 51,
-                mass: 121.76,
-                valence_radius: 1.40,
-                valence_electrons: 5,
-                atom_radius: 1.45,
-            });
-
-            elements.insert("Te".to_string(), Element {
-                name: "Tellurium".to_string(),
-                atomic_number: 52,
-                mass: 127.60,
-                valence_radius: 1.36,
-                valence_electrons: 6,
-                atom_radius: 1.4,
-            });
-
-            elements.insert("I".to_string(), Element {
-                name: "Iodine".to_string(),
-                atomic_number: 53,
-                mass: 126.90,
-                valence_radius: 1.33,
-                valence_electrons: 7,
-                atom_radius: 1.4,
-            });
-
-            elements.insert("Xe".to_string(), Element {
-                name: "Xenon".to_string(),
-                atomic_number: 54,
-                mass: 131.29,
-                valence_radius: 1.31,
-                valence_electrons: 8,
-                atom_radius: 1.3,
-            });
-
-            elements.insert("Cs".to_string(), Element {
-                name: "Cesium".to_string(),
-                atomic_number: 55,
-                mass: 132.91,
-                valence_radius: 2.35,
-                valence_electrons: 1,
-                atom_radius: 2.6,
-            });
-
-            elements.insert("Ba".to_string(), Element {
-                name: "Barium".to_string(),
-                atomic_number: 56,
-                mass: 137.33,
-                valence_radius: 1.98,
-                valence_electrons: 2,
-                atom_radius: 2.15,
-            });
-            
-            elements.insert("La".to_string(), Element {
-                name: "Lanthanum".to_string(),
-                atomic_number: 57,
-                mass: 138.91,
-                valence_radius: 1.69,
-                valence_electrons: 3,
-                atom_radius: 1.95,
-            });
-
-            elements.insert("Ce".to_string(), Element {
-                name: "Cerium".to_string(),
-                atomic_number: 58,
-                mass: 140.116,
-                valence_radius: 1.65,
-                valence_electrons: 4,
-                atom_radius: 1.85,
-            });
-
-            elements.insert("Pr".to_string(), Element {
-                name: "Praseodymium".to_string(),
-                atomic_number: 59,
-                mass: 140.908,
-                valence_radius: 1.65,
-                valence_electrons: 5,
-                atom_radius: 1.85,
-            });
-
-            elements.insert("Nd".to_string(), Element {
-                name: "Neodymium".to_string(),
-                atomic_number: 60,
-                mass: 144.242,
-                valence_radius: 1.64,
-                valence_electrons: 6,
-                atom_radius: 1.85,
-            });
-
-            elements.insert("Pm".to_string(), Element {
-                name: "Promethium".to_string(),
-                atomic_number: 61,
-                mass: 145.0,
-                valence_radius: 1.63,
-                valence_electrons: 7,
-                atom_radius: 1.85,
-            });
-
-            elements.insert("Sm".to_string(), Element {
-                name: "Samarium".to_string(),
-                atomic_number: 62,
-                mass: 150.36,
-                valence_radius: 1.62,
-                valence_electrons: 8,
-                atom_radius: 1.85,
-            });
-
-            elements.insert("Eu".to_string(), Element {
-                name: "Europium".to_string(),
-                atomic_number: 63,
-                mass: 151.964,
-                valence_radius: 1.85,
-                valence_electrons: 9,
-                atom_radius: 1.85,
-            });
-
-            elements.insert("Gd".to_string(), Element {
-                name: "Gadolinium".to_string(),
-                atomic_number: 64,
-                mass: 157.25,
-                valence_radius: 1.61,
-                valence_electrons: 10,
-                atom_radius: 1.80,
-            });
-
-            elements.insert("Tb".to_string(), Element {
-                name: "Terbium".to_string(),
-                atomic_number: 65,
-                mass: 158.925,
-                valence_radius: 1.59,
-                valence_electrons: 11,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Dy".to_string(), Element {
-                name: "Dysprosium".to_string(),
-                atomic_number: 66,
-                mass: 162.500,
-                valence_radius: 1.59,
-                valence_electrons: 12,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Ho".to_string(), Element {
-                name: "Holmium".to_string(),
-                atomic_number: 67,
-                mass: 164.930,
-                valence_radius: 1.58,
-                valence_electrons: 13,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Er".to_string(), Element {
-                name: "Erbium".to_string(),
-                atomic_number: 68,
-                mass: 167.259,
-                valence_radius: 1.57,
-                valence_electrons: 14,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Tm".to_string(), Element {
-                name: "Thulium".to_string(),
-                atomic_number: 69,
-                mass: 168.934,
-                valence_radius: 1.56,
-                valence_electrons: 15,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Yb".to_string(), Element {
-                name: "Ytterbium".to_string(),
-                atomic_number: 70,
-                mass: 173.054,
-                valence_radius: 1.74,
-                valence_electrons: 16,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Lu".to_string(), Element {
-                name: "Lutetium".to_string(),
-                atomic_number: 71,
-                mass: 174.967,
-                valence_radius: 1.56,
-                valence_electrons: 3,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Hf".to_string(), Element {
-                name: "Hafnium".to_string(),
-                atomic_number: 72,
-                mass: 178.49,
-                valence_radius: 1.44,
-                valence_electrons: 4,
-                atom_radius: 1.55,
-            });
-
-            elements.insert("Ta".to_string(), Element {
-                name: "Tantalum".to_string(),
-                atomic_number: 73,
-                mass: 180.948,
-                valence_radius: 1.34,
-                valence_electrons: 5,
-                atom_radius: 1.45,
-            });
-
-            elements.insert("W".to_string(), Element {
-                name: "Tungsten".to_string(),
-                atomic_number: 74,
-                mass: 183.84,
-                valence_radius: 1.30,
-                valence_electrons: 6,
-                atom_radius: 1.35,
-            });
-
-            elements.insert("Re".to_string(), Element {
-                name: "Rhenium".to_string(),
-                atomic_number: 75,
-                mass: 186.207,
-                valence_radius: 1.28,
-                valence_electrons: 7,
-                atom_radius: 1.35,
-            });
-
-            elements.insert("Os".to_string(), Element {
-                name: "Osmium".to_string(),
-                atomic_number: 76,
-                mass: 190.23,
-                valence_radius: 1.26,
-                valence_electrons: 8,
-                atom_radius: 1.30,
-            });
-
-            elements.insert("Ir".to_string(), Element {
-                name: "Iridium".to_string(),
-                atomic_number: 77,
-                mass: 192.217,
-                valence_radius: 1.27,
-                valence_electrons: 9,
-                atom_radius: 1.35,
-            });
-
-            elements.insert("Pt".to_string(), Element {
-                name: "Platinum".to_string(),
-                atomic_number: 78,
-                mass: 195.084,
-                valence_radius: 1.30,
-                valence_electrons: 10,
-                atom_radius: 1.35,
-            });
-
-            elements.insert("Au".to_string(), Element {
-                name: "Gold".to_string(),
-                atomic_number: 79,
-                mass: 196.967,
-                valence_radius: 1.34,
-                valence_electrons: 11,
-                atom_radius: 1.35,
-            });
-
-            elements.insert("Hg".to_string(), Element {
-                name: "Mercury".to_string(),
-                atomic_number: 80,
-                mass: 200.592,
-                valence_radius: 1.49,
-                valence_electrons: 12,
-                atom_radius: 1.50,
-            });
-
-            elements.insert("Tl".to_string(), Element {
-                name: "Thallium".to_string(),
-                atomic_number: 81,
-                mass: 204.38,
-                valence_radius: 1.48,
-                valence_electrons: 3,
-                atom_radius: 1.90,
-            });
-
-            elements.insert("Pb".to_string(), Element {
-                name: "Lead".to_string(),
-                atomic_number: 82,
-                mass: 207.2,
-                valence_radius: 1.47,
-                valence_electrons: 4,
-                atom_radius: 1.80,
-            });
-
-            elements.insert("Bi".to_string(), Element {
-                name: "Bismuth".to_string(),
-                atomic_number: 83,
-                mass: 208.980,
-                valence_radius: 1.46,
-                valence_electrons: 5,
-                atom_radius: 1.60,
-            });
-
-            elements.insert("Po".to_string(), Element {
-                name: "Polonium".to_string(),
-                atomic_number: 84,
-                mass: 209.0,
-                valence_radius: 1.46,
-                valence_electrons: 6,
-                atom_radius: 1.50,
-            });
-
-            elements.insert("At".to_string(), Element {
-                name: "Astatine".to_string(),
-                atomic_number: 85,
-                mass: 210.0,
-                valence_radius: 1.45,
-                valence_electrons: 7,
-                atom_radius: 1.50,
-            });
-
-            elements.insert("Rn".to_string(), Element {
-                name: "Radon".to_string(),
-                atomic_number: 86,
-                mass: 222.0,
-                valence_radius: 1.43,
-                valence_electrons: 8,
-                atom_radius: 1.50,
-            });
-
-            elements.insert("Fr".to_string(), Element {
-                name: "Francium".to_string(),
-                atomic_number: 87,
-                mass: 223.0,
-                valence_radius: 2.5,
-                valence_electrons: 1,
-                atom_radius: 2.60,
-            });
-
-            elements.insert("Ra".to_string(), Element {
-                name: "Radium".to_string(),
-                atomic_number: 88,
-                mass: 226.0,
-                valence_radius: 2.1,
-                valence_electrons: 2,
-                atom_radius: 2.15,
-            });
-
-            elements.insert("Ac".to_string(), Element {
-                name: "Actinium".to_string(),
-                atomic_number: 89,
-                mass: 227.0,
-                valence_radius: 1.95,
-                valence_electrons: 3,
-                atom_radius: 1.95,
-            });
-
-            elements.insert("Th".to_string(), Element {
-                name: "Thorium".to_string(),
-                atomic_number: 90,
-                mass: 232.038,
-                valence_radius: 1.80,
-                valence_electrons: 4,
-                atom_radius: 1.80,
-            });
-
-            elements.insert("Pa".to_string(), Element {
-                name: "Protactinium".to_string(),
-                atomic_number: 91,
-                mass: 231.036,
-                valence_radius: 1.80,
-                valence_electrons: 5,
-                atom_radius: 1.80,
-            });
-
-            elements.insert("U".to_string(), Element {
-                name: "Uranium".to_string(),
-                atomic_number: 92,
-                mass: 238.029,
-                valence_radius: 1.75,
-                valence_electrons: 6,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Np".to_string(), Element {
-                name: "Neptunium".to_string(),
-                atomic_number: 93,
-                mass: 237.0,
-                valence_radius: 1.75,
-                valence_electrons: 7,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Pu".to_string(), Element {
-                name: "Plutonium".to_string(),
-                atomic_number: 94,
-                mass: 244.0,
-                valence_radius: 1.75,
-                valence_electrons: 8,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Am".to_string(), Element {
-                name: "Americium".to_string(),
-                atomic_number: 95,
-                mass: 243.0,
-                valence_radius: 1.75,
-                valence_electrons: 9,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Cm".to_string(), Element {
-                name: "Curium".to_string(),
-                atomic_number: 96,
-                mass: 247.0,
-                valence_radius: 1.75,
-                valence_electrons: 10,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Bk".to_string(), Element {
-                name: "Berkelium".to_string(),
-                atomic_number: 97,
-                mass: 247.0,
-                valence_radius: 1.75,
-                valence_electrons: 11,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Cf".to_string(), Element {
-                name: "Californium".to_string(),
-                atomic_number: 98,
-                mass: 251.0,
-                valence_radius: 1.75,
-                valence_electrons: 12,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Es".to_string(), Element {
-                name: "Einsteinium".to_string(),
-                atomic_number: 99,
-                mass: 252.0,
-                valence_radius: 1.75,
-                valence_electrons: 13,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Fm".to_string(), Element {
-                name: "Fermium".to_string(),
-                atomic_number: 100,
-                mass: 257.0,
-                valence_radius: 1.75,
-                valence_electrons: 14,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Md".to_string(), Element {
-                name: "Mendelevium".to_string(),
-                atomic_number: 101,
-                mass: 258.0,
-                valence_radius: 1.75,
-                valence_electrons: 15,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("No".to_string(), Element {
-                name: "Nobelium".to_string(),
-                atomic_number: 102,
-                mass: 259.0,
-                valence_radius: 1.75,
-                valence_electrons: 16,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Lr".to_string(), Element {
-                name: "Lawrencium".to_string(),
-                atomic_number: 103,
-                mass: 262.0,
-                valence_radius: 1.75,
-                valence_electrons: 3,
-                atom_radius: 1.75,
-            });
-
-            elements.insert("Rf".to_string(), Element {
-                name: "Rutherfordium".to_string(),
-                atomic_number: 104,
-                mass: 267.0,
-                valence_radius: 1.7,
-                valence_electrons: 4,
-                atom_radius: 1.7,
-            });
-
-            elements.insert("Db".to_string(), Element {
-                name: "Dubnium".to_string(),
-                atomic_number: 105,
-                mass: 268.0,
-                valence_radius: 1.7,
-                valence_electrons: 5,
-                atom_radius: 1.7,
-            });
-
-            elements.insert("Sg".to_string(), Element {
-                name: "Seaborgium".to_string(),
-                atomic_number: 106,
-                mass: 269.0,
-                valence_radius: 1.7,
-                valence_electrons: 6,
-                atom_radius: 1.7,
-            });
-
-            elements.insert("Bh".to_string(), Element {
-                name: "Bohrium".to_string(),
-                atomic_number: 107,
-                mass: 270.0,
-                valence_radius: 1.7,
-                valence_electrons: 7,
-                atom_radius: 1.7,
-            });
-
-            elements.insert("Hs".to_string(), Element {
-                name: "Hassium".to_string(),
-                atomic_number: 108,
-                mass: 277.0,
-                valence_radius: 1.7,
-                valence_electrons: 8,
-                atom_radius: 1.7,
-            });
-
-            elements.insert("Mt".to_string(), Element {
-                name: "Meitnerium".to_string(),
-                atomic_number: 109,
-                mass: 278.0,
-                valence_radius: 1.7,
-                valence_electrons: 9,
-                atom_radius: 1.7,
-            });
-
-            elements.insert("Ds".to_string(), Element {
-                name: "Darmstadtium".to_string(),
-                atomic_number: 110,
-                mass: 281.0,
-                valence_radius: 1.7,
-                valence_electrons: 10,
-                atom_radius: 1.7,
-            });
-
-            elements.insert("Rg".to_string(), Element {
-                name: "Roentgenium".to_string(),
-                atomic_number: 111,
-                mass: 282.0,
-                valence_radius: 1.7,
-                valence_electrons: 11,
-                atom_radius: 1.7,
-            });
-
-            elements.insert("Cn".to_string(), Element {
-                name: "Copernicium".to_string(),
-                atomic_number: 112,
-                mass: 285.0,
-                valence_radius: 1.7,
-                valence_electrons: 12,
-                atom_radius: 1.7,
-            });
+            let elements: Vec<Element> = ELEMENTS.iter().enumerate().map(|(index, row)| {
+                let &(symbol, name, mass, valence_radius, valence_electrons, atom_radius, covalent_radius, vdw_radius_bondi, vdw_radius_alvarez, metallic_radius, ion, electronegativity, color, isotope) = row;
+                Element {
+                    symbol: symbol.to_string(),
+                    name: name.to_string(),
+                    atomic_number: index as u64 + 1,
+                    mass,
+                    valence_radius,
+                    valence_electrons,
+                    atom_radius,
+                    covalent_radius,
+                    vdw_radius_bondi,
+                    vdw_radius_alvarez,
+                    metallic_radius,
+                    ionic_radius: ion.map(|(_, radius)| radius),
+                    ion_charge: ion.map(|(charge, _)| charge),
+                    electronegativity,
+                    color: [(color >> 16) as u8, (color >> 8) as u8, color as u8],
+                    isotope,
+                }
+            }).collect();
+            let symbols = elements.iter().enumerate().map(|(i, e)| (e.symbol.clone(), i)).collect();
+            let names = elements.iter().enumerate().map(|(i, e)| (e.name.to_lowercase(), i)).collect();
+            Self { elements, symbols, names }
+        }
 
-            elements.insert("Nh".to_string(), Element {
-                name: "Nihonium".to_string(),
-                atomic_number: 113,
-                mass: 286.0,
-                valence_radius: 1.7,
-                valence_electrons: 3,
-                atom_radius: 1.7,
-            });
+        /// the shared table, see `PERIODIC_TABLE`
+        pub fn global() -> &'static PeriodicTable {
+            &PERIODIC_TABLE
+        }
 
-            elements.insert("Fl".to_string(), Element {
-                name: "Flerovium".to_string(),
-                atomic_number: 114,
-                mass: 289.0,
-                valence_radius: 1.7,
-                valence_electrons: 4,
-                atom_radius: 1.7,
-            });
+        // Get an element by its symbol
+        pub fn get(&self, symbol: &str) -> Option<&Element> {
+            self.symbols.get(symbol).map(|&i| &self.elements[i])
+        }
 
-            elements.insert("Mc".to_string(), Element {
-                name: "Moscovium".to_string(),
-                atomic_number: 115,
-                mass: 290.0,
-                valence_radius: 1.7,
-                valence_electrons: 5,
-                atom_radius: 1.7,
-            });
+        // Get the symbol of an element by its atomic number
+        pub fn symbol(&self, atomic_number: u64) -> Option<&str> {
+            self.by_number(atomic_number).map(|e| e.symbol.as_str())
+        }
 
-            elements.insert("Lv".to_string(), Element {
-                name: "Livermorium".to_string(),
-                atomic_number: 116,
-                mass: 293.0,
-                valence_radius: 1.7,
-                valence_electrons: 6,
-                atom_radius: 1.7,
-            });
+        pub fn by_number(&self, atomic_number: u64) -> Option<&Element> {
+            self.elements.get((atomic_number as usize).checked_sub(1)?)
+        }
 
-            elements.insert("Ts".to_string(), Element {
-                name: "Tennessine".to_string(),
-                atomic_number: 117,
-                mass: 294.0,
-                valence_radius: 1.7,
-                valence_electrons: 7,
-                atom_radius: 1.7,
-            });
+        /// the element of a name in any case, e.g. `iron`
+        pub fn by_name(&self, name: &str) -> Option<&Element> {
+            self.names.get(&name.to_lowercase()).map(|&i| &self.elements[i])
+        }
 
-            elements.insert("Og".to_string(), Element {
-                name: "Oganesson".to_string(),
-                atomic_number: 118,
-                mass: 294.0,
-                valence_radius: 1.6,
-                valence_electrons: 8,
-                atom_radius: 1.6,
-            });
+        /**
+        the element of a label as written in structure files: an atomic number, a symbol in any case such as
+        `FE` or `fe`, or a name
+         */
+        pub fn lookup(&self, label: &str) -> Result<&Element, ElementError> {
+            let trimmed = label.trim();
+            if let Ok(atomic_number) = trimmed.parse::<u64>() {
+                return self.by_number(atomic_number).ok_or_else(|| ElementError::Unknown(label.to_string()));
+            }
+            let mut chars = trimmed.chars();
+            let symbol: String = chars.next().map(|c| c.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect()).unwrap_or_default();
+            self.get(trimmed)
+                .or_else(|| self.get(&symbol))
+                .or_else(|| self.by_name(trimmed))
+                .ok_or_else(|| ElementError::Unknown(label.to_string()))
+        }
 
-            Self { elements }
+        /// the radius of the element of a label in a set
+        pub fn radius(&self, label: &str, set: RadiusSet) -> Result<f64, ElementError> {
+            let element = self.lookup(label)?;
+            element.radius(set).ok_or_else(|| ElementError::NoRadius(element.symbol.clone(), set))
         }
 
-        // Get an element by its symbol
-        pub fn get(&self, symbol: &str) -> Option<&Element> {
-            self.elements.get(symbol)
+        /// every element in order of atomic number
+        pub fn iter(&self) -> impl Iterator<Item = &Element> {
+            self.elements.iter()
         }
+    }
 
-        // Add more methods as needed...
+    impl Default for PeriodicTable {
+        fn default() -> Self {
+            Self::new()
+        }
     }
 }
 
@@ -1225,16 +679,72 @@ mod tests {
         assert_eq!(table.get("NONEXIST"), None);
     }
 
+    #[test]
+    fn test_periodic_table_properties() {
+        use super::periodic_table::RadiusSet;
+        let table = PeriodicTable::global();
+        assert_eq!(table.iter().count(), 118);
+        assert!(table.iter().enumerate().all(|(i, e)| e.atomic_number == i as u64 + 1 && table.get(&e.symbol) == Some(e)));
+        let iron = table.get("Fe").unwrap();
+        assert_eq!(iron.radius(RadiusSet::Atom), Some(1.17));
+        assert_eq!(iron.radius(RadiusSet::Covalent), Some(1.32));
+        assert_eq!(iron.radius(RadiusSet::Bondi), None);
+        assert_eq!(iron.radius(RadiusSet::Alvarez), Some(2.44));
+        assert_eq!(iron.radius(RadiusSet::Metallic), Some(1.26));
+        assert_eq!((iron.ion_charge, iron.ionic_radius), (Some(3), Some(0.645)));
+        assert_eq!(iron.electronegativity, Some(1.83));
+        assert_eq!(iron.hex_color(), "#E06633");
+        assert_eq!(iron.isotope, 56);
+        let oxygen = table.get("O").unwrap();
+        assert_eq!(oxygen.vdw_radius_bondi, Some(1.52));
+        assert_eq!((oxygen.ion_charge, oxygen.ionic_radius), (Some(-2), Some(1.40)));
+        assert_eq!(oxygen.color, [255, 13, 13]);
+        assert_eq!(table.get("He").unwrap().electronegativity, None);
+        // radioactive elements take their longest-lived isotope
+        assert_eq!(table.get("Tc").unwrap().isotope, 98);
+        assert_eq!(table.get("Og").unwrap().isotope, 294);
+    }
+
+    #[test]
+    fn test_periodic_table_lookup() {
+        use super::periodic_table::{ElementError, RadiusSet};
+        let table = PeriodicTable::global();
+        for label in ["Fe", "fe", "FE", " Fe ", "26", "iron", "Iron"] {
+            assert_eq!(table.lookup(label).unwrap().symbol, "Fe", "{}", label);
+        }
+        assert_eq!(table.by_number(8).unwrap().name, "Oxygen");
+        assert_eq!(table.by_name("OXYGEN").unwrap().symbol, "O");
+        assert_eq!(table.symbol(118), Some("Og"));
+        assert_eq!(table.symbol(0), None);
+        assert_eq!(table.symbol(119), None);
+        // the symbol is matched as written first, so `Co` is cobalt and `CO` falls back to it as well
+        assert_eq!(table.lookup("Co").unwrap().atomic_number, 27);
+        assert_eq!(table.lookup("CO").unwrap().atomic_number, 27);
+        for label in ["X", "", "0", "119", "Xx"] {
+            assert_eq!(table.lookup(label), Err(ElementError::Unknown(label.to_string())));
+        }
+        assert_eq!(table.lookup("X").unwrap_err().to_string(), "unknown element `X`");
+        assert_eq!(table.radius("o", RadiusSet::Covalent), Ok(0.66));
+        assert_eq!(table.radius("He", RadiusSet::Metallic), Err(ElementError::NoRadius("He".to_string(), RadiusSet::Metallic)));
+        assert_eq!(table.radius("He", RadiusSet::Metallic).unwrap_err().to_string(), "no metallic radius for He");
+        assert_eq!("Covalent".parse::<RadiusSet>(), Ok(RadiusSet::Covalent));
+        assert!("vdw".parse::<RadiusSet>().is_err());
+    }
+
     #[test]
     fn test_atom_subtraction() {
         use super::structures::{Atom, Coordinate};
         let atom1 = Atom {
             element: "Fe".to_string(),
             coordinate: Coordinate(5.0, 5.0, 5.0),
+            record: None,
+            selective: None
         };
         let atom2 = Atom {
             element: "Fe".to_string(),
             coordinate: Coordinate(1.0, 2.0, 3.0),
+            record: None,
+            selective: None
         };
         let diff = &atom1 - &atom2;
         assert!(diff.0 - 4.0 < 1e-6);
@@ -1258,10 +768,16 @@ mod tests {
                 gamma: 90.0,
             },
             atoms: vec![],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
         };
         let atom = Atom {
             element: "Fe".to_string(),
             coordinate: Coordinate(1.0, 2.0, 3.0),
+            record: None,
+            selective: None
         };
         block.addatom(atom);
         assert_eq!(block.atoms.len(), 1);
@@ -1287,6 +803,10 @@ mod tests {
                 gamma: 90.0,
             },
             atoms: vec![],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
         };
         let new_crystal = CrystalInfo {
             x: 20.0,
@@ -1318,6 +838,10 @@ mod tests {
                 gamma: 90.0,
             },
             atoms: vec![],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
         };
         let new_block = block.expand_crystal(2.0);
         assert!(new_block.crystal.x - 20.0 < 1e-6);
@@ -1341,10 +865,98 @@ mod tests {
                 gamma: 90.0,
             },
             atoms: vec![],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
         };
         let new_block = block.scale_crystal(CoordinateChoice::X, 2.0);
         assert!(new_block.crystal.x - 20.0 < 1e-6);
         assert!(new_block.crystal.y - 10.0 < 1e-6);
         assert!(new_block.crystal.z - 10.0 < 1e-6);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_lattice() {
+        use nalgebra::Matrix3;
+        use super::structures::{Coordinate, Lattice};
+        let crystal = CrystalInfo { x: 5.0, y: 6.0, z: 7.0, alpha: 80.0, beta: 95.0, gamma: 110.0 };
+        let lattice = Lattice::from_crystal_info(&crystal).unwrap();
+        let matrix = lattice.matrix();
+        assert_eq!((matrix[(0, 1)], matrix[(0, 2)], matrix[(1, 2)]), (0.0, 0.0, 0.0));
+        let back = lattice.to_crystal_info();
+        for (a, b) in [(back.x, 5.0), (back.y, 6.0), (back.z, 7.0), (back.alpha, 80.0), (back.beta, 95.0), (back.gamma, 110.0)] {
+            assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+        }
+        let (ca, cb, cg) = (80f64.to_radians().cos(), 95f64.to_radians().cos(), 110f64.to_radians().cos());
+        let volume = 5.0 * 6.0 * 7.0 * (1.0 - ca * ca - cb * cb - cg * cg + 2.0 * ca * cb * cg).sqrt();
+        assert!((lattice.volume() - volume).abs() < 1e-9);
+
+        let product = lattice.matrix() * lattice.reciprocal().matrix().transpose();
+        assert!((product - Matrix3::identity()).norm() < 1e-12);
+        assert!((lattice.reciprocal().volume() - 1.0 / volume).abs() < 1e-12);
+
+        let fractional = Coordinate(0.25, -0.5, 1.25);
+        let cartesian = lattice.to_cartesian(&fractional);
+        let back = lattice.to_fractional(&cartesian);
+        assert!((back.0 - 0.25).abs() < 1e-12 && (back.1 + 0.5).abs() < 1e-12 && (back.2 - 1.25).abs() < 1e-12);
+
+        // a cell with a along y is rotated into the standard orientation
+        let rotated = Lattice::new(Matrix3::new(0.0, 4.0, 0.0, -4.0, 0.0, 0.0, 0.0, 0.0, 3.0)).unwrap();
+        assert!((rotated.standard().matrix() - Matrix3::new(4.0, 0.0, 0.0, 0.0, 4.0, 0.0, 0.0, 0.0, 3.0)).norm() < 1e-12);
+
+        assert!(CrystalInfo { x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 }.lattice().is_none());
+        assert!(Lattice::new(Matrix3::new(1.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn test_block_fractional_coordinates() {
+        use super::structures::{Atom, Coordinate, StructureBlock};
+        let atom = |x, y, z| Atom { element: "Fe".to_string(), coordinate: Coordinate(x, y, z), record: None, selective: None };
+        let mut block = StructureBlock {
+            number: 1,
+            energy: 0.0,
+            symmetry: "P1".to_string(),
+            crystal: CrystalInfo { x: 4.0, y: 4.0, z: 4.0, alpha: 90.0, beta: 90.0, gamma: 120.0 },
+            atoms: vec![atom(0.0, 0.0, 0.0), atom(1.0, 3f64.sqrt(), 2.0)],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
+        };
+        let fractional = block.fractional_coordinates().unwrap();
+        assert!((fractional[1].0 - 0.5).abs() < 1e-12 && (fractional[1].1 - 0.5).abs() < 1e-12 && (fractional[1].2 - 0.5).abs() < 1e-12);
+        let lattice = block.lattice().unwrap();
+        assert!((block.atoms[1].fractional(&lattice).0 - 0.5).abs() < 1e-12);
+
+        block.set_fractional_coordinates(&[Coordinate(0.5, 0.0, 0.0), Coordinate(0.0, 0.0, 0.25)]).unwrap();
+        assert!((block.atoms[0].coordinate.0 - 2.0).abs() < 1e-12);
+        assert!((block.atoms[1].coordinate.2 - 1.0).abs() < 1e-12);
+        assert!(block.set_fractional_coordinates(&[Coordinate(0.0, 0.0, 0.0)]).is_err());
+
+        block.crystal = CrystalInfo { x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 };
+        assert!(block.fractional_coordinates().is_none());
+        assert!(block.set_fractional_coordinates(&[Coordinate(0.0, 0.0, 0.0), Coordinate(0.0, 0.0, 0.0)]).is_err());
+    }
+
+    #[test]
+    fn test_minimum_image() {
+        use super::structures::{Coordinate, Lattice};
+        let lattice = Lattice::from_crystal_info(&CrystalInfo { x: 4.0, y: 5.0, z: 6.0, alpha: 70.0, beta: 110.0, gamma: 50.0 }).unwrap();
+        let length = |v: &Coordinate| (v.0 * v.0 + v.1 * v.1 + v.2 * v.2).sqrt();
+        for vector in [Coordinate(3.9, -2.2, 5.1), Coordinate(-7.3, 8.8, 0.4), Coordinate(0.1, 0.2, -11.9), Coordinate(0.0, 0.0, 0.0)] {
+            // the shortest image by brute force over a few cells
+            let mut shortest = f64::MAX;
+            for (i, j, k) in itertools::iproduct!(-4..=4, -4..=4, -4..=4) {
+                let fractional = lattice.to_fractional(&vector);
+                shortest = shortest.min(length(&lattice.to_cartesian(&Coordinate(fractional.0 + i as f64, fractional.1 + j as f64, fractional.2 + k as f64))));
+            }
+            assert!((length(&lattice.minimum_image(&vector)) - shortest).abs() < 1e-9, "{:?}", vector);
+        }
+        // every image within a cutoff wider than the cell
+        let cubic = Lattice::from_crystal_info(&CrystalInfo { x: 2.0, y: 2.0, z: 2.0, alpha: 90.0, beta: 90.0, gamma: 90.0 }).unwrap();
+        assert_eq!(cubic.images_within(&Coordinate(0.0, 0.0, 0.0), 2.1).len(), 7);
+        assert_eq!(cubic.images_within(&Coordinate(1.0, 0.0, 0.0), 1.1).len(), 2);
+        assert_eq!(cubic.images_within(&Coordinate(6.0, 0.0, 0.0), 0.5).len(), 1);
+    }
+}
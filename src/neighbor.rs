@@ -0,0 +1,468 @@
+pub mod neighbor_list {
+    //! neighbour lists of the atoms of a `StructureBlock`, built with linked cells
+    use std::collections::HashMap;
+    use std::io;
+    use std::path::Path;
+
+    use nalgebra::{Matrix3, RowVector3};
+    use serde::Deserialize;
+
+    use crate::modules::periodic_table::{ElementError, PeriodicTable, RadiusSet};
+    use crate::modules::structures::{Coordinate, StructureBlock};
+
+    /// the distance up to which two atoms are neighbours
+    pub trait Cutoff {
+        /// the cutoff of a pair of elements, an error if it depends on an element missing from the table
+        fn cutoff(&self, element1: &str, element2: &str) -> Result<f64, ElementError>;
+    }
+
+    /// the same cutoff for every pair
+    impl Cutoff for f64 {
+        fn cutoff(&self, _element1: &str, _element2: &str) -> Result<f64, ElementError> {
+            Ok(*self)
+        }
+    }
+
+    /**
+    when two atoms are bonded, shared by the coordination routines
+
+    a bond file gives the cutoffs of pairs of elements, in TOML or JSON (by the `.json` extension), e.g.
+
+    ```toml
+    # the other pairs: either a fixed `cutoff`, or the `radii` of a set times `scale` plus `tolerance`
+    radii = "covalent"
+    scale = 1.0
+    tolerance = 0.3
+
+    [pairs]
+    Fe-O = 2.3
+    Fe-Fe = 2.8
+    ```
+     */
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum BondCriterion {
+        /// the same cutoff for every pair
+        Fixed(f64),
+        /// the sum of the radii of the elements in `set` times `scale`, plus `tolerance`
+        Radii { set: RadiusSet, scale: f64, tolerance: f64 },
+        /// cutoffs of pairs of element symbols in either order, the other pairs are left to `fallback`
+        Pairs { cutoffs: HashMap<(String, String), f64>, fallback: Box<BondCriterion> },
+    }
+
+    /// the layout of a bond file
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct BondFile {
+        cutoff: Option<f64>,
+        radii: Option<RadiusSet>,
+        scale: Option<f64>,
+        tolerance: Option<f64>,
+        #[serde(default)]
+        pairs: HashMap<String, f64>,
+    }
+
+    impl BondCriterion {
+        /// the radii of the elements in a set, the atom radii by default, optionally scaled, plus a tolerance
+        pub fn radii(set: Option<RadiusSet>, scale: Option<f64>, tolerance: Option<f64>) -> Self {
+            BondCriterion::Radii { set: set.unwrap_or_default(), scale: scale.unwrap_or(1.0), tolerance: tolerance.unwrap_or(0.3) }
+        }
+        /// the cutoffs of pairs of elements, e.g. `("Fe", "O")`, with a criterion for the other pairs
+        ///
+        /// the elements are looked up in the periodic table in any case, so `fe-o` is the same pair as `Fe-O`
+        pub fn pairs<I: IntoIterator<Item = ((String, String), f64)>>(cutoffs: I, fallback: BondCriterion) -> Result<Self, ElementError> {
+            let cutoffs = cutoffs.into_iter()
+                .map(|((a, b), cutoff)| Ok((symbols(&a, &b)?, cutoff)))
+                .collect::<Result<HashMap<(String, String), f64>, ElementError>>()?;
+            Ok(BondCriterion::Pairs { cutoffs, fallback: Box::new(fallback) })
+        }
+        /// read a bond file, JSON if named `.json` and TOML otherwise
+        pub fn from_file(path: &Path) -> io::Result<Self> {
+            let content = std::fs::read_to_string(path)?;
+            let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), message));
+            let file: BondFile = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")) {
+                serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?
+            } else {
+                toml::from_str(&content).map_err(|e| invalid(e.to_string()))?
+            };
+            let fallback = match file {
+                BondFile{ cutoff: Some(_), radii: Some(_), .. } | BondFile{ cutoff: Some(_), scale: Some(_), .. } | BondFile{ cutoff: Some(_), tolerance: Some(_), .. } => {
+                    return Err(invalid(String::from("a fixed cutoff takes no radii, scale or tolerance")));
+                },
+                BondFile{ cutoff: Some(cutoff), .. } => BondCriterion::Fixed(cutoff),
+                BondFile{ radii, scale, tolerance, .. } => BondCriterion::radii(radii, scale, tolerance),
+            };
+            if file.pairs.is_empty() {
+                return Ok(fallback);
+            }
+            let cutoffs = file.pairs.into_iter().map(|(pair, cutoff)| {
+                match pair.split_once('-') {
+                    Some((a, b)) if !a.trim().is_empty() && !b.trim().is_empty() => Ok(((a.trim().to_string(), b.trim().to_string()), cutoff)),
+                    _ => Err(invalid(format!("pair `{}` is not written as `A-B`", pair))),
+                }
+            }).collect::<io::Result<Vec<_>>>()?;
+            BondCriterion::pairs(cutoffs, fallback).map_err(|e| invalid(e.to_string()))
+        }
+    }
+    /// the atom radii plus 0.3 angstrom
+    impl Default for BondCriterion {
+        fn default() -> Self {
+            BondCriterion::radii(None, None, None)
+        }
+    }
+    impl Cutoff for BondCriterion {
+        fn cutoff(&self, element1: &str, element2: &str) -> Result<f64, ElementError> {
+            match self {
+                BondCriterion::Fixed(cutoff) => Ok(*cutoff),
+                BondCriterion::Radii { set, scale, tolerance } => {
+                    let table = PeriodicTable::global();
+                    Ok((table.radius(element1, *set)? + table.radius(element2, *set)?) * scale + tolerance)
+                },
+                BondCriterion::Pairs { cutoffs, fallback } => {
+                    // elements unknown to the periodic table may still be bonded by a fixed fallback
+                    match symbols(element1, element2).ok().and_then(|pair| cutoffs.get(&pair)) {
+                        Some(cutoff) => Ok(*cutoff),
+                        None => fallback.cutoff(element1, element2),
+                    }
+                },
+            }
+        }
+    }
+
+    /// the symbols of a pair of elements in a fixed order, so that lookups do not depend on it
+    fn symbols(a: &str, b: &str) -> Result<(String, String), ElementError> {
+        let table = PeriodicTable::global();
+        let (a, b) = (table.lookup(a)?.symbol.clone(), table.lookup(b)?.symbol.clone());
+        Ok(if a <= b { (a, b) } else { (b, a) })
+    }
+
+    /// a neighbour of an atom
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Neighbor {
+        /// the index of the neighbour in the atoms of the block
+        pub index: usize,
+        pub distance: f64,
+        /// the vector from the atom to the neighbour
+        pub vector: Coordinate,
+        /// the lattice translation of the neighbour from its position in the block, zero in clusters
+        pub image: [i64; 3],
+    }
+
+    /**
+    the neighbours of every atom of a block within the cutoff of their elements
+
+    the atoms are sorted into cells at least as wide as the largest cutoff, so only the atoms of the adjacent
+    cells are compared. In periodic blocks the cells are taken in fractional coordinates and wrap around the
+    lattice, every image within the cutoff is a neighbour: a cutoff beyond half the width of the cell gives
+    several images of a pair, and an atom may neighbour its own images.
+     */
+    pub struct NeighborList {
+        neighbors: Vec<Vec<Neighbor>>,
+    }
+    impl NeighborList {
+        /// an error if the cutoff of a pair of the elements of the block is unknown
+        pub fn new<C: Cutoff + ?Sized>(block: &StructureBlock, cutoff: &C) -> Result<Self, ElementError> {
+            // the cutoffs of the pairs of elements of the block
+            let mut elements: Vec<&str> = Vec::new();
+            let species: Vec<usize> = block.atoms.iter().map(|atom| {
+                match elements.iter().position(|e| *e == atom.element) {
+                    Some(index) => index,
+                    None => {
+                        elements.push(&atom.element);
+                        elements.len() - 1
+                    },
+                }
+            }).collect();
+            let cutoffs: Vec<Vec<f64>> = elements.iter()
+                .map(|e1| elements.iter().map(|e2| cutoff.cutoff(e1, e2)).collect())
+                .collect::<Result<_, _>>()?;
+            let max_cutoff = cutoffs.iter().flatten().cloned().fold(0.0, f64::max);
+            let mut neighbors = vec![Vec::new(); block.atoms.len()];
+            if max_cutoff > 0.0 {
+                let within = |i: usize, j: usize, vector: &RowVector3<f64>| {
+                    let cutoff = cutoffs[species[i]][species[j]];
+                    let distance = vector.norm();
+                    (distance <= cutoff).then_some(distance)
+                };
+                match block.periodic_lattice() {
+                    Some(lattice) => Self::search_periodic(block, lattice.matrix(), max_cutoff, within, &mut neighbors),
+                    None => Self::search_cluster(block, max_cutoff, within, &mut neighbors),
+                }
+            }
+            Ok(Self { neighbors })
+        }
+
+        /// linked cells of the cartesian positions
+        fn search_cluster<W>(block: &StructureBlock, max_cutoff: f64, within: W, neighbors: &mut [Vec<Neighbor>])
+        where
+            W: Fn(usize, usize, &RowVector3<f64>) -> Option<f64>,
+        {
+            let positions: Vec<RowVector3<f64>> = block.atoms.iter()
+                .map(|atom| RowVector3::new(atom.coordinate.0, atom.coordinate.1, atom.coordinate.2))
+                .collect();
+            let cell_of = |p: &RowVector3<f64>| [(p[0] / max_cutoff).floor() as i64, (p[1] / max_cutoff).floor() as i64, (p[2] / max_cutoff).floor() as i64];
+            let mut cells: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+            for (index, position) in positions.iter().enumerate() {
+                cells.entry(cell_of(position)).or_default().push(index);
+            }
+            for (i, position) in positions.iter().enumerate() {
+                let cell = cell_of(position);
+                for offset in offsets([1, 1, 1]) {
+                    let Some(atoms) = cells.get(&[cell[0] + offset[0], cell[1] + offset[1], cell[2] + offset[2]]) else { continue };
+                    for &j in atoms.iter().filter(|&&j| j != i) {
+                        let vector = positions[j] - position;
+                        if let Some(distance) = within(i, j, &vector) {
+                            neighbors[i].push(Neighbor{ index: j, distance, vector: Coordinate(vector[0], vector[1], vector[2]), image: [0, 0, 0] });
+                        }
+                    }
+                }
+            }
+        }
+
+        /// linked cells of the fractional coordinates, wrapped into the lattice
+        fn search_periodic<W>(block: &StructureBlock, lattice: &Matrix3<f64>, max_cutoff: f64, within: W, neighbors: &mut [Vec<Neighbor>])
+        where
+            W: Fn(usize, usize, &RowVector3<f64>) -> Option<f64>,
+        {
+            let inverse = lattice.try_inverse().unwrap_or_else(Matrix3::zeros);
+            // the distance between opposite faces of the cell, from the lengths of the reciprocal vectors
+            let widths: Vec<f64> = (0..3).map(|i| 1.0 / inverse.column(i).norm()).collect();
+            // cells at least as wide as the cutoff, or the whole lattice along a thin axis
+            let counts: [i64; 3] = std::array::from_fn(|i| ((widths[i] / max_cutoff).floor() as i64).max(1));
+            // how many cells a cutoff spans, more than one when the lattice is thinner than the cutoff
+            let reach: [i64; 3] = std::array::from_fn(|i| (max_cutoff * counts[i] as f64 / widths[i]).ceil() as i64);
+            // the wrapped fractional coordinates and the lattice translation taking them back
+            let (wrapped, shifts): (Vec<RowVector3<f64>>, Vec<RowVector3<f64>>) = block.atoms.iter().map(|atom| {
+                let fractional = RowVector3::new(atom.coordinate.0, atom.coordinate.1, atom.coordinate.2) * inverse;
+                let shift = fractional.map(f64::floor);
+                (fractional - shift, shift)
+            }).unzip();
+            let cell_of = |f: &RowVector3<f64>| -> [i64; 3] { std::array::from_fn(|i| ((f[i] * counts[i] as f64).floor() as i64).clamp(0, counts[i] - 1)) };
+            let mut cells: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+            for (index, fractional) in wrapped.iter().enumerate() {
+                cells.entry(cell_of(fractional)).or_default().push(index);
+            }
+            for (i, fractional) in wrapped.iter().enumerate() {
+                let cell = cell_of(fractional);
+                for offset in offsets(reach) {
+                    let unwrapped: [i64; 3] = std::array::from_fn(|k| cell[k] + offset[k]);
+                    let target: [i64; 3] = std::array::from_fn(|k| unwrapped[k].rem_euclid(counts[k]));
+                    let Some(atoms) = cells.get(&target) else { continue };
+                    // the lattice translation of the cell beyond the edge of the lattice
+                    let translation = RowVector3::from_fn(|_, k| unwrapped[k].div_euclid(counts[k]) as f64);
+                    let is_origin = translation.iter().all(|t| *t == 0.0);
+                    for &j in atoms.iter().filter(|&&j| j != i || !is_origin) {
+                        let vector = (wrapped[j] + translation - fractional) * lattice;
+                        if let Some(distance) = within(i, j, &vector) {
+                            let image = translation + shifts[i] - shifts[j];
+                            neighbors[i].push(Neighbor{
+                                index: j,
+                                distance,
+                                vector: Coordinate(vector[0], vector[1], vector[2]),
+                                image: [image[0] as i64, image[1] as i64, image[2] as i64],
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        /// the neighbours of an atom
+        pub fn neighbors(&self, index: usize) -> &[Neighbor] {
+            &self.neighbors[index]
+        }
+        /// the number of neighbours of every atom
+        pub fn coordination(&self) -> Vec<u64> {
+            self.neighbors.iter().map(|n| n.len() as u64).collect()
+        }
+        /// the number of atoms of the list
+        pub fn len(&self) -> usize {
+            self.neighbors.len()
+        }
+        pub fn is_empty(&self) -> bool {
+            self.neighbors.is_empty()
+        }
+        pub fn iter(&self) -> impl Iterator<Item = &[Neighbor]> {
+            self.neighbors.iter().map(Vec::as_slice)
+        }
+    }
+
+    /// every offset of a cell up to `reach` cells away along each axis
+    fn offsets(reach: [i64; 3]) -> impl Iterator<Item = [i64; 3]> {
+        (-reach[0]..=reach[0]).flat_map(move |i| {
+            (-reach[1]..=reach[1]).flat_map(move |j| (-reach[2]..=reach[2]).map(move |k| [i, j, k]))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::modules::periodic_table::{ElementError, RadiusSet};
+    use crate::modules::structures::{Atom, Coordinate, CrystalInfo, StructureBlock};
+    use crate::neighbor::neighbor_list::*;
+    use tempfile::TempDir;
+
+    /// atoms at pseudo-random fractional coordinates, some of them outside the cell
+    fn random_block(count: usize, crystal: CrystalInfo, periodic: bool) -> StructureBlock {
+        let mut seed: u64 = 42;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64 * 1.4 - 0.2
+        };
+        let mut block = StructureBlock{
+            number: 0,
+            energy: 0.0,
+            symmetry: "C1".to_string(),
+            crystal,
+            atoms: Vec::new(),
+            periodic,
+            forces: None,
+            record: None,
+            stress: None
+        };
+        let lattice = block.lattice().unwrap();
+        for i in 0..count {
+            let element = if i % 3 == 0 { "O" } else { "Fe" };
+            let coordinate = lattice.to_cartesian(&Coordinate(next(), next(), next()));
+            block.atoms.push(Atom{ element: element.to_string(), coordinate, record: None, selective: None });
+        }
+        block
+    }
+
+    /// the sorted neighbour distances of every atom, by comparing every pair
+    fn brute_force(block: &StructureBlock, cutoff: &dyn Cutoff) -> Vec<Vec<(usize, i64)>> {
+        let key = |distance: f64| (distance * 1e6).round() as i64;
+        let lattice = block.periodic_lattice();
+        block.atoms.iter().enumerate().map(|(i, a)| {
+            let mut found = Vec::new();
+            for (j, b) in block.atoms.iter().enumerate() {
+                let limit = cutoff.cutoff(&a.element, &b.element).unwrap();
+                let vector = b - a;
+                match lattice.as_ref() {
+                    Some(lattice) => {
+                        for image in lattice.images_within(&vector, limit) {
+                            let distance = (image.0 * image.0 + image.1 * image.1 + image.2 * image.2).sqrt();
+                            if i != j || distance > 1e-9 {
+                                found.push((j, key(distance)));
+                            }
+                        }
+                    },
+                    None => {
+                        let distance = (vector.0 * vector.0 + vector.1 * vector.1 + vector.2 * vector.2).sqrt();
+                        if i != j && distance <= limit {
+                            found.push((j, key(distance)));
+                        }
+                    },
+                }
+            }
+            found.sort();
+            found
+        }).collect()
+    }
+
+    fn listed(list: &NeighborList) -> Vec<Vec<(usize, i64)>> {
+        list.iter().map(|neighbors| {
+            let mut found: Vec<(usize, i64)> = neighbors.iter().map(|n| (n.index, (n.distance * 1e6).round() as i64)).collect();
+            found.sort();
+            found
+        }).collect()
+    }
+
+    #[test]
+    fn test_neighbor_list_matches_brute_force() {
+        let triclinic = CrystalInfo{ x: 9.0, y: 10.0, z: 11.0, alpha: 75.0, beta: 100.0, gamma: 65.0 };
+        for periodic in [true, false] {
+            let block = random_block(120, triclinic.clone(), periodic);
+            for cutoff in [1.5, 3.2] {
+                assert_eq!(listed(&NeighborList::new(&block, &cutoff).unwrap()), brute_force(&block, &cutoff), "periodic {} cutoff {}", periodic, cutoff);
+            }
+            let radii = BondCriterion::default();
+            assert_eq!(listed(&NeighborList::new(&block, &radii).unwrap()), brute_force(&block, &radii));
+        }
+        // a cutoff beyond the width of a thin cell
+        let thin = random_block(10, CrystalInfo{ x: 2.0, y: 3.0, z: 12.0, alpha: 90.0, beta: 80.0, gamma: 110.0 }, true);
+        assert_eq!(listed(&NeighborList::new(&thin, &4.5).unwrap()), brute_force(&thin, &4.5));
+    }
+
+    #[test]
+    fn test_neighbor_images() {
+        let block = random_block(40, CrystalInfo{ x: 6.0, y: 7.0, z: 8.0, alpha: 80.0, beta: 95.0, gamma: 105.0 }, true);
+        let lattice = block.lattice().unwrap();
+        let list = NeighborList::new(&block, &3.0).unwrap();
+        assert_eq!(list.len(), 40);
+        assert_eq!(list.coordination().iter().sum::<u64>() as usize, list.iter().map(|n| n.len()).sum::<usize>());
+        for (i, neighbors) in list.iter().enumerate() {
+            for neighbor in neighbors {
+                // the neighbour translated by its image is at the end of the vector
+                let image = lattice.to_cartesian(&Coordinate(neighbor.image[0] as f64, neighbor.image[1] as f64, neighbor.image[2] as f64));
+                let position = &block.atoms[neighbor.index].coordinate;
+                let origin = &block.atoms[i].coordinate;
+                assert!((position.0 + image.0 - origin.0 - neighbor.vector.0).abs() < 1e-9);
+                assert!((position.1 + image.1 - origin.1 - neighbor.vector.1).abs() < 1e-9);
+                assert!((position.2 + image.2 - origin.2 - neighbor.vector.2).abs() < 1e-9);
+                // and the pair is listed from the other side as well
+                assert!(list.neighbors(neighbor.index).iter().any(|n| n.index == i && n.image == [-neighbor.image[0], -neighbor.image[1], -neighbor.image[2]]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bond_criterion() {
+        assert_eq!(BondCriterion::Fixed(2.0).cutoff("Fe", "O"), Ok(2.0));
+        // Fe has an atom radius of 1.17
+        assert!((BondCriterion::default().cutoff("Fe", "Fe").unwrap() - 2.64).abs() < 1e-12);
+        assert!((BondCriterion::radii(None, Some(1.5), Some(0.0)).cutoff("Fe", "Fe").unwrap() - 3.51).abs() < 1e-12);
+        // and a covalent radius of 1.32, labels are looked up in any case
+        assert!((BondCriterion::radii(Some(RadiusSet::Covalent), None, Some(0.0)).cutoff("fe", "FE").unwrap() - 2.64).abs() < 1e-12);
+        assert_eq!(BondCriterion::default().cutoff("Fe", "X"), Err(ElementError::Unknown("X".to_string())));
+        assert_eq!(BondCriterion::radii(Some(RadiusSet::Metallic), None, None).cutoff("Fe", "O"), Err(ElementError::NoRadius("O".to_string(), RadiusSet::Metallic)));
+        let pairs = BondCriterion::pairs([(("O".to_string(), "fe".to_string()), 2.3)], BondCriterion::Fixed(1.0)).unwrap();
+        assert_eq!(pairs.cutoff("Fe", "O"), Ok(2.3));
+        assert_eq!(pairs.cutoff("o", "FE"), Ok(2.3));
+        assert_eq!(pairs.cutoff("O", "O"), Ok(1.0));
+        assert_eq!(pairs.cutoff("X", "O"), Ok(1.0));
+        let unknown = BondCriterion::pairs([(("Fe".to_string(), "Xx".to_string()), 2.3)], BondCriterion::Fixed(1.0));
+        assert_eq!(unknown, Err(ElementError::Unknown("Xx".to_string())));
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bonds.toml");
+        std::fs::write(&path, "# Fe-O bonds of the oxide\nscale = 1.1\n\n[pairs]\nfe-O = 2.3\n\"Fe-Fe\" = 2.8\n").unwrap();
+        let criterion = BondCriterion::from_file(&path).unwrap();
+        assert_eq!(criterion, BondCriterion::pairs([
+            (("Fe".to_string(), "O".to_string()), 2.3),
+            (("Fe".to_string(), "Fe".to_string()), 2.8),
+        ], BondCriterion::Radii{ set: RadiusSet::Atom, scale: 1.1, tolerance: 0.3 }).unwrap());
+        let path = dir.path().join("bonds.json");
+        std::fs::write(&path, r#"{"cutoff": 2.5, "pairs": {"O-H": 1.2}}"#).unwrap();
+        let criterion = BondCriterion::from_file(&path).unwrap();
+        assert_eq!(criterion.cutoff("H", "O"), Ok(1.2));
+        assert_eq!(criterion.cutoff("Fe", "O"), Ok(2.5));
+        std::fs::write(&path, r#"{"cutoff": 2.5}"#).unwrap();
+        assert_eq!(BondCriterion::from_file(&path).unwrap(), BondCriterion::Fixed(2.5));
+        std::fs::write(&path, r#"{"radii": "alvarez", "tolerance": 0.0}"#).unwrap();
+        assert_eq!(BondCriterion::from_file(&path).unwrap(), BondCriterion::radii(Some(RadiusSet::Alvarez), None, Some(0.0)));
+
+        // the file is named in the errors
+        for content in [r#"{"cutoff": 2.5, "tolerance": 0.1}"#, r#"{"pairs": {"FeO": 2.0}}"#, r#"{"pairs": {"Fe-Xx": 2.0}}"#, r#"{"pair": {}}"#, r#"{"radii": "vdw"}"#, r#"{"cutoff": 2.5, "radii": "ionic"}"#, "{"] {
+            std::fs::write(&path, content).unwrap();
+            let err = BondCriterion::from_file(&path).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            assert!(err.to_string().starts_with(&path.display().to_string()), "{}", err);
+        }
+    }
+
+    /// run with `cargo test --release bench_neighbor_list -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_neighbor_list() {
+        let block = random_block(2000, CrystalInfo{ x: 30.0, y: 30.0, z: 30.0, alpha: 90.0, beta: 90.0, gamma: 90.0 }, true);
+        let radii = BondCriterion::default();
+        let start = std::time::Instant::now();
+        let list = NeighborList::new(&block, &radii).unwrap();
+        let cells = start.elapsed();
+        let start = std::time::Instant::now();
+        let pairs = brute_force(&block, &radii);
+        let brute = start.elapsed();
+        assert_eq!(listed(&list), pairs);
+        println!("2000 atoms: linked cells {:?}, every pair {:?}", cells, brute);
+    }
+}
@@ -0,0 +1,244 @@
+pub mod orca {
+    //! ORCA `.inp` inputs and the final geometry of `.out` outputs
+    //!
+    //! molecules have no cell, as in Gaussian files
+    use std::io::{self, BufRead, Write};
+    use std::path::Path;
+
+    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::modules::periodic_table::PeriodicTable;
+    use crate::compression::compression::{open_reader, CompressedWriter};
+    use crate::gaussian::gaussian::{molecule, multiplicity, HARTREE};
+    use crate::parser::parser::{StructureIO, StructureIterator, ParseError, Location, split_columns, parse_float};
+
+    /// the element and coordinates of an atom line, `El x y z`
+    fn parse_atom_line(line: &str, path: Option<&Path>, line_number: usize) -> Result<Atom, ParseError> {
+        let columns = split_columns(line);
+        if columns.len() < 4 {
+            return Err(ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line: line.to_string() });
+        }
+        // labels may carry a fragment or a number, as in `Fe(1)` or `Fe1`
+        let element: String = columns[0].1.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+        if element.is_empty() {
+            return Err(ParseError::UnexpectedLine{ location: Location::new(path, line_number, columns[0].0), line: line.to_string() });
+        }
+        let values = columns[1..4].iter()
+            .map(|(column, text)| parse_float(text, *column).map_err(|e| e.at(path, line_number)))
+            .collect::<Result<Vec<f64>, ParseError>>()?;
+        Ok(Atom{ element, coordinate: Coordinate(values[0], values[1], values[2]), record: None, selective: None })
+    }
+
+    /**
+    parser for ORCA inputs and outputs
+
+    an `.out` output gives a single structure: the last `CARTESIAN COORDINATES (ANGSTROEM)` and the last
+    `FINAL SINGLE POINT ENERGY`, converted to eV. An `.inp` input gives a structure per `$new_job` with a
+    `* xyz` block, the energy is read from a `# Energy:` comment.
+
+    inputs are written with `keywords` and a neutral charge, in the lowest multiplicity, one job per structure
+     */
+    pub struct OrcaParser {
+        table: PeriodicTable,
+        /// the simple input line of written inputs
+        pub keywords: String,
+    }
+    impl OrcaParser {
+        pub fn new() -> Self {
+            Self { table: PeriodicTable::new(), keywords: String::from("! B3LYP def2-SVP Opt") }
+        }
+
+        /// parse the lines of an `.out` output, `None` if they hold no coordinates
+        fn parse_output(&self, lines: &[String], path: Option<&Path>) -> Result<Option<StructureBlock>, ParseError> {
+            let mut energy = 0.0;
+            let mut start = None;
+            for (index, line) in lines.iter().enumerate() {
+                let trimmed = line.trim();
+                if let Some(value) = trimmed.strip_prefix("FINAL SINGLE POINT ENERGY") {
+                    let column = line.len() - value.trim_start().len() + 1;
+                    energy = parse_float(value.trim(), column).map_err(|e| e.at(path, index + 1))? * HARTREE;
+                }
+                else if trimmed == "CARTESIAN COORDINATES (ANGSTROEM)" {
+                    start = Some(index);
+                }
+            }
+            let start = match start {
+                Some(start) => start,
+                None => return Ok(None),
+            };
+            // a line of dashes, then the atoms up to an empty line
+            let atoms = lines.iter().enumerate().skip(start + 2)
+                .take_while(|(_, line)| !line.trim().is_empty())
+                .map(|(index, line)| parse_atom_line(line, path, index + 1))
+                .collect::<Result<Vec<Atom>, ParseError>>()?;
+            if atoms.is_empty() {
+                return Err(ParseError::TruncatedBlock{ location: Location::new(path, start + 1, 0), expected: None, found: 0 });
+            }
+            Ok(Some(molecule(energy, atoms)))
+        }
+
+        /// parse the jobs of an `.inp` input
+        fn parse_input(&self, lines: &[String], path: Option<&Path>) -> Result<Vec<StructureBlock>, ParseError> {
+            let mut blocks = Vec::new();
+            let mut energy = 0.0;
+            let mut index = 0;
+            while index < lines.len() {
+                let line = lines[index].trim();
+                if line.eq_ignore_ascii_case("$new_job") {
+                    energy = 0.0;
+                }
+                else if let Some(value) = line.strip_prefix('#').and_then(|l| l.trim().strip_prefix("Energy:")) {
+                    energy = value.trim().parse::<f64>().unwrap_or(energy);
+                }
+                else if line.starts_with('*') && line[1..].trim_start().to_lowercase().starts_with("xyz ") {
+                    let start = index + 1;
+                    let mut atoms = Vec::new();
+                    loop {
+                        index += 1;
+                        match lines.get(index).map(|l| l.trim()) {
+                            Some("*") => break,
+                            Some("") => (),
+                            Some(_) => atoms.push(parse_atom_line(&lines[index], path, index + 1)?),
+                            None => return Err(ParseError::TruncatedBlock{ location: Location::new(path, start, 0), expected: None, found: atoms.len() }),
+                        }
+                    }
+                    blocks.push(molecule(energy, atoms));
+                }
+                index += 1;
+            }
+            Ok(blocks)
+        }
+    }
+    impl Default for OrcaParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl StructureIO for OrcaParser {
+        // outputs are only read at their final geometry, everything is handled in `parse_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            let blocks = self.parse_structure(input, ignore_parse_error)?.unwrap_or_default();
+            Ok(StructureIterator::new(blocks.into_iter().map(Ok)))
+        }
+        fn parse_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+            let lines = open_reader(input).map_err(ParseError::IoError)?
+                .lines()
+                .collect::<Result<Vec<String>, io::Error>>()
+                .map_err(ParseError::IoError)?;
+            let blocks = match self.parse_output(&lines, Some(input)) {
+                Ok(Some(block)) => Ok(vec![block]),
+                Ok(None) => self.parse_input(&lines, Some(input)),
+                Err(e) => Err(e),
+            };
+            match blocks {
+                Ok(blocks) => Ok(Some(blocks)),
+                Err(_) if ignore_parse_error => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let mut file = CompressedWriter::create(path)?;
+            for (i, block) in structures.iter().enumerate() {
+                if i > 0 {
+                    writeln!(file, "$new_job")?;
+                }
+                writeln!(file, "# Structure {}", i + 1)?;
+                writeln!(file, "# Energy: {}", block.energy)?;
+                writeln!(file, "{}", self.keywords)?;
+                writeln!(file, "* xyz 0 {}", multiplicity(&self.table, &block.atoms)?)?;
+                for atom in block.atoms.iter() {
+                    writeln!(file, "  {: <3} {: >16.10} {: >16.10} {: >16.10}", atom.element, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2)?;
+                }
+                writeln!(file, "*")?;
+            }
+            file.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::{NamedTempFile, TempDir};
+
+    use crate::orca::orca::*;
+    use crate::gaussian::gaussian::HARTREE;
+    use crate::parser::parser::*;
+
+    fn coordinates(file: &mut NamedTempFile, z: f64) {
+        writeln!(file, "---------------------------------").unwrap();
+        writeln!(file, "CARTESIAN COORDINATES (ANGSTROEM)").unwrap();
+        writeln!(file, "---------------------------------").unwrap();
+        writeln!(file, "  O      0.000000    0.000000    {:.6}", z).unwrap();
+        writeln!(file, "  H      0.000000    0.763239   -0.477047").unwrap();
+        writeln!(file, "  H      0.000000   -0.763239   -0.477047").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "----------------------------").unwrap();
+        writeln!(file, "CARTESIAN COORDINATES (A.U.)").unwrap();
+        writeln!(file, "----------------------------").unwrap();
+    }
+
+    #[test]
+    fn test_parse_out() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "                                 *****************").unwrap();
+        writeln!(file, "                                 * O   R   C   A *").unwrap();
+        writeln!(file, "                                 *****************").unwrap();
+        coordinates(&mut file, 0.2);
+        writeln!(file, "FINAL SINGLE POINT ENERGY       -76.400000000000").unwrap();
+        coordinates(&mut file, 0.119262);
+        writeln!(file, "FINAL SINGLE POINT ENERGY       -76.408900000000").unwrap();
+        let blocks = OrcaParser::new().parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert!((blocks[0].energy + 76.4089 * HARTREE).abs() < 1e-6);
+        assert_eq!(blocks[0].atoms.len(), 3);
+        assert!((blocks[0].atoms[0].coordinate.2 - 0.119262).abs() < 1e-9);
+        assert_eq!(detect_format(file.path()).unwrap(), "orca");
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "CARTESIAN COORDINATES (ANGSTROEM)").unwrap();
+        writeln!(file, "---------------------------------").unwrap();
+        writeln!(file, "  O      0.000000    0.000000    0.1x9262").unwrap();
+        let err = OrcaParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::BadFloat{ ref location, .. } if location.line == 3 && location.column == 34), "Got {:?}", err);
+    }
+
+    #[test]
+    fn test_inp_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# Energy: -2.5").unwrap();
+        writeln!(file, "! BP86 def2-SVP Opt").unwrap();
+        writeln!(file, "*xyz 0 1").unwrap();
+        writeln!(file, "  O(1)  0.0  0.0  0.1").unwrap();
+        writeln!(file, "  H     0.0  0.76 -0.48").unwrap();
+        writeln!(file, "  H     0.0 -0.76 -0.48").unwrap();
+        writeln!(file, "*").unwrap();
+        let blocks = OrcaParser::new().parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks[0].energy, -2.5);
+        assert_eq!(blocks[0].atoms[0].element, "O");
+
+        let path = dir.path().join("water.inp");
+        let mut radical = blocks[0].clone();
+        radical.atoms.pop();
+        radical.energy = -1.0;
+        OrcaParser::new().write_structure(&[blocks[0].clone(), radical], &path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("* xyz 0 1\n") && content.contains("$new_job\n") && content.contains("* xyz 0 2\n"), "{}", content);
+        let written = OrcaParser::new().parse_structure(&path, false).unwrap().unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[1].energy, -1.0);
+        assert!((written[0].atoms[2].coordinate.1 + 0.76).abs() < 1e-9);
+        assert_eq!(detect_format(&path).unwrap(), "orca");
+    }
+}
@@ -1,344 +1,1231 @@
 pub mod parser{
     //! some necessary functions to parse a structure file
-    //! currently support: .arc, .xyz
+    //! currently support: .arc, .xyz, POSCAR/CONTCAR (.vasp), .cif, LASP TrainStr.txt, LAMMPS data and dump files, .pdb,
+    //! Quantum ESPRESSO pw.x inputs/outputs, CP2K inputs/.restart files, Gaussian .gjf/.log and ORCA .inp/.out files
+    use std::collections::HashMap;
     use std::fmt::Debug;
-    use std::{path::Path, fs::File, fmt};
+    use std::path::PathBuf;
+    use std::{path::Path, fmt};
     use std::io::{self, BufRead, Write};
-    use regex::Regex;
+    use regex::{Match, Regex};
+    use nalgebra::{Matrix3, RowVector3};
 
-    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::modules::structures::{Atom, AtomRecord, BlockRecord, StructureBlock, CrystalInfo, Coordinate};
+    use crate::vasp::poscar::PoscarParser;
+    use crate::cif::cif_parser::CifParser;
+    use crate::train::train_set::TrainStrParser;
+    use crate::lammps::lammps::{LammpsDataParser, LammpsDumpParser};
+    use crate::pdb::pdb_parser::PdbParser;
+    use crate::qe::espresso::EspressoParser;
+    use crate::cp2k::cp2k_input::Cp2kParser;
+    use crate::gaussian::gaussian::GaussianParser;
+    use crate::orca::orca::OrcaParser;
+    use crate::lasp::lasp_out::LaspOutLog;
+    use crate::compression::compression::{open_reader, split_compression, CompressedWriter, Compression};
+
+    /// position of a parse error, lines and columns start from 1 and 0 means unknown
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct Location {
+        pub path: Option<PathBuf>,
+        pub line: usize,
+        pub column: usize,
+    }
+    impl Location {
+        pub fn new(path: Option<&Path>, line: usize, column: usize) -> Self {
+            Self { path: path.map(Path::to_path_buf), line, column }
+        }
+    }
+    impl fmt::Display for Location {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match &self.path {
+                Some(path) => write!(f, "{}", path.display())?,
+                None => write!(f, "<input>")?,
+            }
+            if self.line > 0 {
+                write!(f, ":{}", self.line)?;
+            }
+            if self.column > 0 {
+                write!(f, ":{}", self.column)?;
+            }
+            Ok(())
+        }
+    }
 
     // custom error type for parsing
     pub enum ParseError {
         IoError(io::Error),
-        ParseError(Vec<String>),
+        /// a line that none of the parse functions understands
+        UnexpectedLine{ location: Location, line: String },
+        /// a number that can not be parsed
+        BadFloat{ location: Location, text: String },
+        /// an atom line before the first block header
+        AtomBeforeHeader{ location: Location },
+        /// a block of a periodic file without cell information, located at the block header
+        MissingCell{ location: Location },
+        /// a block cut short by the end of the file, located at the block header
+        TruncatedBlock{ location: Location, expected: Option<usize>, found: usize },
+        /// a file type or file that is not a supported format
+        UnknownFormat(String),
+    }
+    impl ParseError {
+        /// the location of the error, `None` for IO errors
+        pub fn location(&self) -> Option<&Location> {
+            match self {
+                ParseError::IoError(_) | ParseError::UnknownFormat(_) => None,
+                ParseError::UnexpectedLine{ location, .. }
+                | ParseError::BadFloat{ location, .. }
+                | ParseError::AtomBeforeHeader{ location }
+                | ParseError::MissingCell{ location }
+                | ParseError::TruncatedBlock{ location, .. } => Some(location),
+            }
+        }
+        /// set the file and line of an error raised on a single line, keeping its column
+        pub fn at(mut self, path: Option<&Path>, line: usize) -> Self {
+            match &mut self {
+                ParseError::IoError(_) | ParseError::UnknownFormat(_) => (),
+                ParseError::UnexpectedLine{ location, .. }
+                | ParseError::BadFloat{ location, .. }
+                | ParseError::AtomBeforeHeader{ location }
+                | ParseError::MissingCell{ location }
+                | ParseError::TruncatedBlock{ location, .. } => {
+                    location.path = path.map(Path::to_path_buf);
+                    location.line = line;
+                },
+            }
+            self
+        }
     }
     impl fmt::Display for ParseError {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             match self {
                 ParseError::IoError(e) => write!(f, "IO error: {}", e),
-                ParseError::ParseError(s) => write!(f, "Parse error: {:?}", s),
+                ParseError::UnexpectedLine{ location, line } => write!(f, "{}: unexpected line {:?}", location, line),
+                ParseError::BadFloat{ location, text } => write!(f, "{}: invalid number {:?}", location, text),
+                ParseError::AtomBeforeHeader{ location } => write!(f, "{}: atom before any block header", location),
+                ParseError::MissingCell{ location } => write!(f, "{}: block without cell information", location),
+                ParseError::TruncatedBlock{ location, expected: Some(expected), found } => write!(f, "{}: truncated block, expected {} atoms but found {}", location, expected, found),
+                ParseError::TruncatedBlock{ location, expected: None, found } => write!(f, "{}: truncated block, the file ends after {} atoms", location, found),
+                ParseError::UnknownFormat(format) => write!(f, "unknown file format: {}", format),
             }
         }
     }
     impl Debug for ParseError {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            fmt::Display::fmt(self, f)
+        }
+    }
+    impl std::error::Error for ParseError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
             match self {
-                ParseError::IoError(e) => write!(f, "IO error: {}", e),
-                ParseError::ParseError(s) => write!(f, "Parse error: {:?}", s),
+                ParseError::IoError(e) => Some(e),
+                _ => None,
+            }
+        }
+    }
+
+    /// split a line into whitespace separated columns, together with their 1-based column numbers
+    pub fn split_columns(input: &str) -> Vec<(usize, &str)> {
+        let mut columns = Vec::new();
+        let mut start = None;
+        for (i, c) in input.char_indices().chain(std::iter::once((input.len(), ' '))) {
+            match (start, c.is_whitespace()) {
+                (None, false) => start = Some(i),
+                (Some(s), true) => {
+                    columns.push((s + 1, &input[s..i]));
+                    start = None;
+                },
+                _ => (),
             }
         }
+        columns
+    }
+
+    /// parse a number found at the given column of a line
+    pub fn parse_float(text: &str, column: usize) -> Result<f64, ParseError> {
+        text.parse::<f64>().map_err(|_| ParseError::BadFloat{ location: Location::new(None, 0, column), text: text.to_string() })
     }
+
+    /// the whitespace separated tokens of a line with their 1-based column numbers, as `split_columns` without allocating
+    pub fn tokens(input: &str) -> impl Iterator<Item = (usize, &str)> {
+        input.split_whitespace().map(move |token| (token.as_ptr() as usize - input.as_ptr() as usize + 1, token))
+    }
+
+    /// parse a number captured by a regex
+    fn parse_capture(capture: Option<Match>) -> Result<f64, ParseError> {
+        match capture {
+            Some(capture) => parse_float(capture.as_str(), capture.start() + 1),
+            None => Err(ParseError::BadFloat{ location: Location::default(), text: String::new() }),
+        }
+    }
+
     pub trait StructureIO{
         /// parse atom information line
-        fn parse_atom(&self, input: &str) -> Option<Atom>;
+        ///
+        /// returns `Ok(None)` if the line is not an atom line, or an error if it is but can not be parsed
+        fn parse_atom(&self, input: &str) -> Result<Option<Atom>, ParseError>;
 
         /// parse cell information line
-        fn parse_cell(&self, intput: &str) -> Option<CrystalInfo>;
+        fn parse_cell(&self, intput: &str) -> Result<Option<CrystalInfo>, ParseError>;
 
         /// parse block header line
-        fn parse_header(&self, input: &str) -> Option<StructureBlock>;
+        fn parse_header(&self, input: &str) -> Result<Option<StructureBlock>, ParseError>;
+
+        /// parse a line declaring whether the file is periodic, `PBC=ON` in .arc files
+        fn parse_periodicity(&self, _input: &str) -> Option<bool> {
+            None
+        }
+
+        /// check if the line closes a block, `end` in .arc files
+        fn is_block_end(&self, _input: &str) -> bool {
+            false
+        }
+
+        /// check if the line follows the header of a block and is kept by `record_line`, `!DATE` in .arc files
+        fn is_header_record(&self, _input: &str) -> bool {
+            false
+        }
+
+        /// keep a line of the current block that is neither an atom nor a header line,
+        /// e.g. the `!DATE` line of .arc files, so that the block can be written back unchanged
+        fn record_line(&self, _block: &mut StructureBlock, _input: &str) {}
         
         /// check if the input is illegal
         /// 
-        /// the default implementation returns false if the input is empty, or none of the parse functions can parse the input
-        fn is_illegal(&self, input: &str) -> bool{
-            input.is_empty() || (self.parse_atom(input).is_none() && self.parse_header(input).is_none() && self.parse_cell(input).is_none())
+        /// only the lines that are no atom, header, cell, periodicity or end line are checked, so the parse
+        /// functions need not be tried again. The default implementation takes all of them as illegal
+        fn is_illegal(&self, _input: &str) -> bool{
+            true
+        }
+        /// lazily parse the structures of a file, one block at a time
+        ///
+        /// the default implementation feeds the lines to `parse_atom`, `parse_header` and `parse_cell`,
+        /// trying first the kind of line the block expects next.
+        /// Parse errors are yielded as they are met unless `ignore_parse_error` is set
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError>{
+            let reader = open_reader(input).map_err(ParseError::IoError)?;
+            Ok(StructureIterator::from_lines(self, reader, Some(input), ignore_parse_error))
         }
+        /// parse all structures of a file
+        ///
+        /// returns `None` if no structure is found, or the first `ParseError` unless `ignore_parse_error` is set
         fn parse_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError>{
-            // open the input file
-            let file = File::open(input).map_err(ParseError::IoError)?;
-            let reader = io::BufReader::new(file);
-            // initialize block vector and current block
-            let mut blocks: Vec<StructureBlock> = Vec::new();
-            let mut current_block: Option<StructureBlock> = None;
-            // initialize error vector
-            let mut errors: Vec<String> = Vec::new();
-            for line in reader.lines(){
-                // handle cases of io error
-                let line = line.map_err(ParseError::IoError)?;
-                // parse the line as atom information line
-                if let Some(atom) = self.parse_atom(&line){
-                    // if so, should add the atom to the current block
-                    // initialize new atom
-                    let new_atom = atom;
-                    // push the new atom to the current block
-                    if let Some(block) = current_block.as_mut(){
-                        block.atoms.push(new_atom);
-                    }
+            collect_structures(self.iter_structure(input, ignore_parse_error)?)
+        }
+        fn write_structure(&self, blocks: &[StructureBlock], output: &Path) -> io::Result<()> ;
+            
+    }
+
+    /// a lazy iterator over the structures of a file
+    pub struct StructureIterator<'a>{
+        inner: Box<dyn Iterator<Item = Result<StructureBlock, ParseError>> + 'a>,
+    }
+    impl<'a> StructureIterator<'a> {
+        /// wrap any iterator of parse results
+        pub fn new<I>(iter: I) -> Self
+        where
+            I: Iterator<Item = Result<StructureBlock, ParseError>> + 'a,
+        {
+            Self { inner: Box::new(iter) }
+        }
+        /// iterate over the blocks of a line oriented file, see `StructureIO::iter_structure`
+        ///
+        /// `path` is only used to locate parse errors
+        pub fn from_lines<P, R>(parser: &'a P, reader: R, path: Option<&Path>, ignore_parse_error: bool) -> Self
+        where
+            P: StructureIO + ?Sized,
+            R: BufRead + 'a,
+        {
+            Self::new(LineIterator::new(parser, reader.lines().enumerate(), path, ignore_parse_error, false, false))
+        }
+        /// iterate over the blocks of part of a line oriented file, whose first line is line `first_line` (from 1) of the file
+        ///
+        /// `periodic` and `uses_block_end` are the state of a sequential parse before that line, see `parse_parallel`
+        pub fn from_lines_at<P, R>(parser: &'a P, reader: R, path: Option<&Path>, ignore_parse_error: bool, first_line: usize, periodic: bool, uses_block_end: bool) -> Self
+        where
+            P: StructureIO + ?Sized,
+            R: BufRead + 'a,
+        {
+            let lines = reader.lines().enumerate().map(move |(i, line)| (first_line - 1 + i, line));
+            Self::new(LineIterator::new(parser, lines, path, ignore_parse_error, periodic, uses_block_end))
+        }
+    }
+    impl Iterator for StructureIterator<'_> {
+        type Item = Result<StructureBlock, ParseError>;
+        fn next(&mut self) -> Option<Self::Item> {
+            self.inner.next()
+        }
+    }
+
+    /// the kinds of lines of a line oriented file
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum LineKind {
+        Header,
+        HeaderRecord,
+        Cell,
+        Atom,
+        End,
+        Periodicity,
+    }
+    impl LineKind {
+        /**
+        the order in which the kinds are tried when `self` is expected
+
+        blocks go header → header records (`!DATE`) → cell → atoms → end, so the expected kind usually
+        matches at the first try, and the others are only tried for the lines that do not fit
+         */
+        fn order(self) -> [LineKind; 6] {
+            use LineKind::*;
+            match self {
+                Header | End | Periodicity => [Header, End, Periodicity, HeaderRecord, Cell, Atom],
+                HeaderRecord => [HeaderRecord, Cell, Atom, End, Header, Periodicity],
+                Cell => [Cell, Atom, HeaderRecord, End, Header, Periodicity],
+                Atom => [Atom, End, Header, Cell, HeaderRecord, Periodicity],
+            }
+        }
+    }
+
+    /// a line recognised by the parser
+    enum Line {
+        Header(StructureBlock),
+        HeaderRecord,
+        Cell(CrystalInfo),
+        Atom(Atom),
+        End,
+        Periodicity(bool),
+    }
+
+    /// state of the line oriented parsing: the block being filled is yielded when the next header is met
+    struct LineIterator<'a, P: ?Sized, L>{
+        parser: &'a P,
+        /// lines with their index in the file, from 0
+        lines: L,
+        path: Option<PathBuf>,
+        current_block: Option<StructureBlock>,
+        /// line of the header of the current block
+        block_line: usize,
+        /// whether the current block had a cell line
+        has_cell: bool,
+        /// whether the current block has been closed by an end line
+        closed: bool,
+        /// whether the file declared itself periodic
+        periodic: bool,
+        /// whether the file closes its blocks with end lines
+        uses_block_end: bool,
+        /// the kind of line the current block expects next
+        expected: LineKind,
+        ignore_parse_error: bool,
+    }
+    impl<'a, P, L> LineIterator<'a, P, L>
+    where
+        P: StructureIO + ?Sized,
+        L: Iterator<Item = (usize, io::Result<String>)>,
+    {
+        /// `periodic` is the periodicity declared before the first line, and `uses_block_end` whether an end line
+        /// came before it, for files parsed in chunks
+        fn new(parser: &'a P, lines: L, path: Option<&Path>, ignore_parse_error: bool, periodic: bool, uses_block_end: bool) -> Self {
+            Self {
+                parser,
+                lines,
+                path: path.map(Path::to_path_buf),
+                current_block: None,
+                block_line: 0,
+                has_cell: false,
+                closed: false,
+                periodic,
+                uses_block_end,
+                expected: LineKind::Header,
+                ignore_parse_error,
+            }
+        }
+        /// recognise a line, trying the expected kind first
+        fn classify(&self, line: &str) -> Result<Option<Line>, ParseError> {
+            for kind in self.expected.order() {
+                let recognised = match kind {
+                    LineKind::Header => self.parser.parse_header(line)?.map(Line::Header),
+                    LineKind::HeaderRecord => self.parser.is_header_record(line).then_some(Line::HeaderRecord),
+                    LineKind::Cell => self.parser.parse_cell(line)?.map(Line::Cell),
+                    LineKind::Atom => self.parser.parse_atom(line)?.map(Line::Atom),
+                    LineKind::End => self.parser.is_block_end(line).then_some(Line::End),
+                    LineKind::Periodicity => self.parser.parse_periodicity(line).map(Line::Periodicity),
+                };
+                if recognised.is_some() {
+                    return Ok(recognised);
                 }
-                // parse the line as block header line
-                else if let Some(initial_block) = self.parse_header(&line){
-                    // if the current_block is not None, should push it to the block vector
-                    if let Some(block) = current_block.take(){
-                        blocks.push(block);
+            }
+            Ok(None)
+        }
+        /// parse a line, returns the previous block when a new header is met
+        fn parse_line(&mut self, line: &str, line_number: usize) -> Result<Option<StructureBlock>, ParseError> {
+            let path = self.path.clone();
+            let line_kind = self.classify(line).map_err(|e| e.at(path.as_deref(), line_number))?;
+            match line_kind {
+                Some(Line::Atom(atom)) => {
+                    match self.current_block.as_mut() {
+                        Some(block) => block.atoms.push(atom),
+                        None => return Err(ParseError::AtomBeforeHeader{ location: Location::new(path.as_deref(), line_number, 1) }),
                     }
-                    // initialize the current block
-                    current_block = Some(initial_block);
-                }
-                // parse the line as cell information line
-                else if let Some(cell) = self.parse_cell(&line){
+                    self.expected = LineKind::Atom;
+                },
+                Some(Line::Header(initial_block)) => {
+                    // the previous block is complete
+                    let previous = self.current_block.take().map(|block| self.finish_block(block));
+                    self.current_block = Some(initial_block);
+                    self.block_line = line_number;
+                    self.has_cell = false;
+                    self.closed = false;
+                    self.expected = LineKind::HeaderRecord;
+                    return previous.transpose();
+                },
+                Some(Line::HeaderRecord) => {
+                    if let Some(block) = self.current_block.as_mut() {
+                        self.parser.record_line(block, line);
+                    }
+                    // clusters have no cell line
+                    self.expected = if self.periodic { LineKind::Cell } else { LineKind::Atom };
+                },
+                Some(Line::Cell(cell)) => {
                     // if the current block is not None, should set the cell information
-                    if let Some(block) = current_block.as_mut(){
+                    if let Some(block) = self.current_block.as_mut(){
+                        self.parser.record_line(block, line);
                         block.set_crystal_info(cell);
+                        block.periodic = true;
+                        self.has_cell = true;
                     }
-                }
-                else if !ignore_parse_error && self.is_illegal(&line) {
-                    errors.push(line);
-                }
+                    self.expected = LineKind::Atom;
+                },
+                Some(Line::Periodicity(periodic)) => self.periodic = periodic,
+                Some(Line::End) => {
+                    self.closed = true;
+                    self.uses_block_end = true;
+                    self.expected = LineKind::Header;
+                },
+                None if self.parser.is_illegal(line) => {
+                    return Err(ParseError::UnexpectedLine{ location: Location::new(path.as_deref(), line_number, 1), line: line.to_string() });
+                },
+                // the other lines of a block may still be kept by the parser
+                None => if let Some(block) = self.current_block.as_mut() {
+                    self.parser.record_line(block, line);
+                },
             }
-            // if the user doesn't ignore_parse_error, return the possible error
-            if !errors.is_empty() && !ignore_parse_error {
-                return Err(ParseError::ParseError(errors));
+            Ok(None)
+        }
+        /// check that a block is complete
+        fn finish_block(&self, block: StructureBlock) -> Result<StructureBlock, ParseError> {
+            let location = Location::new(self.path.as_deref(), self.block_line, 0);
+            if self.ignore_parse_error {
+                Ok(block)
+            } else if self.periodic && !self.has_cell {
+                Err(ParseError::MissingCell{ location })
+            } else if self.uses_block_end && !self.closed {
+                Err(ParseError::TruncatedBlock{ location, expected: None, found: block.atoms.len() })
+            } else {
+                Ok(block)
             }
-            // push the last block to the block vector
-            if let Some(block) = current_block.take(){
-                blocks.push(block);
+        }
+    }
+    impl<P, L> Iterator for LineIterator<'_, P, L>
+    where
+        P: StructureIO + ?Sized,
+        L: Iterator<Item = (usize, io::Result<String>)>,
+    {
+        type Item = Result<StructureBlock, ParseError>;
+        fn next(&mut self) -> Option<Self::Item> {
+            while let Some((index, line)) = self.lines.next(){
+                // handle cases of io error
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(ParseError::IoError(e))),
+                };
+                match self.parse_line(&line, index + 1) {
+                    Ok(Some(block)) => return Some(Ok(block)),
+                    Ok(None) => (),
+                    Err(_) if self.ignore_parse_error => (),
+                    Err(e) => return Some(Err(e)),
+                }
             }
-            // if the length of blocks is larger than 0, return the blocks
-            if !blocks.is_empty() {
-                Ok(Some(blocks))
-            } else {
-                Ok(None)
+            // the last block ends with the file
+            let block = self.current_block.take()?;
+            Some(self.finish_block(block))
+        }
+    }
+
+    /**
+    collect the blocks of an iterator, as returned by `StructureIO::parse_structure`
+
+    returns `None` if there is no block, or the first error of the iterator
+     */
+    pub fn collect_structures(iter: StructureIterator) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+        let blocks = iter.collect::<Result<Vec<StructureBlock>, ParseError>>()?;
+        // if the length of blocks is larger than 0, return the blocks
+        if !blocks.is_empty() {
+            Ok(Some(blocks))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /**
+    parse all structures of a line oriented file, such as an .arc file, with `threads` threads
+
+    the file is read into memory and split at header lines into a chunk per thread, each chunk is parsed with
+    the periodicity declared before it, and expects end lines if the blocks before it had them. The blocks, and
+    the first error in file order, are the same as those of `StructureIO::parse_structure`. Parsers overriding
+    `iter_structure` can not be split this way.
+     */
+    pub fn parse_parallel<P>(parser: &P, input: &Path, ignore_parse_error: bool, threads: usize) -> Result<Option<Vec<StructureBlock>>, ParseError>
+    where
+        P: StructureIO + Sync + ?Sized,
+    {
+        if threads <= 1 {
+            return parser.parse_structure(input, ignore_parse_error);
+        }
+        let mut lines = open_reader(input).map_err(ParseError::IoError)?
+            .lines()
+            .collect::<Result<Vec<String>, io::Error>>()
+            .map_err(ParseError::IoError)?;
+        let is_header = |line: &String| matches!(parser.parse_header(line), Ok(Some(_)));
+        let first = lines.iter().position(is_header).unwrap_or(lines.len());
+        // the other chunks start at the first header after an even split of the lines
+        let mut starts = vec![0];
+        for i in 1..threads {
+            let split = (first + (lines.len() - first) * i / threads).max(starts[starts.len() - 1] + 1);
+            if let Some(offset) = lines.get(split..).and_then(|rest| rest.iter().position(is_header)) {
+                starts.push(split + offset);
             }
         }
-        fn write_structure(&self, blocks: &Vec<StructureBlock>, output: &Path) -> io::Result<()> ;
-            
+        // the periodicity and the use of end lines seen by a sequential parse at the start of each chunk
+        let mut states = Vec::new();
+        let (mut periodic, mut uses_block_end, mut scanned) = (false, false, 0);
+        for &start in &starts {
+            for line in &lines[scanned..start] {
+                if let Some(declared) = parser.parse_periodicity(line) {
+                    periodic = declared;
+                } else if parser.is_block_end(line) {
+                    uses_block_end = true;
+                }
+            }
+            scanned = start;
+            states.push((periodic, uses_block_end));
+        }
+        let mut chunks = Vec::new();
+        for (start, state) in starts.into_iter().zip(states).rev() {
+            chunks.push((start, state, lines.split_off(start)));
+        }
+        chunks.reverse();
+        let results = std::thread::scope(|scope| {
+            let handles = chunks.into_iter().map(|(start, (periodic, uses_block_end), chunk)| scope.spawn(move || {
+                let lines = chunk.into_iter().map(Ok).enumerate().map(|(i, line)| (start + i, line));
+                LineIterator::new(parser, lines, Some(input), ignore_parse_error, periodic, uses_block_end).collect::<Result<Vec<StructureBlock>, ParseError>>()
+            })).collect::<Vec<_>>();
+            handles.into_iter().map(|handle| handle.join().expect("parsing thread panicked")).collect::<Vec<_>>()
+        });
+        let blocks = results.into_iter()
+            .collect::<Result<Vec<Vec<StructureBlock>>, ParseError>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<StructureBlock>>();
+        Ok((!blocks.is_empty()).then_some(blocks))
     }
 
+    /**
+    parser for BIOSYM archives
+
+    lines are split into whitespace separated tokens and recognised by their first tokens: an `Energy` header
+    indented by whitespace, a `PBC` cell line, and atom lines with a label in the first column and `CORE` as
+    fifth token. Only atom labels outside ASCII are matched by a regex, for the Unicode word characters.
+    Blocks are read as header → `!DATE` → cell → atoms → end, each line is first tried as the kind expected there.
+     */
     pub struct ArcParser{
         atom_data_regex: Vec<Regex>,
-        block_header_regex: Vec<Regex>,
-        cell_info_regex: Vec<Regex>,       
     }
     impl ArcParser {
         pub fn new() -> Self {
+            // numbers are matched loosely, so that malformed ones are reported instead of skipped
             Self { atom_data_regex: vec![
-                Regex::new(r"^(?P<s>\w+)\s+(?P<f1>-?\d+\.\d+)\s+(?P<f2>-?\d+\.\d+)\s+(?P<f3>-?\d+\.\d+)\s+CORE\s+.*").unwrap()
-            ], 
-            block_header_regex: vec![
-                Regex::new(r"^\s+Energy\s+(\d+)\s+(-?[0-9.]+)\s+(-?[0-9.]+)\s+(.*)$").unwrap(), 
-                Regex::new(r"^\s+Energy\s+(\d+)\s+(-?[0-9.]+)\s+(-?[0-9.]+)").unwrap()
-            ], 
-            cell_info_regex: vec![
-                Regex::new(r"^\w+\s+(?P<x>\d+\.\d+)\s+(?P<y>\d+\.\d+)\s+(?P<z>\d+\.\d+)\s+(?P<alpha>\d+.\d+)\s+(?P<beta>\d+.\d+)\s+(?P<gamma>\d+.\d+)").unwrap()
+                Regex::new(r"^(?P<s>\w+)\s+(?P<f1>\S+)\s+(?P<f2>\S+)\s+(?P<f3>\S+)\s+CORE\s+(?P<tail>.*)").unwrap()
             ]}
         }
-    }
-    impl StructureIO for ArcParser{
-        fn parse_atom(&self, input: &str) -> Option<Atom> {
+
+        /// build an atom from the tokens of its line, `tail` holds the tokens after `CORE`
+        fn atom_from_tokens<'b>(input: &str, label: &str, coordinates: [(usize, &str); 3], mut tail: impl Iterator<Item = (usize, &'b str)>) -> Result<Atom, ParseError> {
+            let [f1, f2, f3] = coordinates.map(|(column, text)| parse_float(text, column));
+            let coordinate = Coordinate(f1?, f2?, f3?);
+            // residue number, force-field type, element, charge and index after CORE
+            let record = match (tail.next(), tail.next(), tail.next(), tail.next(), tail.next()) {
+                (Some((_, residue)), Some((_, ff_type)), Some((_, element)), Some((column, charge)), Some((_, index))) => Some((element.to_string(), AtomRecord{
+                    label: label.to_string(),
+                    residue: residue.to_string(),
+                    ff_type: ff_type.to_string(),
+                    charge: parse_float(charge, column)?,
+                    index: index.to_string(),
+                    raw: input.to_string(),
+                })),
+                _ => None,
+            };
+            Ok(match record {
+                Some((element, record)) => Atom{ element, coordinate, record: Some(record), selective: None },
+                None => Atom{ element: label.to_string(), coordinate, record: None, selective: None },
+            })
+        }
+
+        /// match an atom line with a label outside ASCII
+        fn parse_atom_regex(&self, input: &str) -> Result<Option<Atom>, ParseError> {
             for regex in self.atom_data_regex.iter(){
                 if let Some(caps) = regex.captures(input) {
-                    let s = caps.name("s").unwrap().as_str().to_string();
-                    let f1 = caps.name("f1").unwrap().as_str().parse().unwrap();
-                    let f2 = caps.name("f2").unwrap().as_str().parse().unwrap();
-                    let f3 = caps.name("f3").unwrap().as_str().parse().unwrap();
-                    return Some(Atom{
-                        element: s,
-                        coordinate: Coordinate(f1, f2, f3)
+                    let label = caps.name("s").unwrap().as_str();
+                    let coordinates = ["f1", "f2", "f3"].map(|name| {
+                        let capture = caps.name(name).unwrap();
+                        (capture.start() + 1, capture.as_str())
                     });
+                    let tail = caps.name("tail").unwrap();
+                    let tail = tokens(tail.as_str()).map(move |(column, token)| (tail.start() + column, token));
+                    return Self::atom_from_tokens(input, label, coordinates, tail).map(Some);
                 }
             }
-            None
+            Ok(None)
         }
-        fn parse_cell(&self, intput: &str) -> Option<CrystalInfo> {
-            for regex in self.cell_info_regex.iter(){
-                if let Some(caps) = regex.captures(intput) {
-                    let x = caps.name("x").unwrap().as_str().parse().unwrap();
-                    let y = caps.name("y").unwrap().as_str().parse().unwrap();
-                    let z = caps.name("z").unwrap().as_str().parse().unwrap();
-                    let alpha = caps.name("alpha").unwrap().as_str().parse().unwrap();
-                    let beta = caps.name("beta").unwrap().as_str().parse().unwrap();
-                    let gamma = caps.name("gamma").unwrap().as_str().parse().unwrap();
-                    return Some(CrystalInfo{
-                        x,
-                        y,
-                        z,
-                        alpha,
-                        beta,
-                        gamma
-                    });
-                }
+
+        /// the line an atom was read from, if the atom is unchanged since
+        fn unchanged_atom_line<'b>(&self, atom: &'b Atom) -> Option<&'b str> {
+            let record = atom.record.as_ref()?;
+            let read = self.parse_atom(&record.raw).ok()??;
+            let unchanged = read.element == atom.element
+                && read.coordinate.0 == atom.coordinate.0
+                && read.coordinate.1 == atom.coordinate.1
+                && read.coordinate.2 == atom.coordinate.2
+                && read.record.as_ref() == Some(record);
+            unchanged.then_some(record.raw.as_str())
+        }
+        /// the header line a block was read from, if the header fields are unchanged since
+        fn unchanged_header_line<'b>(&self, block: &'b StructureBlock) -> Option<&'b str> {
+            let record = block.record.as_ref()?;
+            let read = self.parse_header(&record.header).ok()??;
+            let unchanged = read.number == block.number
+                && read.energy == block.energy
+                && read.symmetry == block.symmetry
+                && read.record.is_some_and(|r| r.second == record.second);
+            unchanged.then_some(record.header.as_str())
+        }
+        /// the cell line a block was read from, if the cell is unchanged since
+        fn unchanged_cell_line<'b>(&self, block: &'b StructureBlock) -> Option<&'b str> {
+            let line = block.record.as_ref()?.cell.as_ref()?;
+            let read = self.parse_cell(line).ok()??;
+            let crystal = &block.crystal;
+            let unchanged = (read.x, read.y, read.z, read.alpha, read.beta, read.gamma) == (crystal.x, crystal.y, crystal.z, crystal.alpha, crystal.beta, crystal.gamma);
+            unchanged.then_some(line.as_str())
+        }
+    }
+    impl Default for ArcParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+    impl StructureIO for ArcParser{
+        fn parse_atom(&self, input: &str) -> Result<Option<Atom>, ParseError> {
+            let mut line = tokens(input);
+            // the label is in the first column and only holds word characters
+            let label = match line.next() {
+                Some((1, label)) if label.is_ascii() => label,
+                Some((1, _)) => return self.parse_atom_regex(input),
+                _ => return Ok(None),
+            };
+            if !label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+                return Ok(None);
+            }
+            match (line.next(), line.next(), line.next(), line.next()) {
+                // `CORE` is followed by whitespace, even if nothing comes after it
+                (Some(f1), Some(f2), Some(f3), Some((column, "CORE"))) if input[column + 3..].starts_with(char::is_whitespace) => {
+                    Self::atom_from_tokens(input, label, [f1, f2, f3], line).map(Some)
+                },
+                _ => Ok(None),
             }
-            None
         }
-        fn parse_header(&self, input: &str) -> Option<StructureBlock> {
-            for regex in self.block_header_regex.iter(){
-                if let Some(captures) = regex.captures(input) {
-                    let number = captures[1].parse::<u64>().unwrap();
-                    let _float1 = captures[2].parse::<f64>().unwrap();
-                    let energy = captures[3].parse::<f64>().unwrap();
-                    let symmetry = captures.get(4).map(|s| s.as_str()).unwrap_or("C1").to_string();
-                    return Some(StructureBlock{
-                        number,
-                        energy,
-                        symmetry,
-                        crystal: CrystalInfo{
-                            x: 0.0,
-                            y: 0.0,
-                            z: 0.0,
-                            alpha: 0.0,
-                            beta: 0.0,
-                            gamma: 0.0
-                        },
-                        atoms: Vec::new()
-                    });
+        fn parse_cell(&self, intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            let mut line = tokens(intput);
+            if !matches!(line.next(), Some((1, "PBC"))) {
+                return Ok(None);
+            }
+            let values = line.take(6).collect::<Vec<(usize, &str)>>();
+            if values.len() < 6 {
+                return Ok(None);
+            }
+            let [x, y, z, alpha, beta, gamma] = [0, 1, 2, 3, 4, 5].map(|i| parse_float(values[i].1, values[i].0));
+            Ok(Some(CrystalInfo{
+                x: x?,
+                y: y?,
+                z: z?,
+                alpha: alpha?,
+                beta: beta?,
+                gamma: gamma?
+            }))
+        }
+        fn parse_header(&self, input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            let mut line = tokens(input);
+            // the header is indented
+            let (number, second, energy) = match (line.next(), line.next(), line.next(), line.next()) {
+                (Some((column, "Energy")), Some(number), Some(second), Some(energy)) if column > 1 => (number, second, energy),
+                _ => return Ok(None),
+            };
+            let number = number.1.parse::<u64>().map_err(|_| ParseError::BadFloat{ location: Location::new(None, 0, number.0), text: number.1.to_string() })?;
+            let second = parse_float(second.1, second.0)?;
+            let symmetry = input[energy.0 - 1 + energy.1.len()..].trim();
+            let energy = parse_float(energy.1, energy.0)?;
+            let symmetry = match symmetry {
+                "" => String::from("C1"),
+                symmetry => symmetry.to_string(),
+            };
+            Ok(Some(StructureBlock{
+                number,
+                energy,
+                symmetry,
+                crystal: CrystalInfo{
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                    alpha: 0.0,
+                    beta: 0.0,
+                    gamma: 0.0
+                },
+                atoms: Vec::new(),
+                periodic: false,
+                forces: None,
+                record: Some(BlockRecord{
+                    second,
+                    header: input.to_string(),
+                    ..Default::default()
+                }),
+                stress: None
+            }))
+        }
+        fn parse_periodicity(&self, input: &str) -> Option<bool> {
+            match input.trim() {
+                "PBC=ON" => Some(true),
+                "PBC=OFF" => Some(false),
+                _ => None,
+            }
+        }
+        fn is_block_end(&self, input: &str) -> bool {
+            input.trim() == "end"
+        }
+        fn is_header_record(&self, input: &str) -> bool {
+            input.starts_with("!DATE")
+        }
+        fn record_line(&self, block: &mut StructureBlock, input: &str) {
+            if let Some(record) = block.record.as_mut() {
+                if input.starts_with("!DATE") {
+                    record.date = Some(input.to_string());
+                } else if input.starts_with("PBC") && !input.starts_with("PBC=") {
+                    record.cell = Some(input.to_string());
                 }
             }
-            None
         }
         fn is_illegal(&self, input: &str) -> bool {
-            input.is_empty() || (self.parse_atom(input).is_none() && self.parse_header(input).is_none() && self.parse_cell(input).is_none() && !["end", "!DATE"].contains(&input.trim()) && !input.contains("!BIOSYM archive") && !input.contains("PBC="))
+            // the other lines were already recognised as atoms, headers, cells, `!DATE`, `PBC=ON` or end lines
+            input.is_empty() || (!input.contains("!BIOSYM archive") && !input.contains("PBC="))
         }
-        fn write_structure(&self, structures:&Vec<StructureBlock>, path: &Path) -> io::Result<()> {
-            let mut file = File::create(path)?;
+        /**
+        write the blocks as a BIOSYM archive
+
+        lines read from an .arc file are written back unchanged as long as the values they hold are unchanged,
+        the other lines are formatted from the block. Files without any cell are written with `PBC=OFF`.
+         */
+        fn write_structure(&self, structures:&[StructureBlock], path: &Path) -> io::Result<()> {
+            let mut file = CompressedWriter::create(path)?;
+            let periodic = structures.is_empty() || structures.iter().any(|b| b.periodic);
             writeln!(file, "!BIOSYM archive 2")?;
-            writeln!(file, "PBC=ON")?;
+            writeln!(file, "{}", if periodic { "PBC=ON" } else { "PBC=OFF" })?;
             for block in structures.iter(){
-                writeln!(file, "{: >28} Energy {: >10} {: >16.4} {: >18.6} {: >10}", "", 0, 0.0, block.energy, block.symmetry)?;
-                writeln!(file, "!DATE")?;
-                writeln!(file, "PBC {: >14.8} {: >14.8} {: >14.8} {: >14.8} {: >14.8} {: >14.8}", block.crystal.x, block.crystal.y, block.crystal.z, block.crystal.alpha, block.crystal.beta, block.crystal.gamma)?;
+                match self.unchanged_header_line(block) {
+                    Some(line) => writeln!(file, "{}", line)?,
+                    None => {
+                        let second = block.record.as_ref().map_or(0.0, |r| r.second);
+                        writeln!(file, "{: >28} Energy {: >10} {: >16.4} {: >18.6} {: >10}", "", block.number, second, block.energy, block.symmetry)?
+                    },
+                }
+                writeln!(file, "{}", block.record.as_ref().and_then(|r| r.date.as_deref()).unwrap_or("!DATE"))?;
+                if periodic {
+                    match self.unchanged_cell_line(block) {
+                        Some(line) => writeln!(file, "{}", line)?,
+                        None => writeln!(file, "PBC {: >14.8} {: >14.8} {: >14.8} {: >14.8} {: >14.8} {: >14.8}", block.crystal.x, block.crystal.y, block.crystal.z, block.crystal.alpha, block.crystal.beta, block.crystal.gamma)?,
+                    }
+                }
                 for (i, atom) in block.atoms.iter().enumerate() {
-                    writeln!(file, "{: <5} {: >15.9} {: >15.9} {: >15.9} CORE {: >5} {: >1} {: <3} {: <5} {: <6} {: >5}", atom.element, (atom.coordinate.0), (atom.coordinate.1), (atom.coordinate.2), i+1, "", atom.element, atom.element, 0.0, i+1)?;
+                    if let Some(line) = self.unchanged_atom_line(atom) {
+                        writeln!(file, "{}", line)?;
+                        continue;
+                    }
+                    // atoms from other formats get the element as label and type, and their position as index
+                    let index = (i + 1).to_string();
+                    let (label, residue, ff_type, charge, index) = match &atom.record {
+                        Some(record) => (record.label.as_str(), record.residue.as_str(), record.ff_type.as_str(), record.charge, record.index.as_str()),
+                        None => (atom.element.as_str(), index.as_str(), atom.element.as_str(), 0.0, index.as_str()),
+                    };
+                    writeln!(file, "{: <5} {: >15.9} {: >15.9} {: >15.9} CORE {: >5} {: <3} {: <5} {: >8.4} {: >5}", label, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2, residue, ff_type, atom.element, charge, index)?;
                 }
                 writeln!(file, "end")?;
                 writeln!(file, "end")?;
             }
-            Ok(())
+            file.finish()
         }
     }
+    /**
+    parser for XYZ files, following the extended XYZ convention
+
+    the comment line of each frame is read as `key=value` pairs, e.g.
+    `Lattice="a1 a2 a3 b1 b2 b3 c1 c2 c3" Properties=species:S:1:pos:R:3:forces:R:3 energy=-1.0`,
+    so cells, energies and forces survive a round trip. A plain comment line gives a frame without cell.
+     */
     pub struct XyzParser{
         atom_data_regex: Vec<Regex>,
     }
     impl XyzParser {
         pub fn new() -> Self {
             Self { atom_data_regex: vec![
-                Regex::new(r"^(?P<s>\w+)\s+(?P<f1>-?\d+\.\d+)\s+(?P<f2>-?\d+\.\d+)\s+(?P<f3>-?\d+\.\d+)").unwrap()
+                Regex::new(r"^\s*(?P<s>[A-Za-z]\w*)\s+(?P<f1>\S+)\s+(?P<f2>\S+)\s+(?P<f3>\S+)").unwrap()
             ]}
         }
+
+        /// parse the atom count line of a frame
+        fn parse_count(&self, input: &str) -> Option<usize> {
+            input.trim().parse::<usize>().ok()
+        }
+
+        /**
+        parse a frame after its atom count line, reading the comment line and the atom lines from `lines`
+
+        `header` is the line number of the atom count line, errors in the comment line are located there
+         */
+        fn parse_frame<I>(&self, count: usize, header: usize, path: Option<&Path>, lines: &mut I) -> Result<StructureBlock, ParseError>
+        where
+            I: Iterator<Item = (usize, io::Result<String>)>,
+        {
+            let truncated = |found: usize| ParseError::TruncatedBlock{ location: Location::new(path, header, 0), expected: Some(count), found };
+            let (comment_line, comment) = match lines.next() {
+                Some((index, line)) => (index + 1, line.map_err(ParseError::IoError)?),
+                None => return Err(truncated(0)),
+            };
+            let bad_comment = || ParseError::UnexpectedLine{ location: Location::new(path, comment_line, 1), line: comment.clone() };
+            let info = parse_key_values(&comment);
+            let properties = match info.get("properties") {
+                Some(properties) => parse_properties(properties).ok_or_else(bad_comment)?,
+                None => vec![(String::from("species"), 1), (String::from("pos"), 3)],
+            };
+            // the cell is rotated into the standard orientation, together with positions and forces
+            let (crystal, rotation) = match info.get("lattice") {
+                Some(lattice) => {
+                    let values = lattice.split_whitespace().map(|s| s.parse::<f64>().ok()).collect::<Option<Vec<f64>>>();
+                    let lattice = match values {
+                        Some(values) if values.len() == 9 => Matrix3::from_row_slice(&values),
+                        _ => return Err(bad_comment()),
+                    };
+                    let crystal = CrystalInfo::from_lattice_vectors(&lattice);
+                    let inverse = lattice.try_inverse().ok_or_else(bad_comment)?;
+                    let rotation = inverse * crystal.lattice_vectors();
+                    (crystal, rotation)
+                },
+                None => (CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 }, Matrix3::identity()),
+            };
+            let rotate = |v: RowVector3<f64>| {
+                let v = v * rotation;
+                Coordinate(v[0], v[1], v[2])
+            };
+            let mut atoms = Vec::with_capacity(count);
+            let mut forces = Vec::new();
+            for found in 0..count {
+                let (line_number, line) = match lines.next() {
+                    Some((index, line)) => (index + 1, line.map_err(ParseError::IoError)?),
+                    None => return Err(truncated(found)),
+                };
+                let unexpected = || ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line: line.clone() };
+                let columns = split_columns(&line);
+                let mut element = None;
+                let mut position = None;
+                let mut index = 0;
+                for (name, width) in properties.iter() {
+                    let values = columns.get(index..index + width).ok_or_else(unexpected)?;
+                    index += width;
+                    let vector = || -> Result<RowVector3<f64>, ParseError> {
+                        if values.len() != 3 {
+                            return Err(unexpected());
+                        }
+                        let mut v = RowVector3::zeros();
+                        for (i, (column, text)) in values.iter().enumerate() {
+                            v[i] = parse_float(text, *column).map_err(|e| e.at(path, line_number))?;
+                        }
+                        Ok(v)
+                    };
+                    match name.as_str() {
+                        "species" => element = Some(values[0].1.to_string()),
+                        "pos" => position = Some(rotate(vector()?)),
+                        "forces" | "force" => forces.push(rotate(vector()?)),
+                        _ => (),
+                    }
+                }
+                match (element, position) {
+                    (Some(element), Some(coordinate)) => atoms.push(Atom{ element, coordinate, record: None, selective: None }),
+                    _ => return Err(unexpected()),
+                }
+            }
+            // older files written by this crate keep the energy as `Energy: <value>`
+            let energy = info.get("energy")
+                .and_then(|e| e.parse::<f64>().ok())
+                .or_else(|| comment.split("Energy:").nth(1).and_then(|s| s.split_whitespace().next()).and_then(|s| s.parse::<f64>().ok()))
+                .unwrap_or(0.0);
+            Ok(StructureBlock{
+                number: 0,
+                energy,
+                symmetry: String::from("C1"),
+                crystal,
+                atoms,
+                // a cell is periodic unless `pbc` says otherwise, `pbc="T T F"` slabs are still periodic
+                periodic: info.contains_key("lattice") && info.get("pbc").is_none_or(|pbc| pbc.split_whitespace().any(|v| v.eq_ignore_ascii_case("T"))),
+                forces: if forces.is_empty() { None } else { Some(forces) },
+                record: None,
+                stress: None
+            })
+        }
+    }
+    impl Default for XyzParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// split an extended XYZ comment line into `key=value` pairs, with lowercased keys.
+    /// A key without value is a boolean flag
+    fn parse_key_values(input: &str) -> HashMap<String, String> {
+        let mut pairs = HashMap::new();
+        let mut chars = input.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+            let mut key = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+                key.push(c);
+            }
+            let mut value = String::from("T");
+            if chars.next_if_eq(&'=').is_some() {
+                value.clear();
+                match chars.next_if(|c| *c == '"' || *c == '\'') {
+                    Some(quote) => {
+                        for c in chars.by_ref() {
+                            if c == quote {
+                                break;
+                            }
+                            value.push(c);
+                        }
+                    },
+                    None => {
+                        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                            value.push(c);
+                        }
+                    },
+                }
+            }
+            pairs.insert(key.to_lowercase(), value);
+        }
+        pairs
+    }
+
+    /// parse a `Properties` value, e.g. `species:S:1:pos:R:3`, into names and column widths
+    fn parse_properties(input: &str) -> Option<Vec<(String, usize)>> {
+        let fields: Vec<&str> = input.split(':').collect();
+        if !fields.len().is_multiple_of(3) {
+            return None;
+        }
+        fields.chunks(3)
+            .map(|field| Some((field[0].to_lowercase(), field[2].parse::<usize>().ok()?)))
+            .collect()
     }
 
     impl StructureIO for XyzParser{
-        fn parse_atom(&self, input: &str) -> Option<Atom> {
+        fn parse_atom(&self, input: &str) -> Result<Option<Atom>, ParseError> {
             for regex in self.atom_data_regex.iter(){
                 if let Some(caps) = regex.captures(input) {
                     let s = caps.name("s").unwrap().as_str().to_string();
-                    let f1 = caps.name("f1").unwrap().as_str().parse().unwrap();
-                    let f2 = caps.name("f2").unwrap().as_str().parse().unwrap();
-                    let f3 = caps.name("f3").unwrap().as_str().parse().unwrap();
-                    return Some(Atom{
+                    let f1 = parse_capture(caps.name("f1"))?;
+                    let f2 = parse_capture(caps.name("f2"))?;
+                    let f3 = parse_capture(caps.name("f3"))?;
+                    return Ok(Some(Atom{
                         element: s,
-                        coordinate: Coordinate(f1, f2, f3)
-                    });
+                        coordinate: Coordinate(f1, f2, f3),
+                        record: None,
+                        selective: None
+                    }));
                 }
             }
-            None
+            Ok(None)
         }
-        fn parse_cell(&self, _intput: &str) -> Option<CrystalInfo> {
-            None // xyz file doesn't have cell information
-        }
-        fn parse_header(&self, input: &str) -> Option<StructureBlock> {
-            if let Ok(number) = input.parse::<u64>() {
-                Some(StructureBlock{
-                    number,
-                    energy: 0.0,
-                    symmetry: String::from("C1"),
-                    crystal: CrystalInfo{
-                        x: 0.0,
-                        y: 0.0,
-                        z: 0.0,
-                        alpha: 0.0,
-                        beta: 0.0,
-                        gamma: 0.0
-                    },
-                    atoms: Vec::new()
-                })
-            } else {
-                None
-            }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None) // the cell is in the comment line, see `parse_frame`
+        }
+        /// parse the atom count line, the block number is the position of the frame in the file
+        fn parse_header(&self, input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(self.parse_count(input).map(|count| StructureBlock{
+                number: 0,
+                energy: 0.0,
+                symmetry: String::from("C1"),
+                crystal: CrystalInfo{
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                    alpha: 0.0,
+                    beta: 0.0,
+                    gamma: 0.0
+                },
+                atoms: Vec::with_capacity(count),
+                periodic: false,
+                forces: None,
+                record: None,
+                stress: None
+            }))
         }
         fn is_illegal(&self, input: &str) -> bool {
-            input.is_empty() || (self.parse_atom(input).is_none() && self.parse_header(input).is_none())
+            input.is_empty() || (matches!(self.parse_atom(input), Ok(None)) && matches!(self.parse_header(input), Ok(None)))
         }
-        fn write_structure(&self, structures:&Vec<StructureBlock>, path: &Path) -> io::Result<()>{
-            let mut file = File::create(path)?;
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            let mut lines = open_reader(input).map_err(ParseError::IoError)?.lines().enumerate();
+            let path = input.to_path_buf();
+            let mut number = 0;
+            Ok(StructureIterator::new(std::iter::from_fn(move || {
+                while let Some((index, line)) = lines.next() {
+                    let line = match line {
+                        Ok(line) => line,
+                        Err(e) => return Some(Err(ParseError::IoError(e))),
+                    };
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let result = match self.parse_count(&line) {
+                        Some(count) => self.parse_frame(count, index + 1, Some(&path), &mut lines),
+                        None => Err(ParseError::UnexpectedLine{ location: Location::new(Some(&path), index + 1, 1), line }),
+                    };
+                    match result {
+                        Ok(mut block) => {
+                            block.number = number;
+                            number += 1;
+                            return Some(Ok(block));
+                        },
+                        Err(ParseError::IoError(e)) => return Some(Err(ParseError::IoError(e))),
+                        Err(_) if ignore_parse_error => (),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                None
+            })))
+        }
+        fn write_structure(&self, structures:&[StructureBlock], path: &Path) -> io::Result<()>{
+            let mut file = CompressedWriter::create(path)?;
             for block in structures.iter(){
+                let lattice = block.crystal.lattice_vectors();
+                let has_lattice = lattice.determinant().abs() > 1e-8;
+                let forces = block.forces.as_ref().filter(|f| f.len() == block.atoms.len());
+                let mut comment = Vec::new();
+                if has_lattice {
+                    comment.push(format!("Lattice=\"{}\"", lattice.transpose().iter().map(|v| format!("{:.10}", v)).collect::<Vec<String>>().join(" ")));
+                }
+                if forces.is_some() {
+                    comment.push(String::from("Properties=species:S:1:pos:R:3:forces:R:3"));
+                } else {
+                    comment.push(String::from("Properties=species:S:1:pos:R:3"));
+                }
+                comment.push(format!("energy={}", block.energy));
+                comment.push(format!("pbc=\"{}\"", if has_lattice && block.periodic { "T T T" } else { "F F F" }));
                 writeln!(file, "{}", block.atoms.len())?;
-                writeln!(file, "Energy: {}", block.energy)?;
-                for atom in block.atoms.iter(){
-                    writeln!(file, "{} {} {} {}", atom.element, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2)?;
+                writeln!(file, "{}", comment.join(" "))?;
+                for (i, atom) in block.atoms.iter().enumerate(){
+                    write!(file, "{: <3} {: >16.10} {: >16.10} {: >16.10}", atom.element, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2)?;
+                    if let Some(forces) = forces {
+                        write!(file, " {: >16.10} {: >16.10} {: >16.10}", forces[i].0, forces[i].1, forces[i].2)?;
+                    }
+                    writeln!(file)?;
                 }
             }
-            Ok(())
+            file.finish()
         }
     }
 
-    pub fn get_parser(file_type: &str) -> Box<dyn StructureIO> {
-        match file_type {
-            "arc" => Box::new(ArcParser::new()),
-            "xyz" => Box::new(XyzParser::new()),
-            _ => panic!("unsupported file type: {}", file_type)
+    /// the canonical name of a supported file type, a compression suffix is ignored (`arc.gz` is `arc`)
+    pub fn canonical_format(file_type: &str) -> Option<&'static str> {
+        let (file_type, _) = split_compression(file_type);
+        match file_type.to_lowercase().as_str() {
+            "arc" => Some("arc"),
+            "xyz" => Some("xyz"),
+            "vasp" | "poscar" | "contcar" => Some("poscar"),
+            "cif" => Some("cif"),
+            "train" | "trainstr" => Some("train"),
+            "lammps-data" | "data" | "lmp" => Some("lammps-data"),
+            "lammps-dump" | "dump" | "lammpstrj" => Some("lammps-dump"),
+            "pdb" | "ent" => Some("pdb"),
+            "qe" | "espresso" | "pw" | "pwi" | "pwo" => Some("qe"),
+            "cp2k" | "restart" => Some("cp2k"),
+            "gaussian" | "gjf" | "com" | "log" => Some("gaussian"),
+            // `.inp` is used by both CP2K and ORCA inputs, they are told apart by `detect_format`
+            "orca" => Some("orca"),
+            _ => None,
         }
     }
-    pub fn read_file(filename: &str, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError> {
-        let path = Path::new(filename);
-        let file_type = path.extension().unwrap().to_str().unwrap();
-        let parser = get_parser(file_type);
-        parser.parse_structure(path, ignore_parse_error)
+
+    /// the parser of a file type, see `get_file_type` and `detect_format`
+    pub fn get_parser(file_type: &str) -> Result<Box<dyn StructureIO>, ParseError> {
+        match canonical_format(file_type) {
+            Some("arc") => Ok(Box::new(ArcParser::new())),
+            Some("xyz") => Ok(Box::new(XyzParser::new())),
+            Some("poscar") => Ok(Box::new(PoscarParser::new())),
+            Some("cif") => Ok(Box::new(CifParser::new())),
+            Some("train") => Ok(Box::new(TrainStrParser::new())),
+            Some("lammps-data") => Ok(Box::new(LammpsDataParser::new())),
+            Some("lammps-dump") => Ok(Box::new(LammpsDumpParser::new())),
+            Some("pdb") => Ok(Box::new(PdbParser::new())),
+            Some("qe") => Ok(Box::new(EspressoParser::new())),
+            Some("cp2k") => Ok(Box::new(Cp2kParser::new())),
+            Some("gaussian") => Ok(Box::new(GaussianParser::new())),
+            Some("orca") => Ok(Box::new(OrcaParser::new())),
+            _ => Err(ParseError::UnknownFormat(file_type.to_string())),
+        }
     }
     /**
-    an encapsulation of regex to parse lasp.out file
+    figure out the file type of a path, to be passed to `get_parser`
+
+    VASP files are named POSCAR/CONTCAR, often without an extension, so the file name is checked first.
+    Otherwise the extension is the file type. The extension of a compressed file is skipped,
+    so `all.arc.gz` is an .arc file.
      */
-    struct LaspOutParser{
-        re:Regex,
-    }
-    impl LaspOutParser{
-        fn new() -> Self{
-            let lasp_out_parser = Regex::new(r"^Str symm and Q\s+(?P<num>\d+)").unwrap();
-            Self { re: lasp_out_parser }
+    pub fn get_file_type(path: &Path) -> Option<&str> {
+        let name = path.file_name()?.to_str()?.to_uppercase();
+        if name.starts_with("POSCAR") || name.starts_with("CONTCAR") {
+            return Some("poscar");
+        }
+        if name.starts_with("TRAINSTR") {
+            return Some("train");
         }
+        match Compression::from_path(path) {
+            Compression::None => path.extension()?.to_str(),
+            _ => Path::new(path.file_stem()?).extension()?.to_str(),
+        }
+    }
 
-        /**
-        match lasp.out output information
-         */
-        fn parse(&self, input:&str) -> Option<u64>{
-            if let Some(captures) = self.re.captures(input){
-                let number = captures.name("num").unwrap().as_str().parse().unwrap();
-                return Some(number);
+    /// number of leading lines looked at by `sniff_format`
+    const SNIFF_LINES: usize = 8;
+
+    /**
+    guess the format of a file from its first lines
+
+    returns `None` if the lines look like none of the supported formats
+     */
+    fn sniff_format(lines: &[String]) -> Option<&'static str> {
+        let first = lines.iter().map(|l| l.trim()).find(|l| !l.is_empty())?;
+        if first.starts_with("!BIOSYM archive") {
+            return Some("arc");
+        }
+        if first.starts_with("Start Compound") {
+            return Some("train");
+        }
+        if first.starts_with("ITEM: TIMESTEP") {
+            return Some("lammps-dump");
+        }
+        if ["HEADER", "TITLE", "REMARK", "CRYST1", "MODEL", "ATOM", "HETATM"].iter().any(|record| first.starts_with(record)) {
+            return Some("pdb");
+        }
+        // pw.x inputs start with a namelist, outputs with a banner after an empty line
+        if first.to_lowercase().starts_with("&control") || lines.iter().any(|l| l.trim_start().starts_with("Program PWSCF")) {
+            return Some("qe");
+        }
+        if first.starts_with("# Version information for this restart file") || ["&GLOBAL", "&FORCE_EVAL"].iter().any(|section| first.to_uppercase().starts_with(section)) {
+            return Some("cp2k");
+        }
+        // the sections written by `Cp2kParser`, which may start a file of their own
+        if lines.iter().any(|l| l.split_whitespace().next().is_some_and(|s| s.eq_ignore_ascii_case("&CELL") || s.eq_ignore_ascii_case("&COORD"))) {
+            return Some("cp2k");
+        }
+        if lines.iter().any(|l| l.contains("Entering Gaussian System")) {
+            return Some("gaussian");
+        }
+        // the banner of outputs, or the coordinates block of inputs
+        if lines.iter().map(|l| l.trim()).any(|l| l.contains("* O   R   C   A *") || (l.starts_with('*') && l[1..].trim_start().to_lowercase().starts_with("xyz "))) {
+            return Some("orca");
+        }
+        // the comment line of a data file is followed by the counts
+        if lines.iter().skip(1).any(|l| matches!(l.split_whitespace().collect::<Vec<&str>>().as_slice(), [count, "atoms"] if count.parse::<usize>().is_ok())) {
+            return Some("lammps-data");
+        }
+        // CIF files may start with comments
+        if lines.iter().map(|l| l.trim()).find(|l| !l.is_empty() && !l.starts_with('#')).is_some_and(|l| l.to_lowercase().starts_with("data_")) {
+            return Some("cif");
+        }
+        // link 0 commands, or a route section followed by an empty line
+        if first.starts_with('%') || (first.starts_with('#') && lines.iter().skip_while(|l| l.trim().is_empty()).nth(1).is_some_and(|l| l.trim().is_empty())) {
+            return Some("gaussian");
+        }
+        let floats = |index: usize| -> Option<usize> {
+            let line = lines.get(index)?;
+            let count = line.split_whitespace().take_while(|s| s.parse::<f64>().is_ok()).count();
+            Some(count)
+        };
+        // an atom count, a comment line and atoms
+        if lines[0].trim().parse::<usize>().is_ok() {
+            let atom = lines.get(2).map(|l| l.split_whitespace().collect::<Vec<&str>>());
+            match atom {
+                Some(columns) if columns.len() >= 4 && columns[1..4].iter().all(|s| s.parse::<f64>().is_ok()) => return Some("xyz"),
+                None => return Some("xyz"),
+                _ => (),
             }
-            None
         }
+        // a comment line, the scale factor and the lattice vectors
+        if matches!(floats(1), Some(1 | 3)) && (2..5).all(|i| floats(i).is_some_and(|n| n >= 3)) {
+            return Some("poscar");
+        }
+        None
     }
 
+    /**
+    figure out the format of a file, for files without extension or with a wrong one
+
+    the first lines of the file are checked for the layout of each supported format
+    (`!BIOSYM archive`, an atom count, a POSCAR lattice block, a CIF `data_` block).
+    If they are inconclusive, the file name is used as in `get_file_type`.
+    Returns the canonical name of the format, to be passed to `get_parser`.
+     */
+    pub fn detect_format(path: &Path) -> Result<&'static str, ParseError> {
+        let lines = open_reader(path)
+            .map_err(ParseError::IoError)?
+            .lines()
+            .take(SNIFF_LINES)
+            // a binary file is not a structure file, the name decides
+            .map_while(Result::ok)
+            .collect::<Vec<String>>();
+        sniff_format(&lines)
+            .or_else(|| get_file_type(path).and_then(canonical_format))
+            .ok_or_else(|| ParseError::UnknownFormat(path.display().to_string()))
+    }
+
+    pub fn read_file(filename: &str, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+        let path = Path::new(filename);
+        let parser = get_parser(detect_format(path)?)?;
+        parser.parse_structure(path, ignore_parse_error)
+    }
+    /// `read_file` with .arc files parsed by `threads` threads, see `parse_parallel`
+    pub fn read_file_parallel(filename: &str, ignore_parse_error: bool, threads: usize) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+        let path = Path::new(filename);
+        match detect_format(path)? {
+            "arc" => parse_parallel(&ArcParser::new(), path, ignore_parse_error, threads),
+            format => get_parser(format)?.parse_structure(path, ignore_parse_error),
+        }
+    }
     /**
     check lasp.out file to find all unconverged structures
     returns: `Vec<u64>`, a vector containing the position in all.arc for unconverged strucutres
+
+    see `LaspOutLog` for the whole history of the search
      */
     pub fn find_unconverged_strucutres(path: std::path::PathBuf) -> io::Result<Vec<u64>>{
-        let mut unconverged_strucutres: Vec<u64> = Vec::new();
-        let lasp_out_path = path.join("lasp.out");
-        let file = File::open(lasp_out_path)?;
-        let reader = io::BufReader::new(file);
-        let mut previous_line = String::new();
-        let lasp_out_parser = LaspOutParser::new();
-        for line in reader.lines(){
-            let line = line?;
-            if line.contains("not converged"){
-                let number = lasp_out_parser.parse(&previous_line);
-                if let Some(number) = number{
-                    unconverged_strucutres.push(number.try_into().unwrap());
-                }
-                else{
-                    eprintln!("unseen situation!");
-                    eprintln!("{}", previous_line);
-                    eprintln!("{}",line);
-                }        
-            }
-            else {
-                previous_line = line.clone();
-            }
+        match LaspOutLog::parse(&path.join("lasp.out")) {
+            Ok(log) => Ok(log.unconverged_structures()),
+            Err(ParseError::IoError(e)) => Err(e),
+            Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
         }
-        Ok(unconverged_strucutres)
     }
 
 }
@@ -351,12 +1238,13 @@ mod tests {
 
     use crate::parser::parser::*;
     use crate::modules::structures::*;
+    use regex::Regex;
 
     #[test]
     fn test_parse_atom_success() {
         let parser = ArcParser::new();
         let input = "C        7.210469000   10.148070000    0.813536200 CORE    1 C  C    0.0000    1";
-        let atom = parser.parse_atom(input).unwrap();
+        let atom = parser.parse_atom(input).unwrap().unwrap();
         assert_eq!(atom.element, "C", "Expected 'C'. Got {:?}", atom.element);
         assert_eq!(atom.coordinate.0, 7.210469, "Expected 7.210469. Got {:?}", atom.coordinate.0);
         assert_eq!(atom.coordinate.1, 10.14807, "Expected 10.14807. Got {:?}", atom.coordinate.1);
@@ -366,15 +1254,20 @@ mod tests {
     fn test_parse_atom_fail() {
         let parser = ArcParser::new();
         let input = "some_random_string";
-        let atom = parser.parse_atom(input);
+        let atom = parser.parse_atom(input).unwrap();
         assert!(atom.is_none(), "Expected None. Got {:?}", atom);
+        // an atom line with a malformed number is an error, located at the number
+        let input = "C        7.210469000   10.1480x0000    0.813536200 CORE    1 C  C    0.0000    1";
+        let err = parser.parse_atom(input).unwrap_err();
+        assert!(matches!(&err, ParseError::BadFloat{ text, .. } if text == "10.1480x0000"), "Got {:?}", err);
+        assert_eq!(err.location().unwrap().column, 24);
     }
 
     #[test]
     fn test_parse_header_success() {
         let parser = ArcParser::new();
         let input = "   Energy         0          0.0099      -3620.679360        C1";
-        let block = parser.parse_header(input).unwrap();
+        let block = parser.parse_header(input).unwrap().unwrap();
         assert_eq!(block.number, 0, "Expected 0. Got {:?}", block.number);
         assert_eq!(block.energy, -3620.679360, "Expected -3620.679360. Got {:?}", block.energy);
         assert_eq!(block.symmetry, "C1", "Expected 'C1'. Got {:?}", block.symmetry);
@@ -383,7 +1276,7 @@ mod tests {
     fn test_parse_header_fail() {
         let parser = ArcParser::new();
         let input = "some_random_string";
-        let block = parser.parse_header(input);
+        let block = parser.parse_header(input).unwrap();
         assert!(block.is_none(), "Expected None. Got {:?}", block);
     }
 
@@ -391,7 +1284,7 @@ mod tests {
     fn test_parse_cell_success() {
         let parser = ArcParser::new();
         let input = "PBC   20.19500000   20.19500000   29.51410000   90.00000000   90.00000000  120.00000000";
-        let cell = parser.parse_cell(input).unwrap();
+        let cell = parser.parse_cell(input).unwrap().unwrap();
         assert_eq!(cell.x, 20.195, "Expected 20.195. Got {:?}", cell.x);
         assert_eq!(cell.y, 20.195, "Expected 20.195. Got {:?}", cell.y);
         assert_eq!(cell.z, 29.5141, "Expected 29.5141. Got {:?}", cell.z);
@@ -403,7 +1296,7 @@ mod tests {
     fn test_parse_cell_fail() {
         let parser = ArcParser::new();
         let input = "some_random_string";
-        let cell = parser.parse_cell(input);
+        let cell = parser.parse_cell(input).unwrap();
         assert!(cell.is_none(), "Expected None. Got {:?}", cell);
     }
 
@@ -425,6 +1318,7 @@ mod tests {
         assert_eq!(block.number, 0, "Expected 0. Got {:?}", block.number);
         assert_eq!(block.energy, -3620.679360, "Expected -3620.679360. Got {:?}", block.energy);
         assert_eq!(block.symmetry, "C1", "Expected 'C1'. Got {:?}", block.symmetry);
+        assert!(block.periodic);
         assert_eq!(block.atoms.len(), 1, "Expected 1. Got {:?}", block.atoms.len());
         let atom = &block.atoms[0];
         assert_eq!(atom.element, "C", "Expected 'C'. Got {:?}", atom.element);
@@ -447,7 +1341,7 @@ mod tests {
         let result = parser.parse_structure(path, false);
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::ParseError(_)));
+        assert!(matches!(err, ParseError::UnexpectedLine{ .. }));
         // an IO error
         let path = Path::new("non_existent_file.arc");
         let result = parser.parse_structure(path, false);
@@ -456,11 +1350,136 @@ mod tests {
         assert!(matches!(err, ParseError::IoError(_)));
     }
 
+    #[test]
+    fn test_iter_structure() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "!BIOSYM archive 2").unwrap();
+        writeln!(file, "PBC=ON").unwrap();
+        for i in 0..3 {
+            writeln!(file, "   Energy         {}          0.0099      -362{}.679360        C1", i, i).unwrap();
+            writeln!(file, "!DATE").unwrap();
+            writeln!(file, "PBC   20.19500000   20.19500000   29.51410000   90.00000000   90.00000000  120.00000000").unwrap();
+            writeln!(file, "C        7.210469000   10.148070000    0.813536200 CORE    1 C  C    0.0000    1").unwrap();
+            writeln!(file, "end").unwrap();
+            writeln!(file, "end").unwrap();
+        }
+        writeln!(file, "some_random_string").unwrap();
+        let parser = ArcParser::new();
+        // blocks are yielded in order, the illegal line after the last block comes first
+        let mut iter = parser.iter_structure(file.path(), false).unwrap();
+        assert_eq!(iter.next().unwrap().unwrap().number, 0);
+        assert_eq!(iter.next().unwrap().unwrap().number, 1);
+        let err = iter.next().unwrap().unwrap_err();
+        assert!(matches!(&err, ParseError::UnexpectedLine{ line, .. } if line == "some_random_string"));
+        assert_eq!(err.location().unwrap(), &Location::new(Some(file.path()), 21, 1));
+        let block = iter.next().unwrap().unwrap();
+        assert_eq!(block.number, 2);
+        assert_eq!(block.energy, -3622.67936);
+        assert_eq!(block.atoms.len(), 1);
+        assert!(iter.next().is_none());
+        // the illegal line is skipped when ignoring parse errors
+        let iter = parser.iter_structure(file.path(), true).unwrap();
+        assert_eq!(iter.map(|b| b.unwrap().number).collect::<Vec<u64>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_parse_structure_errors() {
+        let parse = |lines: &[&str]| {
+            let mut file = NamedTempFile::new().unwrap();
+            for line in lines {
+                writeln!(file, "{}", line).unwrap();
+            }
+            ArcParser::new().parse_structure(file.path(), false).unwrap_err()
+        };
+        let header = "   Energy         0          0.0099      -3620.679360        C1";
+        let cell = "PBC   20.19500000   20.19500000   29.51410000   90.00000000   90.00000000  120.00000000";
+        let atom = "C        7.210469000   10.148070000    0.813536200 CORE    1 C  C    0.0000    1";
+        // an atom before any header
+        let err = parse(&["!BIOSYM archive 2", "PBC=ON", atom]);
+        assert!(matches!(err, ParseError::AtomBeforeHeader{ location } if location.line == 3));
+        // a periodic block without cell, located at its header
+        let err = parse(&["!BIOSYM archive 2", "PBC=ON", header, "!DATE", atom, "end", "end"]);
+        assert!(matches!(err, ParseError::MissingCell{ location } if location.line == 3));
+        // the cell is not needed in a cluster file
+        let mut file = NamedTempFile::new().unwrap();
+        for line in ["!BIOSYM archive 2", "PBC=OFF", header, "!DATE", atom, "end", "end"] {
+            writeln!(file, "{}", line).unwrap();
+        }
+        let blocks = ArcParser::new().parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert!(!blocks[0].periodic);
+        // the last block is cut short
+        let err = parse(&["!BIOSYM archive 2", "PBC=ON", header, "!DATE", cell, atom, "end", "end", header, "!DATE", cell, atom]);
+        assert!(matches!(err, ParseError::TruncatedBlock{ location, expected: None, found: 1 } if location.line == 9));
+        // a malformed energy in a header
+        let err = parse(&["!BIOSYM archive 2", "PBC=ON", "   Energy         0          0.0099      -3620.6x9360        C1"]);
+        assert!(matches!(&err, ParseError::BadFloat{ location, .. } if location.line == 3 && location.column == 42), "Got {:?}", err);
+    }
+
+    #[test]
+    fn test_parse_parallel() {
+        // blocks of different sizes, so that the chunks are uneven
+        let write = |missing_cell: Option<usize>, missing_end: Option<usize>| {
+            let mut file = NamedTempFile::new().unwrap();
+            writeln!(file, "!BIOSYM archive 2").unwrap();
+            writeln!(file, "PBC=ON").unwrap();
+            for i in 0..10 {
+                writeln!(file, "   Energy         {}          0.0099      -362{}.679360        C1", i, i).unwrap();
+                writeln!(file, "!DATE").unwrap();
+                if missing_cell != Some(i) {
+                    writeln!(file, "PBC   20.19500000   20.19500000   29.51410000   90.00000000   90.00000000  120.00000000").unwrap();
+                }
+                for j in 0..(i % 4 + 1) {
+                    writeln!(file, "C        7.210469000   10.148070000    {:.9} CORE    1 C  C    0.0000    1", j as f64).unwrap();
+                }
+                if missing_end != Some(i) {
+                    writeln!(file, "end").unwrap();
+                    writeln!(file, "end").unwrap();
+                }
+            }
+            file
+        };
+        let file = write(None, None);
+        let parser = ArcParser::new();
+        let expected = parser.parse_structure(file.path(), false).unwrap().unwrap();
+        for threads in 1..=12 {
+            let blocks = parse_parallel(&parser, file.path(), false, threads).unwrap().unwrap();
+            assert_eq!(blocks.len(), expected.len());
+            for (block, expected) in blocks.iter().zip(expected.iter()) {
+                assert_eq!(block.number, expected.number);
+                assert_eq!(block.energy, expected.energy);
+                assert_eq!(block.crystal.x, expected.crystal.x);
+                assert_eq!(block.atoms.iter().map(|a| a.coordinate.2).collect::<Vec<f64>>(), expected.atoms.iter().map(|a| a.coordinate.2).collect::<Vec<f64>>());
+            }
+        }
+        // the periodicity declared at the top applies to every chunk, and errors keep their line
+        let file = write(Some(7), None);
+        let expected = parser.parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(expected, ParseError::MissingCell{ .. }), "Got {:?}", expected);
+        for threads in 2..=12 {
+            let err = parse_parallel(&parser, file.path(), false, threads).unwrap_err();
+            assert_eq!(err.location(), expected.location());
+            assert_eq!(parse_parallel(&parser, file.path(), true, threads).unwrap().unwrap().len(), 10);
+        }
+        // so do the end lines of the blocks before a chunk, whichever chunk starts at the truncated block
+        for truncated in 1..9 {
+            let file = write(None, Some(truncated));
+            let expected = parser.parse_structure(file.path(), false).unwrap_err();
+            assert!(matches!(expected, ParseError::TruncatedBlock{ .. }), "Got {:?}", expected);
+            for threads in 2..=12 {
+                let err = parse_parallel(&parser, file.path(), false, threads).unwrap_err();
+                assert_eq!(err.location(), expected.location(), "block {} threads {}", truncated, threads);
+                assert_eq!(parse_parallel(&parser, file.path(), true, threads).unwrap().unwrap().len(), 10);
+            }
+        }
+        assert_eq!(read_file_parallel(file.path().to_str().unwrap(), true, 4).unwrap().unwrap().len(), 10);
+    }
+
     #[test]
     fn test_parse_xyz_atom_success() {
         let parser = XyzParser::new();
         let input = "C        7.210469000   10.148070000    0.813536200";
-        let atom = parser.parse_atom(input).unwrap();
+        let atom = parser.parse_atom(input).unwrap().unwrap();
         assert_eq!(atom.element, "C");
         assert_eq!(atom.coordinate.0, 7.210469);
         assert_eq!(atom.coordinate.1, 10.14807);
@@ -470,7 +1489,7 @@ mod tests {
     fn test_parse_xyz_atom_fail() {
         let parser = XyzParser::new();
         let input = "some_random_string";
-        let atom = parser.parse_atom(input);
+        let atom = parser.parse_atom(input).unwrap();
         assert!(atom.is_none());
     }
 
@@ -478,8 +1497,9 @@ mod tests {
     fn test_parse_xyz_header_success() {
         let parser = XyzParser::new();
         let input = "5";
-        let block = parser.parse_header(input).unwrap();
-        assert_eq!(block.number, 5);
+        let block = parser.parse_header(input).unwrap().unwrap();
+        // the number is the position of the frame, set by parse_structure
+        assert_eq!(block.number, 0);
         assert_eq!(block.energy, 0.0);
         assert_eq!(block.symmetry, "C1");
     }
@@ -487,7 +1507,7 @@ mod tests {
     fn test_parse_xyz_header_fail() {
         let parser = XyzParser::new();
         let input = "some_random_string";
-        let block = parser.parse_header(input);
+        let block = parser.parse_header(input).unwrap();
         assert!(block.is_none());
     }
 
@@ -495,7 +1515,8 @@ mod tests {
     fn test_parse_xyz_structure_success() {
         // construct a temporary file for testing
         let mut file = NamedTempFile::new().unwrap();
-        writeln!(file, "5").unwrap();
+        writeln!(file, "1").unwrap();
+        writeln!(file, "a plain comment line").unwrap();
         writeln!(file, "C        7.210469000   10.148070000    0.813536200").unwrap();
         let parser = XyzParser::new();
         let path = file.path();
@@ -504,7 +1525,7 @@ mod tests {
         let blocks = result.unwrap().unwrap();
         assert_eq!(blocks.len(), 1);
         let block = &blocks[0];
-        assert_eq!(block.number, 5);
+        assert_eq!(block.number, 0);
         assert_eq!(block.energy, 0.0);
         assert_eq!(block.symmetry, "C1");
         assert_eq!(block.atoms.len(), 1);
@@ -529,7 +1550,14 @@ mod tests {
         let result = parser.parse_structure(path, false);
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::ParseError(_)));
+        assert!(matches!(err, ParseError::UnexpectedLine{ .. }));
+        // a frame cut short, located at its atom count line
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "3").unwrap();
+        writeln!(file, "energy=-1.0").unwrap();
+        writeln!(file, "C 0.0 0.0 0.0").unwrap();
+        let err = parser.parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::TruncatedBlock{ location, expected: Some(3), found: 1 } if location.line == 1));
         // an IO error
         let path = Path::new("non_existent_file.xyz");
         let result = parser.parse_structure(path, false);
@@ -538,6 +1566,126 @@ mod tests {
         assert!(matches!(err, ParseError::IoError(_)));
     }
     #[test]
+    fn test_parse_extxyz_structure_success() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "2").unwrap();
+        writeln!(file, "Lattice=\"0.0 4.0 0.0 -5.0 0.0 0.0 0.0 0.0 6.0\" Properties=species:S:1:pos:R:3:tags:I:1:forces:R:3 energy=-12.5 pbc=\"T T T\"").unwrap();
+        writeln!(file, "Fe  0.0 2.0 0.0  1  0.0 0.1 0.0").unwrap();
+        writeln!(file, "O   -1.0 0.0 3.0  0  0.2 0.0 -0.3").unwrap();
+        writeln!(file, "1").unwrap();
+        writeln!(file, "energy=-3.0").unwrap();
+        writeln!(file, "H   1.0 2.0 3.0").unwrap();
+        let parser = XyzParser::new();
+        let blocks = parser.parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 2);
+        let block = &blocks[0];
+        assert_eq!(block.number, 0);
+        assert_eq!(block.energy, -12.5);
+        assert!((block.crystal.x - 4.0).abs() < 1e-9);
+        assert!((block.crystal.y - 5.0).abs() < 1e-9);
+        assert!((block.crystal.gamma - 90.0).abs() < 1e-9);
+        // the cell is rotated so that a is along x
+        assert!((block.atoms[0].coordinate.0 - 2.0).abs() < 1e-9);
+        assert!(block.atoms[0].coordinate.1.abs() < 1e-9);
+        assert!((block.atoms[1].coordinate.1 - 1.0).abs() < 1e-9);
+        assert!((block.atoms[1].coordinate.2 - 3.0).abs() < 1e-9);
+        let forces = block.forces.as_ref().unwrap();
+        assert!((forces[0].0 - 0.1).abs() < 1e-9);
+        assert!((forces[1].1 + 0.2).abs() < 1e-9);
+        assert!((forces[1].2 + 0.3).abs() < 1e-9);
+        let block = &blocks[1];
+        assert_eq!(block.number, 1);
+        assert_eq!(block.energy, -3.0);
+        assert_eq!(block.crystal.x, 0.0);
+        assert!(block.forces.is_none());
+        assert_eq!(block.atoms[0].element, "H");
+    }
+
+    #[test]
+    fn test_write_extxyz_structure_success() {
+        let parser = XyzParser::new();
+        let file = NamedTempFile::new().unwrap();
+        let blocks = vec![
+            StructureBlock{
+                number: 0,
+                energy: -3620.67936,
+                symmetry: "C1".to_string(),
+                crystal: CrystalInfo{
+                    x: 20.195,
+                    y: 20.195,
+                    z: 29.5141,
+                    alpha: 90.0,
+                    beta: 90.0,
+                    gamma: 120.0
+                },
+                atoms: vec![
+                    Atom{
+                        element: "C".to_string(),
+                        coordinate: Coordinate(7.210469, 10.14807, 0.8135362),
+                        record: None,
+                        selective: None
+                    }
+                ],
+                periodic: true,
+                forces: Some(vec![Coordinate(0.5, -0.25, 1.0)]),
+                record: None,
+                stress: None
+            },
+            StructureBlock{
+                number: 1,
+                energy: -3620.5,
+                symmetry: "C1".to_string(),
+                crystal: CrystalInfo{
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                    alpha: 0.0,
+                    beta: 0.0,
+                    gamma: 0.0
+                },
+                atoms: vec![
+                    Atom{
+                        element: "O".to_string(),
+                        coordinate: Coordinate(1.0, 2.0, 3.0),
+                        record: None,
+                        selective: None
+                    }
+                ],
+                periodic: false,
+                forces: None,
+                record: None,
+                stress: None
+            }
+        ];
+        parser.write_structure(&blocks, file.path()).unwrap();
+        let read_blocks = parser.parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(read_blocks.len(), 2);
+        let read_block = &read_blocks[0];
+        assert_eq!(read_block.energy, -3620.67936);
+        assert!((read_block.crystal.x - 20.195).abs() < 1e-6);
+        assert!((read_block.crystal.z - 29.5141).abs() < 1e-6);
+        assert!((read_block.crystal.gamma - 120.0).abs() < 1e-6);
+        assert!((read_block.atoms[0].coordinate.0 - 7.210469).abs() < 1e-6);
+        assert!((read_block.atoms[0].coordinate.1 - 10.14807).abs() < 1e-6);
+        assert!((read_block.atoms[0].coordinate.2 - 0.8135362).abs() < 1e-6);
+        let forces = read_block.forces.as_ref().unwrap();
+        assert!((forces[0].0 - 0.5).abs() < 1e-6);
+        assert!((forces[0].1 + 0.25).abs() < 1e-6);
+        assert!(read_block.periodic);
+        let read_block = &read_blocks[1];
+        assert_eq!(read_block.energy, -3620.5);
+        assert_eq!(read_block.crystal.x, 0.0);
+        assert!(read_block.forces.is_none());
+        assert!(!read_block.periodic);
+        // a cluster in a box keeps the box, but not its periodicity
+        let mut cluster = blocks[0].clone();
+        cluster.periodic = false;
+        parser.write_structure(&[cluster], file.path()).unwrap();
+        let read_block = &parser.parse_structure(file.path(), false).unwrap().unwrap()[0];
+        assert!((read_block.crystal.x - 20.195).abs() < 1e-6);
+        assert!(!read_block.periodic);
+    }
+    #[test]
     fn test_write_arc_structure_success() {
         let parser = ArcParser::new();
         let file = NamedTempFile::new().unwrap();
@@ -557,9 +1705,15 @@ mod tests {
                 atoms: vec![
                     Atom{
                         element: "C".to_string(),
-                        coordinate: Coordinate(7.210469, 10.14807, 0.8135362)
+                        coordinate: Coordinate(7.210469, 10.14807, 0.8135362),
+                        record: None,
+                        selective: None
                     }
-                ]
+                ],
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
             }
         ];
         let path = file.path();
@@ -584,4 +1738,355 @@ mod tests {
         assert_eq!(read_atom.coordinate.1, 10.14807);
         assert_eq!(read_atom.coordinate.2, 0.8135362);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_compressed_structure() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let block = StructureBlock{
+            number: 0,
+            energy: -3620.679360,
+            symmetry: "C1".to_string(),
+            crystal: CrystalInfo{ x: 20.195, y: 20.195, z: 29.5141, alpha: 90.0, beta: 90.0, gamma: 120.0 },
+            atoms: vec![
+                Atom{
+                    element: "C".to_string(),
+                    coordinate: Coordinate(7.210469, 10.14807, 0.8135362),
+                    record: None,
+                    selective: None
+                }
+            ],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
+        };
+        for name in ["all.arc.gz", "all.xyz.xz", "all.arc.zst"] {
+            let path = dir.path().join(name);
+            assert_eq!(get_file_type(&path), Some(&name[4..7]));
+            // the inner format is picked by the file name, the compression is transparent
+            block.write(&path).unwrap();
+            assert_ne!(std::fs::read(&path).unwrap()[0], b'!');
+            let blocks = read_file(path.to_str().unwrap(), false).unwrap().unwrap();
+            assert_eq!(blocks.len(), 1);
+            assert_eq!(blocks[0].energy, -3620.679360);
+            assert!((blocks[0].atoms[0].coordinate.2 - 0.8135362).abs() < 1e-9);
+        }
+        assert_eq!(get_file_type(Path::new("POSCAR.gz")), Some("poscar"));
+        assert_eq!(get_file_type(Path::new("structure.gz")), None);
+    }
+
+    #[test]
+    fn test_detect_format() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let write = |name: &str, lines: &[&str]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, lines.join("\n")).unwrap();
+            path
+        };
+        let arc = write("all.arc.bak", &["!BIOSYM archive 2", "PBC=ON"]);
+        assert_eq!(detect_format(&arc).unwrap(), "arc");
+        let xyz = write("trajectory", &["1", "energy=-1.0", "C 0.0 0.0 0.0"]);
+        assert_eq!(detect_format(&xyz).unwrap(), "xyz");
+        let poscar = write("relaxed", &["Si", "1.0", "5.43 0.0 0.0", "0.0 5.43 0.0", "0.0 0.0 5.43", "Si", "1", "Direct", "0.0 0.0 0.0"]);
+        assert_eq!(detect_format(&poscar).unwrap(), "poscar");
+        let cif = write("structure.txt", &["# generated", "data_NaCl", "_cell_length_a 5.64"]);
+        assert_eq!(detect_format(&cif).unwrap(), "cif");
+        // the content wins over a wrong extension
+        let wrong = write("wrong.xyz", &["!BIOSYM archive 2"]);
+        assert_eq!(detect_format(&wrong).unwrap(), "arc");
+        // an inconclusive content falls back to the file name
+        let named = write("CONTCAR", &[""]);
+        assert_eq!(detect_format(&named).unwrap(), "poscar");
+        let unknown = write("notes", &["some_random_string"]);
+        assert!(matches!(detect_format(&unknown), Err(ParseError::UnknownFormat(_))));
+        // `.inp` inputs of CP2K and ORCA are told apart by their content only
+        let cp2k = write("geo.inp", &["&GLOBAL", "  PROJECT water", "&END GLOBAL"]);
+        assert_eq!(detect_format(&cp2k).unwrap(), "cp2k");
+        let orca = write("water.inp", &["! B3LYP def2-SVP Opt", "* xyz 0 1", "O 0.0 0.0 0.0", "*"]);
+        assert_eq!(detect_format(&orca).unwrap(), "orca");
+        let written = write("cell.inp", &["# Energy: -1.0", "&CELL", "  A [angstrom] 5.0 0.0 0.0"]);
+        assert_eq!(detect_format(&written).unwrap(), "cp2k");
+        let inp = write("empty.inp", &[""]);
+        assert!(matches!(detect_format(&inp), Err(ParseError::UnknownFormat(_))));
+        assert!(matches!(get_parser("inp"), Err(ParseError::UnknownFormat(_))));
+        assert!(matches!(read_file(unknown.to_str().unwrap(), true), Err(ParseError::UnknownFormat(_))));
+        assert!(matches!(get_parser("bak"), Err(ParseError::UnknownFormat(_))));
+        assert_eq!(read_file(arc.to_str().unwrap(), true).unwrap().map(|b| b.len()), None);
+        assert_eq!(read_file(poscar.to_str().unwrap(), false).unwrap().unwrap()[0].atoms.len(), 1);
+    }
+
+    #[test]
+    fn test_arc_round_trip_is_byte_stable() {
+        let content = [
+            "!BIOSYM archive 2",
+            "PBC=ON",
+            "                        Energy         7        0.1234        -1234.567891  P1",
+            "!DATE     Mon Oct  2 10:15:01 2023",
+            "PBC     10.0000000     11.0000000     12.0000000     90.0000000     90.0000000    120.0000000",
+            "Pt1        0.000000000    0.000000000    0.000000000 CORE    1 pt Pt   0.1250    1",
+            "O2         1.250000000    2.500000000    3.750000000 CORE    1 o  O   -0.8500    2",
+            "end",
+            "end",
+            "",
+        ].join("\n");
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("all.arc");
+        let output = dir.path().join("copy.arc");
+        std::fs::write(&input, &content).unwrap();
+        let parser = ArcParser::new();
+        let mut blocks = parser.parse_structure(&input, false).unwrap().unwrap();
+        let block = &blocks[0];
+        assert_eq!(block.number, 7);
+        assert_eq!(block.record.as_ref().unwrap().second, 0.1234);
+        let record = block.atoms[1].record.as_ref().unwrap();
+        assert_eq!(block.atoms[1].element, "O");
+        assert_eq!((record.label.as_str(), record.ff_type.as_str(), record.charge, record.index.as_str()), ("O2", "o", -0.85, "2"));
+        parser.write_structure(&blocks, &output).unwrap();
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), content);
+        // a moved atom is formatted again, keeping its columns
+        blocks[0].atoms[1].coordinate.2 = 4.0;
+        blocks[0].energy = -1234.5;
+        parser.write_structure(&blocks, &output).unwrap();
+        let written = std::fs::read_to_string(&output).unwrap();
+        let lines = written.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[3], "!DATE     Mon Oct  2 10:15:01 2023");
+        assert_eq!(lines[5], "Pt1        0.000000000    0.000000000    0.000000000 CORE    1 pt Pt   0.1250    1");
+        let block = &parser.parse_structure(&output, false).unwrap().unwrap()[0];
+        assert_eq!((block.number, block.energy, block.record.as_ref().unwrap().second), (7, -1234.5, 0.1234));
+        let record = block.atoms[1].record.as_ref().unwrap();
+        assert_eq!(block.atoms[1].coordinate.2, 4.0);
+        assert_eq!((record.label.as_str(), record.ff_type.as_str(), record.charge, record.index.as_str()), ("O2", "o", -0.85, "2"));
+    }
+
+    /// the regexes `ArcParser` matched every line against before its tokenizer
+    struct RegexArcParser {
+        atom: Regex,
+        header: Regex,
+        cell: Regex,
+        arc: ArcParser,
+    }
+    impl RegexArcParser {
+        fn new() -> Self {
+            Self {
+                atom: Regex::new(r"^(?P<s>\w+)\s+(?P<f1>\S+)\s+(?P<f2>\S+)\s+(?P<f3>\S+)\s+CORE\s+(?P<tail>.*)").unwrap(),
+                header: Regex::new(r"^\s+Energy\s+(?P<number>\S+)\s+(?P<f1>\S+)\s+(?P<energy>\S+)\s*(?P<symmetry>.*)$").unwrap(),
+                cell: Regex::new(r"^PBC\s+(?P<x>\S+)\s+(?P<y>\S+)\s+(?P<z>\S+)\s+(?P<alpha>\S+)\s+(?P<beta>\S+)\s+(?P<gamma>\S+)").unwrap(),
+                arc: ArcParser::new(),
+            }
+        }
+        fn float(caps: &regex::Captures, name: &str) -> Result<f64, ParseError> {
+            let capture = caps.name(name).unwrap();
+            parse_float(capture.as_str(), capture.start() + 1)
+        }
+    }
+    impl StructureIO for RegexArcParser {
+        fn parse_atom(&self, input: &str) -> Result<Option<Atom>, ParseError> {
+            let Some(caps) = self.atom.captures(input) else { return Ok(None) };
+            let coordinate = Coordinate(Self::float(&caps, "f1")?, Self::float(&caps, "f2")?, Self::float(&caps, "f3")?);
+            let tail = caps.name("tail").unwrap();
+            let columns = split_columns(tail.as_str());
+            let element = match columns.as_slice() {
+                [_, _, (_, element), (column, charge), _, ..] => {
+                    parse_float(charge, tail.start() + column)?;
+                    element.to_string()
+                },
+                _ => caps.name("s").unwrap().as_str().to_string(),
+            };
+            Ok(Some(Atom{ element, coordinate, record: None, selective: None }))
+        }
+        fn parse_cell(&self, input: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            let Some(caps) = self.cell.captures(input) else { return Ok(None) };
+            Ok(Some(CrystalInfo{
+                x: Self::float(&caps, "x")?,
+                y: Self::float(&caps, "y")?,
+                z: Self::float(&caps, "z")?,
+                alpha: Self::float(&caps, "alpha")?,
+                beta: Self::float(&caps, "beta")?,
+                gamma: Self::float(&caps, "gamma")?,
+            }))
+        }
+        fn parse_header(&self, input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            let Some(caps) = self.header.captures(input) else { return Ok(None) };
+            let number = caps.name("number").unwrap();
+            let number = number.as_str().parse::<u64>().map_err(|_| ParseError::BadFloat{ location: Location::new(None, 0, number.start() + 1), text: number.as_str().to_string() })?;
+            Self::float(&caps, "f1")?;
+            let energy = Self::float(&caps, "energy")?;
+            let symmetry = caps.name("symmetry").unwrap().as_str().trim();
+            let mut block = self.arc.parse_header("   Energy 0 0.0 0.0").unwrap().unwrap();
+            block.number = number;
+            block.energy = energy;
+            block.symmetry = if symmetry.is_empty() { String::from("C1") } else { symmetry.to_string() };
+            Ok(Some(block))
+        }
+        fn parse_periodicity(&self, input: &str) -> Option<bool> {
+            self.arc.parse_periodicity(input)
+        }
+        fn is_block_end(&self, input: &str) -> bool {
+            self.arc.is_block_end(input)
+        }
+        fn is_illegal(&self, input: &str) -> bool {
+            input.is_empty() || (matches!(self.parse_atom(input), Ok(None)) && matches!(self.parse_header(input), Ok(None)) && matches!(self.parse_cell(input), Ok(None)) && input.trim() != "end" && !input.starts_with("!DATE") && !input.contains("!BIOSYM archive") && !input.contains("PBC="))
+        }
+        /// only used to compare the parsing
+        fn write_structure(&self, _blocks: &[StructureBlock], _output: &Path) -> std::io::Result<()> {
+            Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "the regex .arc parser can not write structures"))
+        }
+    }
+
+    #[test]
+    fn test_arc_tokenizer_matches_regex() {
+        let legacy = RegexArcParser::new();
+        let parser = ArcParser::new();
+        let location = |e: &ParseError| e.location().map(|l| l.column);
+        let atoms = [
+            "C        7.210469000   10.148070000    0.813536200 CORE    1 C  C    0.0000    1",
+            "C\t1.0\t2.0\t3.0\tCORE\t1 C Fe 0.5 1",
+            "C 1.0 2.0 3.0 CORE ",
+            "C 1.0 2.0 3.0 CORE",
+            " C 1.0 2.0 3.0 CORE 1",
+            "C-1 1.0 2.0 3.0 CORE 1",
+            "Cé 1.0 2.0 3.0 CORE 1 x Fe 0.0 1",
+            "C 1.0 2.0 3.0 CORES 1",
+            "C 1.0 2.x 3.0 CORE 1",
+            "C 1.0 2.0 3.0 CORE 1 C C 0.x 1",
+            "PBC   20.19500000   20.19500000   29.51410000   90.00000000   90.00000000  120.00000000",
+            "end",
+        ];
+        for line in atoms {
+            match (legacy.parse_atom(line), parser.parse_atom(line)) {
+                (Ok(None), Ok(None)) => (),
+                (Ok(Some(a)), Ok(Some(b))) => assert_eq!(format!("{:?}", a), format!("{:?}", b), "{}", line),
+                (Err(a), Err(b)) => assert_eq!(location(&a), location(&b), "{}", line),
+                (a, b) => panic!("{}: {:?} and {:?}", line, a, b),
+            }
+        }
+        let headers = [
+            "   Energy         0          0.0099      -3620.679360        C1",
+            "   Energy 0 0.0 -1.0",
+            "\tEnergy\t1\t0.0\t-1.0\tP 1 21 1  ",
+            "Energy 0 0.0 -1.0 C1",
+            "   Energy 0 0.0",
+            "   Energies 0 0.0 -1.0",
+            "   Energy x 0.0 -1.0",
+            "   Energy 0 0.0 -1.x C1",
+        ];
+        for line in headers {
+            match (legacy.parse_header(line), parser.parse_header(line)) {
+                (Ok(None), Ok(None)) => (),
+                (Ok(Some(a)), Ok(Some(b))) => assert_eq!((a.number, a.energy, a.symmetry), (b.number, b.energy, b.symmetry), "{}", line),
+                (Err(a), Err(b)) => assert_eq!(location(&a), location(&b), "{}", line),
+                (a, b) => panic!("{}: {:?} and {:?}", line, a.map(|b| b.is_some()), b.map(|b| b.is_some())),
+            }
+        }
+        let cells = ["PBC 1 2 3 90 90 90", "PBC\t1 2 3 90 90 120 extra", "PBC=ON", "PBC 1 2 3 90 90", " PBC 1 2 3 90 90 90", "PBC 1 2 3 9x 90 90"];
+        for line in cells {
+            match (legacy.parse_cell(line), parser.parse_cell(line)) {
+                (Ok(None), Ok(None)) => (),
+                (Ok(Some(a)), Ok(Some(b))) => assert_eq!((a.x, a.gamma), (b.x, b.gamma), "{}", line),
+                (Err(a), Err(b)) => assert_eq!(location(&a), location(&b), "{}", line),
+                (a, b) => panic!("{}: {:?} and {:?}", line, a.map(|c| c.is_some()), b.map(|c| c.is_some())),
+            }
+        }
+    }
+
+    /// counts the atom, header and cell lines `ArcParser` is asked to parse, and the lines checked by `is_illegal`
+    #[derive(Default)]
+    struct CountingArcParser {
+        arc: ArcParser,
+        tries: std::cell::Cell<usize>,
+        illegal: std::cell::Cell<usize>,
+    }
+    impl StructureIO for CountingArcParser {
+        fn parse_atom(&self, input: &str) -> Result<Option<Atom>, ParseError> {
+            self.tries.set(self.tries.get() + 1);
+            self.arc.parse_atom(input)
+        }
+        fn parse_cell(&self, input: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            self.tries.set(self.tries.get() + 1);
+            self.arc.parse_cell(input)
+        }
+        fn parse_header(&self, input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            self.tries.set(self.tries.get() + 1);
+            self.arc.parse_header(input)
+        }
+        fn parse_periodicity(&self, input: &str) -> Option<bool> {
+            self.arc.parse_periodicity(input)
+        }
+        fn is_block_end(&self, input: &str) -> bool {
+            self.arc.is_block_end(input)
+        }
+        fn is_header_record(&self, input: &str) -> bool {
+            self.arc.is_header_record(input)
+        }
+        fn record_line(&self, block: &mut StructureBlock, input: &str) {
+            self.arc.record_line(block, input)
+        }
+        fn is_illegal(&self, input: &str) -> bool {
+            self.illegal.set(self.illegal.get() + 1);
+            self.arc.is_illegal(input)
+        }
+        fn write_structure(&self, blocks: &[StructureBlock], output: &Path) -> std::io::Result<()> {
+            self.arc.write_structure(blocks, output)
+        }
+    }
+
+    #[test]
+    fn test_arc_line_states() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "!BIOSYM archive 2").unwrap();
+        writeln!(file, "PBC=ON").unwrap();
+        for i in 0..3 {
+            writeln!(file, "   Energy         {}          0.0099      -3620.679360        C1", i).unwrap();
+            writeln!(file, "!DATE     Oct 17 10:00:00 2026").unwrap();
+            writeln!(file, "PBC   20.19500000   20.19500000   29.51410000   90.00000000   90.00000000  120.00000000").unwrap();
+            writeln!(file, "C        7.210469000   10.148070000    0.813536200 CORE    1 C  C    0.0000    1").unwrap();
+            writeln!(file, "O        7.210469000   10.148070000    1.813536200 CORE    2 O  O    0.0000    2").unwrap();
+            writeln!(file, "end").unwrap();
+            writeln!(file, "end").unwrap();
+        }
+        let parser = CountingArcParser::default();
+        let blocks = parser.parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[2].atoms.len(), 2);
+        assert!(blocks[2].record.as_ref().unwrap().date.as_deref().unwrap().starts_with("!DATE"));
+        // the lines of the blocks are parsed at most once, as the kind the block expects, and `!DATE` lines not at all.
+        // Only the first line is tried as every kind and then checked by `is_illegal`
+        assert_eq!(parser.tries.get(), 3 + 1 + 3 * 6);
+        assert_eq!(parser.illegal.get(), 1);
+    }
+
+    /// throughput of the tokenizer against the regexes it replaced, on a generated file of 1.3 million lines
+    ///
+    /// run with `cargo test --release bench_arc_tokenizer -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_arc_tokenizer() {
+        let mut file = NamedTempFile::new().unwrap();
+        {
+            let mut writer = std::io::BufWriter::new(file.as_file_mut());
+            writeln!(writer, "!BIOSYM archive 2").unwrap();
+            writeln!(writer, "PBC=ON").unwrap();
+            for i in 0..20000 {
+                writeln!(writer, "   Energy         {}          0.0099      -3620.679360        C1", i).unwrap();
+                writeln!(writer, "!DATE").unwrap();
+                writeln!(writer, "PBC   20.19500000   20.19500000   29.51410000   90.00000000   90.00000000  120.00000000").unwrap();
+                for j in 0..60 {
+                    writeln!(writer, "Fe       7.210469000   10.148070000    {: >11.9} CORE {: >4} Fe Fe   0.0000 {: >4}", j as f64 * 0.1, j + 1, j + 1).unwrap();
+                }
+                writeln!(writer, "end").unwrap();
+                writeln!(writer, "end").unwrap();
+            }
+        }
+        let lines = 2 + 20000 * 65;
+        let time = |parser: &dyn StructureIO| {
+            let start = std::time::Instant::now();
+            let blocks = parser.parse_structure(file.path(), false).unwrap().unwrap();
+            assert_eq!(blocks.len(), 20000);
+            start.elapsed().as_secs_f64()
+        };
+        let regex = time(&RegexArcParser::new());
+        let tokenizer = time(&ArcParser::new());
+        println!("regex: {:.0} lines/s, tokenizer: {:.0} lines/s, {:.1}x", lines as f64 / regex, lines as f64 / tokenizer, regex / tokenizer);
+        assert!(tokenizer < regex);
+    }
+}
@@ -0,0 +1,259 @@
+pub mod pdb_parser {
+    //! reader and writer of Protein Data Bank files, for visualization with PyMOL or VMD
+    //!
+    //! PDB is a fixed column format. The cell is the `CRYST1` record, in the same orientation as `CrystalInfo`,
+    //! and the atoms are `ATOM`/`HETATM` records. Several structures are written as `MODEL`/`ENDMDL` frames,
+    //! which viewers play as an animation
+    use std::io::{self, BufRead, Write};
+    use std::path::Path;
+
+    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::compression::compression::{open_reader, CompressedWriter};
+    use crate::parser::parser::{StructureIO, StructureIterator, ParseError, Location, parse_float};
+
+    /// the text in the 1-based columns `start..=end` of a line, trimmed
+    fn field(line: &str, start: usize, end: usize) -> Option<(usize, &str)> {
+        let text = line.get(start - 1..end.min(line.len()))?.trim();
+        (!text.is_empty()).then_some((start, text))
+    }
+
+    /// the element of an atom record, from the element columns or else from the atom name
+    fn element_of(line: &str) -> Option<String> {
+        let symbol = match field(line, 77, 78) {
+            Some((_, symbol)) => symbol.to_string(),
+            None => field(line, 13, 16)?.1.chars().take_while(|c| c.is_ascii_alphabetic()).collect(),
+        };
+        let mut chars = symbol.chars();
+        let first = chars.next()?;
+        Some(first.to_ascii_uppercase().to_string() + &chars.as_str().to_ascii_lowercase())
+    }
+
+    /// the energy of a `REMARK   1 Energy: <value>` record
+    fn energy_of(line: &str) -> Option<f64> {
+        line.split("Energy:").nth(1)?.split_whitespace().next()?.parse::<f64>().ok()
+    }
+
+    /**
+    parser for PDB files
+
+    models are structures numbered by their serial, atoms outside of models form a single structure.
+    A `CRYST1` record applies to the following structures until the next one. The energy is kept in a
+    `REMARK   1 Energy: <value>` record before each model. Structures without cell are written without `CRYST1`.
+     */
+    pub struct PdbParser {}
+    impl PdbParser {
+        pub fn new() -> Self {
+            Self {}
+        }
+
+        /// parse an `ATOM`/`HETATM` record, `path` and `line_number` are only used to locate errors
+        fn parse_record(&self, line: &str, path: Option<&Path>, line_number: usize) -> Result<Atom, ParseError> {
+            let unexpected = || ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line: line.to_string() };
+            let mut position = [0.0; 3];
+            for (i, start) in [31, 39, 47].into_iter().enumerate() {
+                let (column, text) = field(line, start, start + 7).ok_or_else(unexpected)?;
+                position[i] = parse_float(text, column).map_err(|e| e.at(path, line_number))?;
+            }
+            Ok(Atom{
+                element: element_of(line).ok_or_else(unexpected)?,
+                coordinate: Coordinate(position[0], position[1], position[2]),
+                record: None,
+                selective: None
+            })
+        }
+
+        /// parse a `CRYST1` record
+        fn parse_cryst1(&self, line: &str, path: Option<&Path>, line_number: usize) -> Result<CrystalInfo, ParseError> {
+            let mut values = [0.0; 6];
+            for (i, (start, end)) in [(7, 15), (16, 24), (25, 33), (34, 40), (41, 47), (48, 54)].into_iter().enumerate() {
+                let (column, text) = field(line, start, end).ok_or_else(|| ParseError::UnexpectedLine{ location: Location::new(path, line_number, 1), line: line.to_string() })?;
+                values[i] = parse_float(text, column).map_err(|e| e.at(path, line_number))?;
+            }
+            let [x, y, z, alpha, beta, gamma] = values;
+            Ok(CrystalInfo{ x, y, z, alpha, beta, gamma })
+        }
+    }
+    impl Default for PdbParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl StructureIO for PdbParser {
+        // the cell and energy records come before the model they belong to, everything is handled in `iter_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            let mut lines = open_reader(input).map_err(ParseError::IoError)?.lines().enumerate().peekable();
+            let path = input.to_path_buf();
+            let mut crystal = CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 };
+            let mut energy = 0.0;
+            let mut count = 0;
+            Ok(StructureIterator::new(std::iter::from_fn(move || {
+                let mut current: Option<StructureBlock> = None;
+                loop {
+                    // a model ends before the next one, even without ENDMDL
+                    if current.is_some() && lines.peek().is_some_and(|(_, l)| l.as_ref().is_ok_and(|l| l.starts_with("MODEL"))) {
+                        return current.map(Ok);
+                    }
+                    let (index, line) = match lines.next() {
+                        Some((index, Ok(line))) => (index, line),
+                        Some((_, Err(e))) => return Some(Err(ParseError::IoError(e))),
+                        None => return current.map(Ok),
+                    };
+                    let record = line.get(..6).unwrap_or(&line).trim_end();
+                    let block = |number: u64| StructureBlock{
+                        number,
+                        energy,
+                        symmetry: String::from("C1"),
+                        crystal: crystal.clone(),
+                        atoms: Vec::new(),
+                        periodic: crystal.lattice().is_some(),
+                        forces: None,
+                        record: None,
+                        stress: None
+                    };
+                    match record {
+                        "MODEL" => {
+                            count += 1;
+                            let number = field(&line, 11, 14).and_then(|(_, n)| n.parse::<u64>().ok()).unwrap_or(count);
+                            current = Some(block(number));
+                        },
+                        "ATOM" | "HETATM" => match self.parse_record(&line, Some(&path), index + 1) {
+                            Ok(atom) => current.get_or_insert_with(|| { count += 1; block(count - 1) }).atoms.push(atom),
+                            Err(_) if ignore_parse_error => (),
+                            Err(e) => return Some(Err(e)),
+                        },
+                        "CRYST1" => match self.parse_cryst1(&line, Some(&path), index + 1) {
+                            Ok(cell) => {
+                                if let Some(block) = current.as_mut() {
+                                    block.crystal = cell.clone();
+                                }
+                                crystal = cell;
+                            },
+                            Err(_) if ignore_parse_error => (),
+                            Err(e) => return Some(Err(e)),
+                        },
+                        "REMARK" => if let Some(value) = energy_of(&line) {
+                            if let Some(block) = current.as_mut() {
+                                block.energy = value;
+                            }
+                            energy = value;
+                        },
+                        "ENDMDL" | "END" if current.is_some() => return current.map(Ok),
+                        // titles, bonds, TER and the other records are not needed
+                        _ => (),
+                    }
+                }
+            })))
+        }
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let mut file = CompressedWriter::create(path)?;
+            // a single structure is written without MODEL, which some programs do not expect
+            let models = structures.len() > 1;
+            for (i, block) in structures.iter().enumerate() {
+                writeln!(file, "REMARK   1 Energy: {}", block.energy)?;
+                let crystal = &block.crystal;
+                if crystal.lattice_vectors().determinant().abs() > 1e-8 {
+                    writeln!(file, "CRYST1{:9.3}{:9.3}{:9.3}{:7.2}{:7.2}{:7.2} P 1           1", crystal.x, crystal.y, crystal.z, crystal.alpha, crystal.beta, crystal.gamma)?;
+                }
+                if models {
+                    writeln!(file, "MODEL     {:4}", i + 1)?;
+                }
+                for (serial, atom) in block.atoms.iter().enumerate() {
+                    let element = atom.element.to_uppercase();
+                    // one letter elements start in the second column of the name
+                    let name = if element.len() == 1 { format!(" {:<3}", element) } else { format!("{:<4}", element) };
+                    // every atom is in residue 1 of chain A, with occupancy 1 and temperature factor 0
+                    writeln!(file, "HETATM{:5} {} MOL A   1    {:8.3}{:8.3}{:8.3}  1.00  0.00          {:>2}",
+                        (serial + 1) % 100000, name, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2, element)?;
+                }
+                if models {
+                    writeln!(file, "ENDMDL")?;
+                }
+            }
+            writeln!(file, "END")?;
+            file.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::{NamedTempFile, TempDir};
+
+    use crate::pdb::pdb_parser::*;
+    use crate::parser::parser::*;
+    use crate::modules::structures::*;
+
+    #[test]
+    fn test_parse_pdb() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "HEADER    water").unwrap();
+        writeln!(file, "CRYST1   10.000   10.000   12.000  90.00  90.00 120.00 P 1           1").unwrap();
+        writeln!(file, "ATOM      1  O   HOH A   1       1.000   2.000   3.000  1.00  0.00           O").unwrap();
+        writeln!(file, "HETATM    2 FE1  MOL A   1      -1.500   0.000   0.250  1.00  0.00").unwrap();
+        writeln!(file, "TER").unwrap();
+        writeln!(file, "END").unwrap();
+        assert_eq!(detect_format(file.path()).unwrap(), "pdb");
+        let blocks = PdbParser::new().parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].crystal.gamma, 120.0);
+        assert_eq!(blocks[0].crystal.z, 12.0);
+        assert_eq!(blocks[0].atoms[0].element, "O");
+        assert_eq!(blocks[0].atoms[1].element, "Fe");
+        assert_eq!(blocks[0].atoms[1].coordinate.0, -1.5);
+        assert_eq!(blocks[0].atoms[0].coordinate.2, 3.0);
+    }
+
+    #[test]
+    fn test_parse_pdb_fail() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "ATOM      1  O   HOH A   1       1.000   2.x00   3.000  1.00  0.00           O").unwrap();
+        writeln!(file, "ATOM      2  O   HOH A   1       1.000").unwrap();
+        let err = PdbParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::BadFloat{ ref location, .. } if location.line == 1 && location.column == 39), "Got {:?}", err);
+        let blocks = PdbParser::new().parse_structure(file.path(), true).unwrap();
+        assert!(blocks.is_none());
+    }
+
+    #[test]
+    fn test_pdb_models_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let block = |energy: f64, x: f64| StructureBlock{
+            number: 0,
+            energy,
+            symmetry: "C1".to_string(),
+            crystal: CrystalInfo{ x: 8.0, y: 8.0, z: 10.0, alpha: 90.0, beta: 90.0, gamma: 90.0 },
+            atoms: vec![
+                Atom{ element: "Pt".to_string(), coordinate: Coordinate(x, 0.0, 0.0), record: None, selective: None },
+                Atom{ element: "O".to_string(), coordinate: Coordinate(0.0, 1.25, 2.0), record: None, selective: None },
+            ],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
+        };
+        let path = dir.path().join("traj.pdb");
+        PdbParser::new().write_structure(&[block(-10.5, 1.0), block(-11.0, 1.5)], &path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("CRYST1    8.000    8.000   10.000  90.00  90.00  90.00 P 1           1\nMODEL        1\n"));
+        assert!(content.contains("HETATM    1 PT   MOL A   1       1.000   0.000   0.000  1.00  0.00          PT\n"));
+        assert!(content.contains("HETATM    2  O   MOL A   1       0.000   1.250   2.000  1.00  0.00           O\n"));
+        let blocks = PdbParser::new().parse_structure(&path, false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].number, 2);
+        assert_eq!(blocks[1].energy, -11.0);
+        assert_eq!(blocks[1].atoms[0].element, "Pt");
+        assert_eq!(blocks[1].atoms[0].coordinate.0, 1.5);
+        assert_eq!(blocks[1].crystal.z, 10.0);
+    }
+}
@@ -0,0 +1,354 @@
+pub mod espresso {
+    //! Quantum ESPRESSO input cards and the final geometry of pw.x output
+    //!
+    //! structures are written as the `ATOMIC_SPECIES`, `CELL_PARAMETERS` and `ATOMIC_POSITIONS` cards of a pw.x
+    //! input, to be completed with the namelists. The same cards are read back from inputs, and from pw.x
+    //! outputs where the last geometry of a relaxation is taken
+    use std::io::{self, BufRead, Write};
+    use std::path::Path;
+    use nalgebra::{Matrix3, RowVector3};
+
+    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::modules::periodic_table::PeriodicTable;
+    use crate::compression::compression::{open_reader, CompressedWriter};
+    use crate::parser::parser::{StructureIO, StructureIterator, ParseError, Location, split_columns, parse_float};
+
+    /// the Bohr radius in angstrom
+    pub const BOHR: f64 = 0.529177210903;
+    /// the Rydberg energy in eV
+    pub const RYDBERG: f64 = 13.605693122994;
+
+    /// the unit of a card, from its header such as `ATOMIC_POSITIONS (angstrom)` or `CELL_PARAMETERS (alat= 10.2)`
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Unit {
+        Angstrom,
+        Bohr,
+        /// in units of the lattice parameter, in bohr if it is given in the header
+        Alat(Option<f64>),
+        Crystal,
+    }
+    impl Unit {
+        fn from_header(header: &str) -> Option<Self> {
+            let options = header.split_once(char::is_whitespace).map_or("", |(_, o)| o).trim_matches([' ', '(', ')', '{', '}']).to_lowercase();
+            match options.as_str() {
+                "angstrom" => Some(Unit::Angstrom),
+                "bohr" => Some(Unit::Bohr),
+                "crystal" => Some(Unit::Crystal),
+                // QE takes alat when no unit is given
+                "" | "alat" => Some(Unit::Alat(None)),
+                other => other.strip_prefix("alat=").and_then(|v| v.trim().parse::<f64>().ok()).map(|v| Unit::Alat(Some(v))),
+            }
+        }
+    }
+
+    /// species labels and positions of an `ATOMIC_POSITIONS` card, with its unit and line
+    type Positions = (Vec<(String, RowVector3<f64>)>, Unit, usize);
+
+    /**
+    parser for pw.x inputs and outputs
+
+    a file gives a single structure: the last cell and positions printed, in angstrom. The lattice parameter is
+    taken from `celldm(1)` of inputs or from `lattice parameter (alat)` of outputs, and outputs without
+    `CELL_PARAMETERS` use their `crystal axes`. The energy is the last `!    total energy`, converted to eV.
+    Like POSCAR files, the cell is rotated into the standard orientation of `CrystalInfo`.
+     */
+    pub struct EspressoParser {
+        table: PeriodicTable,
+    }
+    impl EspressoParser {
+        pub fn new() -> Self {
+            Self { table: PeriodicTable::new() }
+        }
+
+        /// parse the lines of a pw.x input or output, `path` is only used to locate parse errors
+        fn parse_lines(&self, lines: &[String], path: Option<&Path>) -> Result<StructureBlock, ParseError> {
+            let mut alat = None;
+            let mut energy = 0.0;
+            let mut cell: Option<(Matrix3<f64>, Unit)> = None;
+            let mut positions: Option<Positions> = None;
+            let mut index = 0;
+            // the three numbers of a line, after `skip` columns
+            let vector = |index: usize, skip: usize| -> Result<Option<RowVector3<f64>>, ParseError> {
+                let columns = split_columns(lines[index].trim_end_matches(|c: char| c == ')' || c.is_whitespace()));
+                let values = columns.iter().skip(skip).take(3)
+                    .map(|(column, text)| parse_float(text.trim_start_matches('('), *column).map_err(|e| e.at(path, index + 1)))
+                    .collect::<Result<Vec<f64>, ParseError>>()?;
+                Ok((values.len() == 3).then(|| RowVector3::new(values[0], values[1], values[2])))
+            };
+            while index < lines.len() {
+                let line = lines[index].trim();
+                let header = line.split_whitespace().next().unwrap_or_default();
+                // namelists may hold several assignments on a line
+                let lower = line.to_lowercase();
+                let assignment = ["lattice parameter (alat)", "celldm(1)"].iter().find_map(|key| lower.find(key).map(|i| &line[i + key.len()..]));
+                if let Some(assignment) = assignment {
+                    let value = assignment.trim_start().strip_prefix('=')
+                        .and_then(|v| v.split([',', ' ']).find(|s| !s.is_empty()))
+                        .and_then(|v| v.replace(['d', 'D'], "e").parse::<f64>().ok());
+                    alat = value.or(alat);
+                }
+                else if line.starts_with('!') && line.contains("total energy") {
+                    let value = line.split('=').nth(1).and_then(|v| v.split_whitespace().next());
+                    if let Some(value) = value {
+                        energy = parse_float(value, 1).map_err(|e| e.at(path, index + 1))? * RYDBERG;
+                    }
+                }
+                else if line.starts_with('!') && line.contains("Energy:") {
+                    energy = line.split("Energy:").nth(1).and_then(|v| v.split_whitespace().next()).and_then(|v| v.parse::<f64>().ok()).unwrap_or(energy);
+                }
+                else if line.starts_with("crystal axes:") || header.eq_ignore_ascii_case("CELL_PARAMETERS") {
+                    let unit = match header.eq_ignore_ascii_case("CELL_PARAMETERS") {
+                        true => Unit::from_header(line).ok_or_else(|| ParseError::UnexpectedLine{ location: Location::new(path, index + 1, 1), line: lines[index].clone() })?,
+                        false => Unit::Alat(None),
+                    };
+                    // `a(1) = ( 1.0 0.0 0.0 )` in outputs, three numbers in cards
+                    let skip = if line.starts_with("crystal axes:") { 3 } else { 0 };
+                    let mut matrix = Matrix3::zeros();
+                    for i in 0..3 {
+                        let row_index = index + 1 + i;
+                        let row = match lines.get(row_index) {
+                            Some(_) => vector(row_index, skip)?,
+                            None => None,
+                        };
+                        let row = row.ok_or_else(|| ParseError::MissingCell{ location: Location::new(path, index + 1, 0) })?;
+                        matrix.set_row(i, &row);
+                    }
+                    cell = Some((matrix, unit));
+                    index += 3;
+                }
+                else if header.eq_ignore_ascii_case("ATOMIC_POSITIONS") {
+                    let unit = Unit::from_header(line).ok_or_else(|| ParseError::UnexpectedLine{ location: Location::new(path, index + 1, 1), line: lines[index].clone() })?;
+                    let start = index + 1;
+                    let mut atoms = Vec::new();
+                    // the card ends at the first line that is not an atom, fixed coordinates may follow the position
+                    while let Some(next) = lines.get(index + 1) {
+                        let columns = split_columns(next);
+                        match columns.first() {
+                            Some((_, label)) if columns.len() >= 4 && label.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                                let position = vector(index + 1, 1)?.unwrap();
+                                atoms.push((label.to_string(), position));
+                                index += 1;
+                            },
+                            _ => break,
+                        }
+                    }
+                    positions = Some((atoms, unit, start));
+                }
+                else if line.contains("positions (alat units)") {
+                    // `1  Si  tau(   1) = (   0.0000000   0.0000000   0.0000000  )` printed at the start of outputs
+                    let start = index + 1;
+                    let mut atoms = Vec::new();
+                    while let Some(next) = lines.get(index + 1).filter(|l| l.contains("tau(")) {
+                        let label = next.split_whitespace().nth(1).unwrap_or_default().to_string();
+                        let (_, values) = next.split_once('=').unwrap_or_default();
+                        let offset = next.len() - values.len();
+                        let columns = split_columns(values.trim_end_matches(|c: char| c == ')' || c.is_whitespace()).trim_start_matches([' ', '(']));
+                        let shift = values.len() - values.trim_start_matches([' ', '(']).len();
+                        let values = columns.iter().take(3)
+                            .map(|(column, text)| parse_float(text, offset + shift + column).map_err(|e| e.at(path, index + 2)))
+                            .collect::<Result<Vec<f64>, ParseError>>()?;
+                        if values.len() < 3 {
+                            return Err(ParseError::UnexpectedLine{ location: Location::new(path, index + 2, 1), line: next.clone() });
+                        }
+                        atoms.push((label, RowVector3::new(values[0], values[1], values[2])));
+                        index += 1;
+                    }
+                    positions = Some((atoms, Unit::Alat(None), start));
+                }
+                index += 1;
+            }
+            let (atoms, unit, line) = positions.ok_or(ParseError::TruncatedBlock{ location: Location::new(path, lines.len(), 0), expected: None, found: 0 })?;
+            let location = Location::new(path, line, 0);
+            let (lattice, cell_unit) = cell.ok_or(ParseError::MissingCell{ location: location.clone() })?;
+            // lengths in bohr are scaled by the lattice parameter
+            let scale = |unit: Unit| match unit {
+                Unit::Angstrom | Unit::Crystal => Some(1.0),
+                Unit::Bohr => Some(BOHR),
+                Unit::Alat(Some(alat)) => Some(alat * BOHR),
+                Unit::Alat(None) => alat.map(|alat| alat * BOHR),
+            };
+            let lattice = lattice * scale(cell_unit).ok_or(ParseError::MissingCell{ location: location.clone() })?;
+            let crystal = CrystalInfo::from_lattice_vectors(&lattice);
+            let inverse = lattice.try_inverse().ok_or(ParseError::MissingCell{ location: location.clone() })?;
+            let standard = crystal.lattice_vectors();
+            let atom_scale = scale(unit).ok_or(ParseError::MissingCell{ location })?;
+            let atoms = atoms.into_iter().map(|(label, position)| {
+                let fractional = match unit {
+                    Unit::Crystal => position,
+                    _ => position * atom_scale * inverse,
+                };
+                let position = fractional * standard;
+                // species labels such as Fe1 are numbered kinds of an element
+                let element = label.trim_end_matches(|c: char| !c.is_ascii_alphabetic()).to_string();
+                Atom{ element, coordinate: Coordinate(position[0], position[1], position[2]), record: None, selective: None }
+            }).collect();
+            Ok(StructureBlock{
+                number: 0,
+                energy,
+                symmetry: String::from("C1"),
+                crystal,
+                atoms,
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
+            })
+        }
+    }
+    impl Default for EspressoParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl StructureIO for EspressoParser {
+        // cards span several lines and refer to each other, everything is handled in `parse_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            // only the final geometry is kept, there is nothing to stream
+            let blocks = self.parse_structure(input, ignore_parse_error)?.unwrap_or_default();
+            Ok(StructureIterator::new(blocks.into_iter().map(Ok)))
+        }
+        fn parse_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+            let lines = open_reader(input).map_err(ParseError::IoError)?
+                .lines()
+                .collect::<Result<Vec<String>, io::Error>>()
+                .map_err(ParseError::IoError)?;
+            match self.parse_lines(&lines, Some(input)) {
+                Ok(block) => Ok(Some(vec![block])),
+                Err(_) if ignore_parse_error => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let block = match structures {
+                [block] => block,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("a pw.x input holds exactly one structure, got {}", structures.len()))),
+            };
+            let mut species: Vec<&str> = Vec::new();
+            for atom in block.atoms.iter() {
+                if !species.contains(&atom.element.as_str()) {
+                    species.push(&atom.element);
+                }
+            }
+            let lattice = block.lattice().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("block {} with energy {} has no valid cell", block.number, block.energy)))?;
+            let mut file = CompressedWriter::create(path)?;
+            writeln!(file, "! Energy: {} eV", block.energy)?;
+            writeln!(file, "! ibrav = 0, nat = {}, ntyp = {}", block.atoms.len(), species.len())?;
+            writeln!(file, "ATOMIC_SPECIES")?;
+            for element in species.iter() {
+                let mass = self.table.get(element).map(|e| e.mass)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("unknown element {}", element)))?;
+                writeln!(file, "  {: <3} {: >10.4}  {}.UPF", element, mass, element)?;
+            }
+            writeln!(file, "CELL_PARAMETERS angstrom")?;
+            for row in lattice.matrix().row_iter() {
+                writeln!(file, "  {: >18.10} {: >18.10} {: >18.10}", row[0], row[1], row[2])?;
+            }
+            writeln!(file, "ATOMIC_POSITIONS angstrom")?;
+            for atom in block.atoms.iter() {
+                writeln!(file, "  {: <3} {: >18.10} {: >18.10} {: >18.10}", atom.element, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2)?;
+            }
+            file.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::{NamedTempFile, TempDir};
+
+    use crate::qe::espresso::*;
+    use crate::parser::parser::*;
+    use crate::modules::structures::CrystalInfo;
+
+    #[test]
+    fn test_parse_pw_output() {
+        // a relaxation: the cell of the header and the last positions
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "     Program PWSCF v.7.2 starts on 17Oct2026").unwrap();
+        writeln!(file, "     lattice parameter (alat)  =      10.0000  a.u.").unwrap();
+        writeln!(file, "     crystal axes: (cart. coord. in units of alat)").unwrap();
+        writeln!(file, "               a(1) = (   1.000000   0.000000   0.000000 )  ").unwrap();
+        writeln!(file, "               a(2) = (   0.000000   1.000000   0.000000 )  ").unwrap();
+        writeln!(file, "               a(3) = (   0.000000   0.000000   2.000000 )  ").unwrap();
+        writeln!(file, "     site n.     atom                  positions (alat units)").unwrap();
+        writeln!(file, "         1           O   tau(   1) = (   0.0000000   0.0000000   0.0000000  )").unwrap();
+        writeln!(file, "         2           H   tau(   2) = (   0.1000000   0.0000000   0.0000000  )").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "!    total energy              =     -43.10000000 Ry").unwrap();
+        writeln!(file, "ATOMIC_POSITIONS (angstrom)").unwrap();
+        writeln!(file, "O             0.0000000000        0.0000000000        0.0000000000    0   0   0").unwrap();
+        writeln!(file, "H1            0.9500000000        0.0000000000        0.1000000000").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "!    total energy              =     -44.00000000 Ry").unwrap();
+        writeln!(file, "End final coordinates").unwrap();
+        let block = &EspressoParser::new().parse_structure(file.path(), false).unwrap().unwrap()[0];
+        assert!((block.energy + 44.0 * RYDBERG).abs() < 1e-9);
+        assert!((block.crystal.x - 10.0 * BOHR).abs() < 1e-9);
+        assert!((block.crystal.z - 20.0 * BOHR).abs() < 1e-9);
+        assert_eq!(block.atoms.len(), 2);
+        assert_eq!(block.atoms[1].element, "H");
+        assert!((block.atoms[1].coordinate.0 - 0.95).abs() < 1e-9);
+        assert_eq!(detect_format(file.path()).unwrap(), "qe");
+    }
+
+    #[test]
+    fn test_parse_pw_output_fail() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "ATOMIC_POSITIONS (alat)").unwrap();
+        writeln!(file, "O  0.0 0.0 0.0").unwrap();
+        let err = EspressoParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::MissingCell{ ref location } if location.line == 1), "Got {:?}", err);
+        writeln!(file, "CELL_PARAMETERS (alat= 10.0)").unwrap();
+        writeln!(file, "  1.0 0.0 0.0").unwrap();
+        writeln!(file, "  0.0 1.x 0.0").unwrap();
+        let err = EspressoParser::new().parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(err, ParseError::BadFloat{ ref location, .. } if location.line == 5 && location.column == 7), "Got {:?}", err);
+    }
+
+    #[test]
+    fn test_pw_input_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "&SYSTEM").unwrap();
+        writeln!(file, "  ibrav = 0, celldm(1) = 10.0, nat = 2, ntyp = 1").unwrap();
+        writeln!(file, "/").unwrap();
+        writeln!(file, "CELL_PARAMETERS alat").unwrap();
+        writeln!(file, "  0.0 1.0 0.0").unwrap();
+        writeln!(file, "  -1.0 0.0 0.0").unwrap();
+        writeln!(file, "  0.0 0.0 1.0").unwrap();
+        writeln!(file, "ATOMIC_POSITIONS crystal").unwrap();
+        writeln!(file, "  Si 0.0 0.0 0.0").unwrap();
+        writeln!(file, "  Si 0.25 0.5 0.5").unwrap();
+        writeln!(file, "K_POINTS gamma").unwrap();
+        let blocks = EspressoParser::new().parse_structure(file.path(), false).unwrap().unwrap();
+        let a = 10.0 * BOHR;
+        // the cell is rotated so that a is along x
+        assert!((blocks[0].atoms[1].coordinate.0 - 0.25 * a).abs() < 1e-9);
+        assert!((blocks[0].atoms[1].coordinate.1 - 0.5 * a).abs() < 1e-9);
+        let path = dir.path().join("relax.pwi");
+        EspressoParser::new().write_structure(&blocks, &path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("ATOMIC_SPECIES\n  Si     28.0860  Si.UPF\nCELL_PARAMETERS angstrom\n"), "{}", content);
+        let written = &EspressoParser::new().parse_structure(&path, false).unwrap().unwrap()[0];
+        assert!((written.crystal.x - a).abs() < 1e-9);
+        assert!((written.atoms[1].coordinate.2 - 0.5 * a).abs() < 1e-9);
+        assert!(EspressoParser::new().write_structure(&[blocks[0].clone(), blocks[0].clone()], &path).is_err());
+        // neither does a cluster without a cell
+        let mut molecule = blocks[0].clone();
+        molecule.periodic = false;
+        molecule.crystal = CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 };
+        let err = EspressoParser::new().write_structure(&[molecule], &path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
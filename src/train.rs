@@ -0,0 +1,459 @@
+pub mod train_set {
+    //! reader and writer of the LASP training set files TrainStr.txt and TrainFor.txt
+    //!
+    //! every structure of TrainStr.txt is a compound
+    //! ```text
+    //!  Start Compound:          1
+    //!  Energy          1       0.0000    -296.8316750000
+    //!  lat      12.0000000000    0.0000000000    0.0000000000
+    //!  lat       0.0000000000   12.0000000000    0.0000000000
+    //!  lat       0.0000000000    0.0000000000   12.0000000000
+    //!  ele    8    5.0000000000    5.0000000000    5.0000000000    0.0000000000
+    //!  End Compound
+    //! ```
+    //! with the energy as the last number of the `Energy` line, the lattice vectors and one line per atom
+    //! holding the atomic number, the Cartesian position and the charge.
+    //! TrainFor.txt holds the same compounds in the same order, with a `stress` line (xx yy zz xy yz zx)
+    //! and one `force <Z> fx fy fz` line per atom
+    use std::io::{self, BufRead, Write};
+    use std::path::{Path, PathBuf};
+    use nalgebra::{Matrix3, RowVector3};
+
+    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::modules::periodic_table::PeriodicTable;
+    use crate::compression::compression::{open_reader, CompressedWriter};
+    use crate::parser::parser::{StructureIO, StructureIterator, ParseError, Location, split_columns, parse_float};
+
+    /// the lines of a compound, from `Start Compound` to `End Compound`
+    struct Compound {
+        /// line number of `Start Compound`, starting from 1
+        line: usize,
+        number: u64,
+        /// the lines inside the compound with their line numbers
+        lines: Vec<(usize, String)>,
+        /// whether `End Compound` was met before the end of the file
+        closed: bool,
+    }
+
+    /**
+    parser for LASP training sets
+
+    a TrainStr.txt is read together with the TrainFor.txt next to it, found by replacing `TrainStr` in the
+    file name, so `TrainStr.txt.gz` is paired with `TrainFor.txt.gz`. The forces and stress are attached to the
+    structures, and written to the TrainFor.txt when every structure has forces.
+    Like POSCAR files, the cell is rotated into the standard orientation of `CrystalInfo`, the forces and stress with it.
+    Atom charges are not kept.
+     */
+    pub struct TrainStrParser {
+        table: PeriodicTable,
+    }
+    impl TrainStrParser {
+        pub fn new() -> Self {
+            Self { table: PeriodicTable::new() }
+        }
+
+        /// the TrainFor.txt of a TrainStr.txt, `None` if the file name does not contain `TrainStr`
+        pub fn forces_path(path: &Path) -> Option<PathBuf> {
+            let name = path.file_name()?.to_str()?;
+            name.contains("TrainStr").then(|| path.with_file_name(name.replacen("TrainStr", "TrainFor", 1)))
+        }
+
+        /// the element of an atom line, given by its atomic number or its symbol
+        fn element(&self, text: &str) -> Option<String> {
+            match text.parse::<u64>() {
+                Ok(number) => self.table.symbol(number).map(|s| s.to_string()),
+                Err(_) => self.table.get(text).map(|_| text.to_string()),
+            }
+        }
+
+        /// parse the structure of a TrainStr.txt compound, with the rotation of its cell into the standard orientation
+        fn parse_compound(&self, compound: &Compound, path: Option<&Path>) -> Result<(StructureBlock, Matrix3<f64>), ParseError> {
+            let mut energy = 0.0;
+            let mut lattice = Vec::with_capacity(3);
+            let mut atoms = Vec::new();
+            for (line_number, line) in compound.lines.iter() {
+                let unexpected = || ParseError::UnexpectedLine{ location: Location::new(path, *line_number, 1), line: line.clone() };
+                let columns = split_columns(line);
+                let Some(((_, keyword), rest)) = columns.split_first() else {
+                    continue;
+                };
+                let floats = |values: &[(usize, &str)]| -> Result<Vec<f64>, ParseError> {
+                    values.iter().map(|(column, text)| parse_float(text, *column).map_err(|e| e.at(path, *line_number))).collect()
+                };
+                match keyword.to_lowercase().as_str() {
+                    "energy" => match rest.last() {
+                        Some(last) => energy = floats(std::slice::from_ref(last))?[0],
+                        None => return Err(unexpected()),
+                    },
+                    "lat" if rest.len() >= 3 && lattice.len() < 3 => {
+                        let row = floats(&rest[..3])?;
+                        lattice.push(RowVector3::new(row[0], row[1], row[2]));
+                    },
+                    "ele" if rest.len() >= 4 => {
+                        let element = self.element(rest[0].1).ok_or_else(unexpected)?;
+                        let position = floats(&rest[1..4])?;
+                        atoms.push(Atom{ element, coordinate: Coordinate(position[0], position[1], position[2]), record: None, selective: None });
+                    },
+                    _ => return Err(unexpected()),
+                }
+            }
+            let location = Location::new(path, compound.line, 0);
+            if !compound.closed {
+                return Err(ParseError::TruncatedBlock{ location, expected: None, found: atoms.len() });
+            }
+            if lattice.len() < 3 {
+                return Err(ParseError::MissingCell{ location });
+            }
+            let lattice = Matrix3::from_rows(&lattice);
+            let rotation = rotation_of(&lattice).ok_or(ParseError::MissingCell{ location })?;
+            for atom in atoms.iter_mut() {
+                atom.coordinate = rotate(&atom.coordinate, &rotation);
+            }
+            let block = StructureBlock{
+                number: compound.number,
+                energy,
+                symmetry: String::from("C1"),
+                crystal: CrystalInfo::from_lattice_vectors(&lattice),
+                atoms,
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
+            };
+            Ok((block, rotation))
+        }
+
+        /// attach the forces and stress of a TrainFor.txt compound to its structure, rotated as the structure was
+        fn attach_compound(&self, block: &mut StructureBlock, rotation: &Matrix3<f64>, compound: &Compound, path: Option<&Path>) -> Result<(), ParseError> {
+            let location = Location::new(path, compound.line, 0);
+            if compound.number != block.number {
+                return Err(ParseError::UnexpectedLine{ location, line: format!("Start Compound: {}", compound.number) });
+            }
+            let mut stress = None;
+            let mut forces = Vec::with_capacity(block.atoms.len());
+            for (line_number, line) in compound.lines.iter() {
+                let unexpected = || ParseError::UnexpectedLine{ location: Location::new(path, *line_number, 1), line: line.clone() };
+                let columns = split_columns(line);
+                let Some(((_, keyword), rest)) = columns.split_first() else {
+                    continue;
+                };
+                let values = match keyword.to_lowercase().as_str() {
+                    "stress" if rest.len() >= 6 => &rest[..6],
+                    // the element column is not checked, the order of the atoms is
+                    "force" if rest.len() >= 4 => &rest[1..4],
+                    _ => return Err(unexpected()),
+                };
+                let values = values.iter()
+                    .map(|(column, text)| parse_float(text, *column).map_err(|e| e.at(path, *line_number)))
+                    .collect::<Result<Vec<f64>, ParseError>>()?;
+                match values.as_slice() {
+                    [xx, yy, zz, xy, yz, zx] => stress = Some([*xx, *yy, *zz, *xy, *yz, *zx]),
+                    [x, y, z] => forces.push(Coordinate(*x, *y, *z)),
+                    _ => return Err(unexpected()),
+                }
+            }
+            if !compound.closed || forces.len() != block.atoms.len() {
+                return Err(ParseError::TruncatedBlock{ location, expected: Some(block.atoms.len()), found: forces.len() });
+            }
+            block.forces = Some(forces.iter().map(|f| rotate(f, rotation)).collect());
+            block.stress = stress.map(|s| rotate_stress(&s, rotation));
+            Ok(())
+        }
+    }
+    impl Default for TrainStrParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// the rotation of a lattice into the standard orientation of `CrystalInfo`, for row vectors
+    fn rotation_of(lattice: &Matrix3<f64>) -> Option<Matrix3<f64>> {
+        let standard = CrystalInfo::from_lattice_vectors(lattice).lattice_vectors();
+        Some(lattice.try_inverse()? * standard)
+    }
+
+    fn rotate(vector: &Coordinate, rotation: &Matrix3<f64>) -> Coordinate {
+        let rotated = RowVector3::new(vector.0, vector.1, vector.2) * rotation;
+        Coordinate(rotated[0], rotated[1], rotated[2])
+    }
+
+    fn rotate_stress(stress: &[f64; 6], rotation: &Matrix3<f64>) -> [f64; 6] {
+        let [xx, yy, zz, xy, yz, zx] = *stress;
+        let tensor = Matrix3::new(xx, xy, zx, xy, yy, yz, zx, yz, zz);
+        let tensor = rotation.transpose() * tensor * rotation;
+        [tensor[(0, 0)], tensor[(1, 1)], tensor[(2, 2)], tensor[(0, 1)], tensor[(1, 2)], tensor[(2, 0)]]
+    }
+
+    /// read the next compound of a file, `None` at the end of the file
+    fn read_compound<I>(lines: &mut I, path: Option<&Path>) -> Option<Result<Compound, ParseError>>
+    where
+        I: Iterator<Item = (usize, io::Result<String>)>,
+    {
+        let mut compound: Option<Compound> = None;
+        for (index, line) in lines {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(ParseError::IoError(e))),
+            };
+            let trimmed = line.trim();
+            match compound.as_mut() {
+                Some(compound) if trimmed.starts_with("End Compound") => {
+                    compound.closed = true;
+                    break;
+                },
+                Some(compound) => compound.lines.push((index + 1, line)),
+                None if trimmed.is_empty() => (),
+                None => {
+                    let number = trimmed.strip_prefix("Start Compound")
+                        .map(|s| s.trim_start_matches(':').trim())
+                        .and_then(|s| s.parse::<u64>().ok());
+                    match number {
+                        Some(number) => compound = Some(Compound{ line: index + 1, number, lines: Vec::new(), closed: false }),
+                        None => return Some(Err(ParseError::UnexpectedLine{ location: Location::new(path, index + 1, 1), line })),
+                    }
+                },
+            }
+        }
+        compound.map(Ok)
+    }
+
+    impl StructureIO for TrainStrParser {
+        // a compound spans several lines of two files, everything is handled in `iter_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            let mut structures = open_reader(input).map_err(ParseError::IoError)?.lines().enumerate();
+            let forces_path = Self::forces_path(input).filter(|p| p.exists());
+            let mut forces = match forces_path.as_deref() {
+                Some(path) => Some(open_reader(path).map_err(ParseError::IoError)?.lines().enumerate()),
+                None => None,
+            };
+            let path = input.to_path_buf();
+            Ok(StructureIterator::new(std::iter::from_fn(move || loop {
+                let compound = read_compound(&mut structures, Some(&path))?;
+                // the compounds of both files are read in step, even when one of them is broken
+                let force_compound = forces.as_mut().map(|lines| read_compound(lines, forces_path.as_deref()));
+                let block = compound.and_then(|compound| {
+                    let (mut block, rotation) = self.parse_compound(&compound, Some(&path))?;
+                    match force_compound {
+                        Some(Some(force_compound)) => self.attach_compound(&mut block, &rotation, &force_compound?, forces_path.as_deref())?,
+                        Some(None) => return Err(ParseError::TruncatedBlock{ location: Location::new(forces_path.as_deref(), 0, 0), expected: Some(block.atoms.len()), found: 0 }),
+                        None => (),
+                    }
+                    Ok(block)
+                });
+                match block {
+                    Err(_) if ignore_parse_error => continue,
+                    block => return Some(block),
+                }
+            })))
+        }
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let with_forces = structures.iter().filter(|b| b.forces.is_some()).count();
+            if with_forces != 0 && with_forces != structures.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("only {} of {} structures have forces, a training set needs all or none", with_forces, structures.len())));
+            }
+            // TrainFor.txt has a stress and a force on every atom of each structure
+            for block in structures.iter().filter(|b| b.forces.is_some()) {
+                if block.stress.is_none() {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("structure {} has forces but no stress", block.number)));
+                }
+                let forces = block.forces.as_ref().map_or(0, Vec::len);
+                if forces != block.atoms.len() {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("structure {} has {} forces for {} atoms", block.number, forces, block.atoms.len())));
+                }
+            }
+            // the forces go to the TrainFor.txt named after the TrainStr.txt
+            let forces_path = match Self::forces_path(path) {
+                Some(forces_path) => Some(forces_path),
+                None if with_forces != 0 => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not named TrainStr*, so the forces have no TrainFor.txt to go to", path.display())));
+                },
+                None => None,
+            };
+            // LASP trains on periodic cells only
+            let lattices = structures.iter().map(|block| {
+                block.periodic_lattice().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("block {} with energy {} has no periodic cell", block.number, block.energy)))
+            }).collect::<io::Result<Vec<_>>>()?;
+            let atomic_number = |element: &str| self.table.get(element)
+                .map(|e| e.atomic_number)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("unknown element {}", element)));
+            // compounds are numbered in order, as LASP does
+            let mut file = CompressedWriter::create(path)?;
+            for (i, (block, lattice)) in structures.iter().zip(lattices.iter()).enumerate() {
+                writeln!(file, " Start Compound: {: >10}", i + 1)?;
+                writeln!(file, " Energy {: >10} {: >12.4} {: >20.10}", i + 1, 0.0, block.energy)?;
+                for row in lattice.matrix().row_iter() {
+                    writeln!(file, " lat {: >18.10} {: >18.10} {: >18.10}", row[0], row[1], row[2])?;
+                }
+                for atom in block.atoms.iter() {
+                    let charge = atom.record.as_ref().map_or(0.0, |r| r.charge);
+                    writeln!(file, " ele {: >4} {: >18.10} {: >18.10} {: >18.10} {: >14.10}", atomic_number(&atom.element)?, atom.coordinate.0, atom.coordinate.1, atom.coordinate.2, charge)?;
+                }
+                writeln!(file, " End Compound")?;
+            }
+            file.finish()?;
+            let forces_path = match forces_path {
+                Some(forces_path) if with_forces != 0 => forces_path,
+                _ => return Ok(()),
+            };
+            let mut file = CompressedWriter::create(&forces_path)?;
+            for (i, block) in structures.iter().enumerate() {
+                // every structure has both, checked above
+                let (Some(stress), Some(forces)) = (block.stress, block.forces.as_ref()) else { continue };
+                writeln!(file, " Start Compound: {: >10}", i + 1)?;
+                writeln!(file, " stress {}", stress.iter().map(|s| format!("{: >18.10}", s)).collect::<Vec<String>>().join(" "))?;
+                for (atom, force) in block.atoms.iter().zip(forces) {
+                    writeln!(file, " force {: >4} {: >18.10} {: >18.10} {: >18.10}", atomic_number(&atom.element)?, force.0, force.1, force.2)?;
+                }
+                writeln!(file, " End Compound")?;
+            }
+            file.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    use crate::parser::parser::*;
+    use crate::train::train_set::*;
+    use crate::modules::structures::CrystalInfo;
+
+    fn write_training_set(dir: &std::path::Path) -> std::path::PathBuf {
+        let path = dir.join("TrainStr.txt");
+        let mut file = std::fs::File::create(&path).unwrap();
+        for (number, energy) in [(1, -296.83), (2, -297.5)] {
+            writeln!(file, " Start Compound:          {}", number).unwrap();
+            writeln!(file, " Energy          {}       0.0000    {}", number, energy).unwrap();
+            writeln!(file, " lat      12.0000000000    0.0000000000    0.0000000000").unwrap();
+            writeln!(file, " lat       0.0000000000   12.0000000000    0.0000000000").unwrap();
+            writeln!(file, " lat       0.0000000000    0.0000000000   10.0000000000").unwrap();
+            writeln!(file, " ele    8    5.0000000000    5.0000000000    5.0000000000    0.0000000000").unwrap();
+            writeln!(file, " ele    1    5.9000000000    5.0000000000    5.0000000000    0.0000000000").unwrap();
+            writeln!(file, " End Compound").unwrap();
+        }
+        let mut file = std::fs::File::create(dir.join("TrainFor.txt")).unwrap();
+        for number in [1, 2] {
+            writeln!(file, " Start Compound:          {}", number).unwrap();
+            writeln!(file, " stress    0.1000    0.2000    0.3000    0.0000    0.0000    0.0000").unwrap();
+            writeln!(file, " force    8    0.5000    0.0000    0.0000").unwrap();
+            writeln!(file, " force    1   -0.5000    0.0000    0.0000").unwrap();
+            writeln!(file, " End Compound").unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_parse_training_set() {
+        let dir = TempDir::new().unwrap();
+        let path = write_training_set(dir.path());
+        assert_eq!(detect_format(&path).unwrap(), "train");
+        let blocks = TrainStrParser::new().parse_structure(&path, false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].number, 2);
+        assert_eq!(blocks[1].energy, -297.5);
+        assert!((blocks[0].crystal.z - 10.0).abs() < 1e-9);
+        assert_eq!(blocks[0].atoms[1].element, "H");
+        assert!((blocks[0].atoms[1].coordinate.0 - 5.9).abs() < 1e-9);
+        assert!((blocks[0].forces.as_ref().unwrap()[1].0 + 0.5).abs() < 1e-9);
+        assert!((blocks[0].stress.unwrap()[2] - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_training_set_rotated_cell() {
+        // a along y, the forces and stress are rotated with the cell
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("TrainStr.txt");
+        std::fs::write(&path, " Start Compound: 1\n Energy 1 0.0 -1.0\n lat 0.0 12.0 0.0\n lat -12.0 0.0 0.0\n lat 0.0 0.0 10.0\n ele 1 0.0 2.0 0.0 0.0\n End Compound\n").unwrap();
+        std::fs::write(dir.path().join("TrainFor.txt"), " Start Compound: 1\n stress 0.1 0.2 0.3 0.0 0.0 0.0\n force 1 0.0 0.5 0.0\n End Compound\n").unwrap();
+        let blocks = TrainStrParser::new().parse_structure(&path, false).unwrap().unwrap();
+        assert!((blocks[0].atoms[0].coordinate.0 - 2.0).abs() < 1e-9);
+        assert!((blocks[0].forces.as_ref().unwrap()[0].0 - 0.5).abs() < 1e-9);
+        let stress = blocks[0].stress.unwrap();
+        assert!((stress[0] - 0.2).abs() < 1e-9 && (stress[1] - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_training_set_fail() {
+        let dir = TempDir::new().unwrap();
+        let path = write_training_set(dir.path());
+        let content = std::fs::read_to_string(&path).unwrap().replacen("5.9000000000", "5.9x00000000", 1);
+        std::fs::write(&path, content).unwrap();
+        let err = TrainStrParser::new().parse_structure(&path, false).unwrap_err();
+        assert!(matches!(err, ParseError::BadFloat{ ref location, .. } if location.line == 7), "Got {:?}", err);
+        // the broken compound is skipped, the second one is still paired with its forces
+        let blocks = TrainStrParser::new().parse_structure(&path, true).unwrap().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].forces.is_some());
+        // a force compound with too few atoms
+        let path = write_training_set(dir.path());
+        let forces = dir.path().join("TrainFor.txt");
+        let content = std::fs::read_to_string(&forces).unwrap().replacen(" force    1   -0.5000    0.0000    0.0000\n", "", 1);
+        std::fs::write(&forces, content).unwrap();
+        let err = TrainStrParser::new().parse_structure(&path, false).unwrap_err();
+        assert!(matches!(err, ParseError::TruncatedBlock{ expected: Some(2), found: 1, .. }), "Got {:?}", err);
+    }
+
+    #[test]
+    fn test_write_training_set() {
+        let dir = TempDir::new().unwrap();
+        let blocks = TrainStrParser::new().parse_structure(&write_training_set(dir.path()), false).unwrap().unwrap();
+        let output = dir.path().join("out").join("TrainStr.txt.gz");
+        std::fs::create_dir(output.parent().unwrap()).unwrap();
+        TrainStrParser::new().write_structure(&blocks, &output).unwrap();
+        assert!(dir.path().join("out").join("TrainFor.txt.gz").exists());
+        let written = TrainStrParser::new().parse_structure(&output, false).unwrap().unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[1].energy, blocks[1].energy);
+        assert_eq!(written[0].atoms[0].element, "O");
+        assert_eq!(written[0].forces, blocks[0].forces);
+        assert_eq!(written[1].stress, blocks[1].stress);
+        // a training set has forces for all structures or none
+        let mut partial = blocks.clone();
+        partial[0].forces = None;
+        assert!(TrainStrParser::new().write_structure(&partial, &output).is_err());
+        // and a stress and a force on every atom of each structure, nothing is made up
+        let mut missing = blocks.clone();
+        missing[1].stress = None;
+        let err = TrainStrParser::new().write_structure(&missing, &output).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let mut missing = blocks.clone();
+        missing[0].forces.as_mut().unwrap().pop();
+        let err = TrainStrParser::new().write_structure(&missing, &output).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        // structures without forces need no stress
+        let blocks_with_forces = blocks.clone();
+        let mut bare = blocks;
+        for block in bare.iter_mut() {
+            block.forces = None;
+            block.stress = None;
+        }
+        let output = dir.path().join("bare").join("TrainStr.txt");
+        std::fs::create_dir(output.parent().unwrap()).unwrap();
+        TrainStrParser::new().write_structure(&bare, &output).unwrap();
+        assert!(!dir.path().join("bare").join("TrainFor.txt").exists());
+        // and may be written under any name, unlike those with forces
+        TrainStrParser::new().write_structure(&bare, &dir.path().join("dataset.txt")).unwrap();
+        let err = TrainStrParser::new().write_structure(&blocks_with_forces, &dir.path().join("forces.txt")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(!dir.path().join("forces.txt").exists());
+        // a cluster has no cell to train on
+        let mut cluster = bare;
+        cluster[0].periodic = false;
+        let err = TrainStrParser::new().write_structure(&cluster, &output).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        cluster[0].periodic = true;
+        cluster[0].crystal = CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 };
+        assert!(TrainStrParser::new().write_structure(&cluster, &output).is_err());
+    }
+}
@@ -0,0 +1,443 @@
+pub mod poscar {
+    //! reader and writer of VASP POSCAR/CONTCAR files
+    use std::path::Path;
+    use std::io::{self, BufRead, Write};
+    use nalgebra::{Matrix3, RowVector3};
+
+    use crate::modules::structures::{Atom, StructureBlock, CrystalInfo, Coordinate};
+    use crate::compression::compression::{open_reader, CompressedWriter};
+    use crate::parser::parser::{StructureIO, StructureIterator, ParseError, Location, split_columns, parse_float};
+
+    /**
+    parser for VASP 5 POSCAR/CONTCAR files
+
+    a POSCAR holds exactly one structure. The energy is kept in the comment line as `Energy: <value>`,
+    the same way `XyzParser` does. Selective dynamics flags are kept in `Atom::selective`, and written back
+    when any atom has them.
+     */
+    pub struct PoscarParser{}
+    impl PoscarParser {
+        pub fn new() -> Self {
+            Self {}
+        }
+
+        /**
+        parse the lines of a POSCAR file into a `StructureBlock`
+
+        `path` is only used to locate parse errors
+         */
+        fn parse_lines(&self, lines: &[String], path: Option<&Path>) -> Result<StructureBlock, ParseError> {
+            let error = |index: usize| match lines.get(index) {
+                Some(line) => ParseError::UnexpectedLine{ location: Location::new(path, index + 1, 1), line: line.clone() },
+                None => ParseError::TruncatedBlock{ location: Location::new(path, 1, 0), expected: None, found: 0 },
+            };
+            let floats = |index: usize| -> Result<Vec<f64>, ParseError> {
+                let line = lines.get(index).ok_or_else(|| error(index))?;
+                split_columns(line).into_iter().map(|(column, text)| parse_float(text, column).map_err(|e| e.at(path, index + 1))).collect()
+            };
+            let comment = lines.first().ok_or_else(|| error(0))?;
+            // scale factor: a single factor, a negative target volume or one factor per Cartesian axis
+            let scale = floats(1)?;
+            let mut lattice = Matrix3::<f64>::zeros();
+            for i in 0..3 {
+                let row = floats(2 + i)?;
+                if row.len() < 3 {
+                    return Err(error(2 + i));
+                }
+                lattice.set_row(i, &RowVector3::new(row[0], row[1], row[2]));
+            }
+            let scale = match scale.as_slice() {
+                [s] if *s < 0.0 => {
+                    let s = (-s / lattice.determinant().abs()).cbrt();
+                    RowVector3::new(s, s, s)
+                },
+                [s] => RowVector3::new(*s, *s, *s),
+                [sx, sy, sz] => RowVector3::new(*sx, *sy, *sz),
+                _ => return Err(error(1)),
+            };
+            for i in 0..3 {
+                let row = lattice.row(i).component_mul(&scale);
+                lattice.set_row(i, &row);
+            }
+            // VASP 5 has an element line before the counts, VASP 4 keeps the elements in the comment line
+            let mut index = 5;
+            let first = lines.get(index).ok_or_else(|| error(index))?;
+            let elements: Vec<String> = if first.split_whitespace().all(|s| s.parse::<usize>().is_ok()) {
+                comment.split_whitespace().map(|s| s.to_string()).collect()
+            } else {
+                index += 1;
+                first.split_whitespace().map(|s| s.to_string()).collect()
+            };
+            let counts = lines.get(index).ok_or_else(|| error(index))?
+                .split_whitespace()
+                .map(|s| s.parse::<usize>().map_err(|_| error(index)))
+                .collect::<Result<Vec<usize>, ParseError>>()?;
+            if counts.is_empty() || elements.len() < counts.len() {
+                return Err(error(index));
+            }
+            index += 1;
+            // optional selective dynamics line
+            let selective = lines.get(index).is_some_and(|l| l.trim_start().starts_with(['s', 'S']));
+            if selective {
+                index += 1;
+            }
+            let cartesian = match lines.get(index).and_then(|l| l.trim_start().chars().next()) {
+                Some('c' | 'C' | 'k' | 'K') => true,
+                Some(_) => false,
+                None => return Err(error(index)),
+            };
+            index += 1;
+            // convert the positions into the standard orientation of the cell
+            let crystal = CrystalInfo::from_lattice_vectors(&lattice);
+            let inverse = lattice.try_inverse().ok_or_else(|| error(2))?;
+            let standard = crystal.lattice_vectors();
+            let total = counts.iter().sum();
+            let mut atoms = Vec::with_capacity(total);
+            for (element, count) in elements.iter().zip(counts.iter()) {
+                for _ in 0..*count {
+                    let line = lines.get(index).ok_or(ParseError::TruncatedBlock{ location: Location::new(path, 1, 0), expected: Some(total), found: atoms.len() })?;
+                    let position = floats_prefix(line).ok_or_else(|| error(index))?.map_err(|e| e.at(path, index + 1))?;
+                    let fractional = if cartesian {
+                        position.component_mul(&scale) * inverse
+                    } else {
+                        position
+                    };
+                    let position = fractional * standard;
+                    let flags = if selective {
+                        Some(selective_flags(line).ok_or_else(|| error(index))?)
+                    } else {
+                        None
+                    };
+                    atoms.push(Atom{
+                        element: element.clone(),
+                        coordinate: Coordinate(position[0], position[1], position[2]),
+                        record: None,
+                        selective: flags
+                    });
+                    index += 1;
+                }
+            }
+            let energy = comment.split("Energy:").nth(1)
+                .and_then(|s| s.split_whitespace().next())
+                .and_then(|s| s.parse::<f64>().ok())
+                .unwrap_or(0.0);
+            Ok(StructureBlock{
+                number: 0,
+                energy,
+                symmetry: String::from("C1"),
+                crystal,
+                atoms,
+                periodic: true,
+                forces: None,
+                record: None,
+                stress: None
+            })
+        }
+    }
+    impl Default for PoscarParser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// the first three floats of a position line, ignoring selective dynamics flags and labels.
+    /// Returns `None` if the line has less than three columns
+    fn floats_prefix(line: &str) -> Option<Result<RowVector3<f64>, ParseError>> {
+        let columns = split_columns(line);
+        if columns.len() < 3 {
+            return None;
+        }
+        let mut position = RowVector3::zeros();
+        for (i, (column, text)) in columns.into_iter().take(3).enumerate() {
+            match parse_float(text, column) {
+                Ok(value) => position[i] = value,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Some(Ok(position))
+    }
+
+    /// the selective dynamics flags after the position, `T` if the coordinate may move.
+    /// Returns `None` if there are not three of them
+    fn selective_flags(line: &str) -> Option<[bool; 3]> {
+        let mut flags = [false; 3];
+        let mut columns = line.split_whitespace().skip(3);
+        for flag in flags.iter_mut() {
+            *flag = match columns.next()? {
+                "T" | "t" => true,
+                "F" | "f" => false,
+                _ => return None,
+            };
+        }
+        Some(flags)
+    }
+
+    impl StructureIO for PoscarParser{
+        // a POSCAR is not line oriented, everything is handled in `parse_structure`
+        fn parse_atom(&self, _input: &str) -> Result<Option<Atom>, ParseError> {
+            Ok(None)
+        }
+        fn parse_cell(&self, _intput: &str) -> Result<Option<CrystalInfo>, ParseError> {
+            Ok(None)
+        }
+        fn parse_header(&self, _input: &str) -> Result<Option<StructureBlock>, ParseError> {
+            Ok(None)
+        }
+        fn iter_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<StructureIterator<'_>, ParseError> {
+            // a POSCAR holds a single structure, there is nothing to stream
+            let blocks = self.parse_structure(input, ignore_parse_error)?.unwrap_or_default();
+            Ok(StructureIterator::new(blocks.into_iter().map(Ok)))
+        }
+        fn parse_structure(&self, input: &Path, ignore_parse_error: bool) -> Result<Option<Vec<StructureBlock>>, ParseError> {
+            let lines = open_reader(input).map_err(ParseError::IoError)?
+                .lines()
+                .collect::<Result<Vec<String>, io::Error>>()
+                .map_err(ParseError::IoError)?;
+            match self.parse_lines(&lines, Some(input)) {
+                Ok(block) => Ok(Some(vec![block])),
+                Err(_) if ignore_parse_error => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        fn write_structure(&self, structures: &[StructureBlock], path: &Path) -> io::Result<()> {
+            let block = match structures {
+                [block] => block,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("a POSCAR holds exactly one structure, got {}", structures.len()))),
+            };
+            let lattice = block.crystal.lattice_vectors();
+            // clusters have no cell, or one of zero angles
+            let volume = lattice.determinant().abs();
+            if volume.is_nan() || volume < 1e-8 {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("block {} with energy {} has no valid cell", block.number, block.energy)));
+            }
+            let mut file = CompressedWriter::create(path)?;
+            writeln!(file, "Energy: {}", block.energy)?;
+            writeln!(file, "{: >19.14}", 1.0)?;
+            for row in lattice.row_iter() {
+                writeln!(file, " {: >21.16} {: >21.16} {: >21.16}", row[0], row[1], row[2])?;
+            }
+            // group consecutive atoms of the same element, so the atom order is kept
+            let mut groups: Vec<(&str, usize)> = Vec::new();
+            for atom in block.atoms.iter() {
+                match groups.last_mut() {
+                    Some((element, count)) if *element == atom.element => *count += 1,
+                    _ => groups.push((&atom.element, 1)),
+                }
+            }
+            writeln!(file, "{}", groups.iter().map(|(element, _)| format!("{: >5}", element)).collect::<String>())?;
+            writeln!(file, "{}", groups.iter().map(|(_, count)| format!("{: >6}", count)).collect::<String>())?;
+            // atoms without flags among constrained ones are free to move
+            let selective = block.atoms.iter().any(|atom| atom.selective.is_some());
+            if selective {
+                writeln!(file, "Selective dynamics")?;
+            }
+            writeln!(file, "Cartesian")?;
+            for atom in block.atoms.iter() {
+                write!(file, " {: >19.16} {: >19.16} {: >19.16}", atom.coordinate.0, atom.coordinate.1, atom.coordinate.2)?;
+                if selective {
+                    for flag in atom.selective.unwrap_or([true; 3]) {
+                        write!(file, " {}", if flag { "T" } else { "F" })?;
+                    }
+                }
+                writeln!(file)?;
+            }
+            file.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use tempfile::NamedTempFile;
+    use std::io::Write;
+
+    use crate::parser::parser::*;
+    use crate::vasp::poscar::*;
+    use crate::modules::structures::*;
+
+    #[test]
+    fn test_parse_poscar_direct() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "Fe O").unwrap();
+        writeln!(file, "   2.0").unwrap();
+        writeln!(file, "     2.0    0.0    0.0").unwrap();
+        writeln!(file, "     0.0    2.0    0.0").unwrap();
+        writeln!(file, "     0.0    0.0    3.0").unwrap();
+        writeln!(file, "   Fe   O").unwrap();
+        writeln!(file, "    1   2").unwrap();
+        writeln!(file, "Direct").unwrap();
+        writeln!(file, "  0.0  0.0  0.0").unwrap();
+        writeln!(file, "  0.5  0.5  0.5").unwrap();
+        writeln!(file, "  0.25  0.5  0.0").unwrap();
+        let parser = PoscarParser::new();
+        let blocks = parser.parse_structure(file.path(), false).unwrap().unwrap();
+        assert_eq!(blocks.len(), 1);
+        let block = &blocks[0];
+        assert!((block.crystal.x - 4.0).abs() < 1e-9);
+        assert!((block.crystal.y - 4.0).abs() < 1e-9);
+        assert!((block.crystal.z - 6.0).abs() < 1e-9);
+        assert!((block.crystal.gamma - 90.0).abs() < 1e-9);
+        assert_eq!(block.atoms.len(), 3);
+        assert_eq!(block.atoms[0].element, "Fe");
+        assert_eq!(block.atoms[1].element, "O");
+        assert_eq!(block.atoms[2].element, "O");
+        assert!((block.atoms[1].coordinate.0 - 2.0).abs() < 1e-9);
+        assert!((block.atoms[1].coordinate.2 - 3.0).abs() < 1e-9);
+        assert!((block.atoms[2].coordinate.0 - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_poscar_cartesian_selective_dynamics() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "slab").unwrap();
+        writeln!(file, "-64.0").unwrap();
+        writeln!(file, "  1.0 0.0 0.0").unwrap();
+        writeln!(file, "  0.0 1.0 0.0").unwrap();
+        writeln!(file, "  0.0 0.0 1.0").unwrap();
+        writeln!(file, "Pt").unwrap();
+        writeln!(file, "2").unwrap();
+        writeln!(file, "Selective dynamics").unwrap();
+        writeln!(file, "Cartesian").unwrap();
+        writeln!(file, "  0.0 0.0 0.0 F F F").unwrap();
+        writeln!(file, "  0.5 0.5 0.5 T T F").unwrap();
+        let parser = PoscarParser::new();
+        let blocks = parser.parse_structure(file.path(), false).unwrap().unwrap();
+        let block = &blocks[0];
+        // the negative scale factor is the volume of the cell
+        assert!((block.crystal.x - 4.0).abs() < 1e-9);
+        assert_eq!(block.atoms.len(), 2);
+        assert!((block.atoms[1].coordinate.0 - 2.0).abs() < 1e-9);
+        assert!((block.atoms[1].coordinate.1 - 2.0).abs() < 1e-9);
+        assert!((block.atoms[1].coordinate.2 - 2.0).abs() < 1e-9);
+        assert_eq!(block.atoms[0].selective, Some([false, false, false]));
+        assert_eq!(block.atoms[1].selective, Some([true, true, false]));
+
+        // the flags are written back
+        let written = NamedTempFile::new().unwrap();
+        parser.write_structure(&blocks, written.path()).unwrap();
+        let content = std::fs::read_to_string(written.path()).unwrap();
+        assert!(content.contains("Selective dynamics\nCartesian\n"), "{}", content);
+        let read_block = &parser.parse_structure(written.path(), false).unwrap().unwrap()[0];
+        assert_eq!(read_block.atoms.iter().map(|a| a.selective).collect::<Vec<_>>(), vec![Some([false, false, false]), Some([true, true, false])]);
+
+        // an atom line without its flags
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "slab\n1.0\n  4.0 0.0 0.0\n  0.0 4.0 0.0\n  0.0 0.0 4.0\nPt\n1\nSelective dynamics\nDirect\n  0.0 0.0 0.0").unwrap();
+        let err = parser.parse_structure(file.path(), false).unwrap_err();
+        assert!(matches!(&err, ParseError::UnexpectedLine{ location, .. } if location.line == 10), "Got {:?}", err);
+    }
+
+    #[test]
+    fn test_parse_poscar_rotated_cell() {
+        // a hexagonal cell with b along y, which is rotated into the standard orientation
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "hexagonal").unwrap();
+        writeln!(file, "1.0").unwrap();
+        writeln!(file, "  0.0 3.0 0.0").unwrap();
+        writeln!(file, "  -2.598076211353316 -1.5 0.0").unwrap();
+        writeln!(file, "  0.0 0.0 5.0").unwrap();
+        writeln!(file, "C").unwrap();
+        writeln!(file, "1").unwrap();
+        writeln!(file, "Direct").unwrap();
+        writeln!(file, "  0.5 0.0 0.5").unwrap();
+        let parser = PoscarParser::new();
+        let block = &parser.parse_structure(file.path(), false).unwrap().unwrap()[0];
+        assert!((block.crystal.x - 3.0).abs() < 1e-9);
+        assert!((block.crystal.y - 3.0).abs() < 1e-9);
+        assert!((block.crystal.gamma - 120.0).abs() < 1e-9);
+        assert!((block.atoms[0].coordinate.0 - 1.5).abs() < 1e-9);
+        assert!(block.atoms[0].coordinate.1.abs() < 1e-9);
+        assert!((block.atoms[0].coordinate.2 - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_poscar_fail() {
+        let parser = PoscarParser::new();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "some_random_string").unwrap();
+        let path = file.path();
+        let result = parser.parse_structure(path, true);
+        assert!(result.unwrap().is_none());
+        let result = parser.parse_structure(path, false);
+        assert!(matches!(result.unwrap_err(), ParseError::TruncatedBlock{ .. }));
+        let result = parser.parse_structure(Path::new("non_existent_POSCAR"), false);
+        assert!(matches!(result.unwrap_err(), ParseError::IoError(_)));
+    }
+
+    #[test]
+    fn test_write_poscar_structure() {
+        let block = StructureBlock{
+            number: 0,
+            energy: -3620.67936,
+            symmetry: "C1".to_string(),
+            crystal: CrystalInfo{
+                x: 5.0,
+                y: 5.0,
+                z: 8.0,
+                alpha: 90.0,
+                beta: 90.0,
+                gamma: 120.0
+            },
+            atoms: vec![
+                Atom{
+                    element: "Fe".to_string(),
+                    coordinate: Coordinate(1.0, 1.0, 1.0),
+                    record: None,
+                    selective: None
+                },
+                Atom{
+                    element: "O".to_string(),
+                    coordinate: Coordinate(2.0, 1.5, 3.0),
+                    record: None,
+                    selective: None
+                },
+                Atom{
+                    element: "Fe".to_string(),
+                    coordinate: Coordinate(0.5, 2.5, 4.0),
+                    record: None,
+                    selective: None
+                }
+            ],
+            periodic: true,
+            forces: None,
+            record: None,
+            stress: None
+        };
+        let parser = PoscarParser::new();
+        let file = NamedTempFile::new().unwrap();
+        parser.write_structure(std::slice::from_ref(&block), file.path()).unwrap();
+        let read_block = &parser.parse_structure(file.path(), false).unwrap().unwrap()[0];
+        assert_eq!(read_block.energy, -3620.67936);
+        assert!((read_block.crystal.x - 5.0).abs() < 1e-9);
+        assert!((read_block.crystal.z - 8.0).abs() < 1e-9);
+        assert!((read_block.crystal.gamma - 120.0).abs() < 1e-9);
+        let elements: Vec<&str> = read_block.atoms.iter().map(|a| a.element.as_str()).collect();
+        assert_eq!(elements, vec!["Fe", "O", "Fe"]);
+        for (atom, read_atom) in block.atoms.iter().zip(read_block.atoms.iter()) {
+            assert!((atom.coordinate.0 - read_atom.coordinate.0).abs() < 1e-9);
+            assert!((atom.coordinate.1 - read_atom.coordinate.1).abs() < 1e-9);
+            assert!((atom.coordinate.2 - read_atom.coordinate.2).abs() < 1e-9);
+        }
+        assert!(read_block.atoms.iter().all(|a| a.selective.is_none()));
+        assert!(!std::fs::read_to_string(file.path()).unwrap().contains("Selective"));
+        // more than one structure does not fit in a POSCAR
+        let result = parser.write_structure(&[block.clone(), block.clone()], file.path());
+        assert!(result.is_err());
+        // neither does a block without a cell
+        let mut cluster = block;
+        cluster.crystal = CrystalInfo{ x: 0.0, y: 0.0, z: 0.0, alpha: 0.0, beta: 0.0, gamma: 0.0 };
+        cluster.periodic = false;
+        let err = parser.write_structure(&[cluster], file.path()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_poscar_file_type() {
+        assert_eq!(get_file_type(Path::new("run/CONTCAR")), Some("poscar"));
+        assert_eq!(get_file_type(Path::new("POSCAR_relaxed")), Some("poscar"));
+        assert_eq!(get_file_type(Path::new("minimum.vasp")), Some("vasp"));
+        assert_eq!(get_file_type(Path::new("all.arc")), Some("arc"));
+        assert_eq!(get_file_type(Path::new("README")), None);
+    }
+}